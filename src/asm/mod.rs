@@ -1,6 +1,9 @@
 pub mod lower;
 pub mod register;
 
+/// The names of all available targets.
+pub const TARGETS: &[&str] = &["risc-v"];
+
 /// Contains lowering logic for all available targets.
 pub mod targets {
     /// Contains code for lowering IR to RISC-V.
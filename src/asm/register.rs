@@ -10,6 +10,14 @@ pub struct RegMgr<const N: usize> {
 }
 
 impl<const N: usize> RegMgr<N> {
+    /// Create a manager with every register free. Registers are handed out lowest index first.
+    pub fn new() -> Self {
+        RegMgr {
+            registers: [true; N],
+            free: (0..N).rev().collect(),
+        }
+    }
+
     /// Given its index, checks whether a register is currently in use or not.
     pub fn is_free(&self, index: usize) -> bool {
         self.registers[index]
@@ -1,16 +1,17 @@
 #![allow(dead_code)]
-#![allow(unused_variables)]
 
-use std::fs::File;
-use std::io;
+use std::io::{self, Write};
 
 use crate::asm::lower::Lower;
 use crate::asm::register::RegMgr;
-use crate::ir::{BinInstr, Instr};
+use crate::ir::{Addr, BinInstr, Const, IRRoot, Instr, Op};
 use crate::shared::{Index, Map};
 
 type Integer = isize;
 
+/// The label every checked instruction branches to when it overflows.
+const OVERFLOW: &str = "overflow";
+
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub enum Container {
     Register(Index),
@@ -18,12 +19,12 @@ pub enum Container {
 }
 
 #[allow(non_camel_case_types)]
-pub struct RISC_V<'a> {
-    /// The file we are writing to.
-    file: File,
+pub struct RISC_V<'a, W: Write> {
+    /// The stream we are writing to.
+    out: W,
 
-    /// List of IR instructions to be lowered.
-    instrs: &'a [Instr],
+    /// The IR program to be lowered, along with its constant pools.
+    ir: &'a IRRoot<'a>,
 
     /// Manages temporary registers.
     temps: RegMgr<6>,
@@ -42,29 +43,229 @@ pub struct RISC_V<'a> {
 
     /// Current stack pointer offset
     offset: Integer,
+
+    /// Whether any instruction branches to the overflow path, which is then emitted once after the program.
+    overflow: bool,
 }
 
-impl<'a> Lower for RISC_V<'a> {
+impl<W: Write> Lower for RISC_V<'_, W> {
     fn lower(&mut self) -> io::Result<()> {
-        for instr in self.instrs {
+        for instr in &self.ir.instrs {
             self.lower_instr(instr)?;
         }
 
+        if self.overflow {
+            writeln!(self.out, "{}:", OVERFLOW)?;
+            writeln!(self.out, "    unimp")?;
+        }
+
         Ok(())
     }
 }
 
-impl<'a> RISC_V<'a> {
+impl<'a, W: Write> RISC_V<'a, W> {
+    /// Create a lowering of the given program that writes assembly to `out`.
+    pub fn new(out: W, ir: &'a IRRoot<'a>) -> Self {
+        RISC_V {
+            out,
+            ir,
+            temps: RegMgr::new(),
+            arguments: RegMgr::new(),
+            saved: RegMgr::new(),
+            temp_map: Map::new(),
+            name_map: Map::new(),
+            offset: 0,
+            overflow: false,
+        }
+    }
+
     fn lower_instr(&mut self, instr: &Instr) -> io::Result<()> {
         match instr {
-            Instr::Binary(bin_instr) => self.lower_bin_instr(bin_instr)?,
-            _ => todo!(),
+            Instr::Binary(bin_instr) => self.lower_bin_instr(bin_instr),
+            _ => Err(io::Error::other(
+                "Only binary instructions can be lowered to RISC-V",
+            )),
+        }
+    }
+
+    fn lower_bin_instr(&mut self, bin_instr: &BinInstr) -> io::Result<()> {
+        let (rs1, l_scratch) = self.source(&bin_instr.la)?;
+        let (rs2, r_scratch) = self.source(&bin_instr.ra)?;
+        let rd = self.destination(&bin_instr.da)?;
+
+        match bin_instr.op {
+            Op::Plus => self.emit("add", &[&rd, &rs1, &rs2])?,
+            Op::Minus => self.emit("sub", &[&rd, &rs1, &rs2])?,
+            Op::Mult => self.emit("mul", &[&rd, &rs1, &rs2])?,
+            Op::Div => self.emit("div", &[&rd, &rs1, &rs2])?,
+            Op::Rem => self.emit("rem", &[&rd, &rs1, &rs2])?,
+            Op::Lt => self.emit("slt", &[&rd, &rs1, &rs2])?,
+            Op::Gt => self.emit("slt", &[&rd, &rs2, &rs1])?,
+            Op::Le => {
+                self.emit("slt", &[&rd, &rs2, &rs1])?;
+                self.emit("xori", &[&rd, &rd, "1"])?;
+            }
+            Op::Ge => {
+                self.emit("slt", &[&rd, &rs1, &rs2])?;
+                self.emit("xori", &[&rd, &rd, "1"])?;
+            }
+            Op::Eq => {
+                self.emit("sub", &[&rd, &rs1, &rs2])?;
+                self.emit("seqz", &[&rd, &rd])?;
+            }
+            Op::Ne => {
+                self.emit("sub", &[&rd, &rs1, &rs2])?;
+                self.emit("snez", &[&rd, &rd])?;
+            }
+            Op::And => self.emit("and", &[&rd, &rs1, &rs2])?,
+            Op::Or => self.emit("or", &[&rd, &rs1, &rs2])?,
+
+            // `mul` only keeps the lower 32 bits of the product, which overflowed if the upper 32 bits (from `mulh`)
+            // aren't just the sign of the lower ones repeated
+            Op::CheckedMult => {
+                let high = self.scratch()?;
+                let sign = self.scratch()?;
+
+                self.emit("mulh", &[&high.1, &rs1, &rs2])?;
+                self.emit("mul", &[&rd, &rs1, &rs2])?;
+                self.emit("srai", &[&sign.1, &rd, "31"])?;
+                self.emit("bne", &[&high.1, &sign.1, OVERFLOW])?;
+
+                self.temps.set_free(high.0);
+                self.temps.set_free(sign.0);
+                self.overflow = true;
+            }
+
+            Op::Neg => unreachable!("negation is a unary operator"),
+        }
+
+        for index in [l_scratch, r_scratch].into_iter().flatten() {
+            self.temps.set_free(index);
         }
 
         Ok(())
     }
 
-    fn lower_bin_instr(&mut self, bin_instr: &BinInstr) -> io::Result<()> {
-        todo!()
+    /// Write one instruction with the given operands.
+    fn emit(&mut self, mnemonic: &str, operands: &[&str]) -> io::Result<()> {
+        writeln!(self.out, "    {} {}", mnemonic, operands.join(", "))
+    }
+
+    /// Return the register holding the value of an operand. Constants are first loaded into a scratch register,
+    /// whose index is returned as well so it can be freed once the instruction has been lowered.
+    fn source(&mut self, addr: &Addr) -> io::Result<(String, Option<Index>)> {
+        match addr {
+            Addr::Const(Const::Int(index)) => {
+                let value = self.ir.interner.integers.value_of(*index).copied();
+                let value = value.ok_or_else(|| io::Error::other("Unknown integer constant"))?;
+
+                let (index, register) = self.scratch()?;
+                self.emit("li", &[&register, &value.to_string()])?;
+
+                Ok((register, Some(index)))
+            }
+            Addr::Temp(index) => match self.temp_map.from(index) {
+                Some(Container::Register(register)) => Ok((format!("t{}", register), None)),
+                _ => Err(io::Error::other(format!(
+                    "Temporary t{} is used before it is set",
+                    index
+                ))),
+            },
+            Addr::Name(_) => Ok((self.destination(addr)?, None)),
+        }
+    }
+
+    /// Return the register an instruction should write its result to, claiming one the first time an address is set.
+    /// Temporaries live in `t` registers and names in saved `s` registers (starting from `s1`, since `s0` is the frame
+    /// pointer).
+    fn destination(&mut self, addr: &Addr) -> io::Result<String> {
+        match addr {
+            Addr::Temp(index) => {
+                let register = match self.temp_map.from(index) {
+                    Some(Container::Register(register)) => *register,
+                    _ => {
+                        let register = self.temps.get_free().ok_or_else(Self::out_of_registers)?;
+                        self.temp_map.insert(*index, Container::Register(register));
+                        register
+                    }
+                };
+
+                Ok(format!("t{}", register))
+            }
+            Addr::Name(index) => {
+                let register = match self.name_map.from(index) {
+                    Some(Container::Register(register)) => *register,
+                    _ => {
+                        let register = self.saved.get_free().ok_or_else(Self::out_of_registers)?;
+                        self.name_map.insert(*index, Container::Register(register));
+                        register
+                    }
+                };
+
+                Ok(format!("s{}", register + 1))
+            }
+            Addr::Const(_) => Err(io::Error::other(
+                "A constant can't be the destination of an instruction",
+            )),
+        }
+    }
+
+    /// Claim a temporary register for intermediate values, returning its index and name.
+    fn scratch(&mut self) -> io::Result<(Index, String)> {
+        let index = self.temps.get_free().ok_or_else(Self::out_of_registers)?;
+        Ok((index, format!("t{}", index)))
+    }
+
+    fn out_of_registers() -> io::Error {
+        io::Error::other("Ran out of registers while lowering to RISC-V")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::LoweringPool;
+
+    /// Lower `t0 = a <op> b` for two integer constants and return the assembly.
+    fn asm(a: i32, op: Op, b: i32) -> String {
+        let mut interner = LoweringPool::new();
+        let la = Addr::Const(Const::Int(interner.integers.insert(a)));
+        let ra = Addr::Const(Const::Int(interner.integers.insert(b)));
+
+        let ir = IRRoot {
+            last_label: 0,
+            interner,
+            instrs: vec![Instr::Binary(BinInstr::new(Addr::Temp(0), la, op, ra))],
+        };
+
+        let mut out = Vec::new();
+        RISC_V::new(&mut out, &ir).lower().unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain_multiplication_keeps_the_lower_half() {
+        assert_eq!(
+            asm(6, Op::Mult, 7),
+            "    li t0, 6\n    li t1, 7\n    mul t2, t0, t1\n"
+        );
+    }
+
+    #[test]
+    fn checked_multiplication_branches_on_overflow() {
+        assert_eq!(
+            asm(65536, Op::CheckedMult, 65536),
+            concat!(
+                "    li t0, 65536\n",
+                "    li t1, 65536\n",
+                "    mulh t3, t0, t1\n",
+                "    mul t2, t0, t1\n",
+                "    srai t4, t2, 31\n",
+                "    bne t3, t4, overflow\n",
+                "overflow:\n",
+                "    unimp\n",
+            )
+        );
     }
 }
@@ -0,0 +1,66 @@
+use super::{
+    Expr, ExprBin, ExprBlock, ExprCall, ExprCmp, ExprField, ExprIf, ExprLit, ExprMatch,
+    ExprMethodCall, ExprRef, ExprStruct, ExprTuple, ExprTupleIndex, ExprUnary, Ident,
+};
+
+/// Unlike `Visit`, which leaves passes to accumulate their state in `self`, this trait lets a pass return a value
+/// from every expression it visits. Every kind of expression must be handled, since there's no sensible default
+/// `Output` to return. Only `fold_expr` has a default, which dispatches to the other methods.
+pub trait Fold<'a>: Sized {
+    /// The value produced for every expression.
+    type Output;
+
+    fn fold_expr(&mut self, expr: &'a Expr) -> Self::Output {
+        fold_expr(self, expr)
+    }
+
+    fn fold_expr_bin(&mut self, expr_bin: &'a ExprBin) -> Self::Output;
+
+    fn fold_expr_call(&mut self, expr_call: &'a ExprCall) -> Self::Output;
+
+    fn fold_expr_lit(&mut self, expr_lit: &'a ExprLit) -> Self::Output;
+
+    fn fold_expr_struct(&mut self, expr_struct: &'a ExprStruct) -> Self::Output;
+
+    fn fold_ident(&mut self, ident: &'a Ident) -> Self::Output;
+
+    fn fold_expr_field(&mut self, expr_field: &'a ExprField) -> Self::Output;
+
+    fn fold_expr_method_call(&mut self, expr_method_call: &'a ExprMethodCall) -> Self::Output;
+
+    fn fold_expr_tuple(&mut self, expr_tuple: &'a ExprTuple) -> Self::Output;
+
+    fn fold_expr_tuple_index(&mut self, expr_tuple_index: &'a ExprTupleIndex) -> Self::Output;
+
+    fn fold_expr_ref(&mut self, expr_ref: &'a ExprRef) -> Self::Output;
+
+    fn fold_expr_unary(&mut self, expr_unary: &'a ExprUnary) -> Self::Output;
+
+    fn fold_expr_block(&mut self, expr_block: &'a ExprBlock) -> Self::Output;
+
+    fn fold_expr_if(&mut self, expr_if: &'a ExprIf) -> Self::Output;
+
+    fn fold_expr_cmp(&mut self, expr_cmp: &'a ExprCmp) -> Self::Output;
+
+    fn fold_expr_match(&mut self, expr_match: &'a ExprMatch) -> Self::Output;
+}
+
+pub fn fold_expr<'a, F: Fold<'a>>(folder: &mut F, expr: &'a Expr) -> F::Output {
+    match expr {
+        Expr::Binary(expr_bin) => folder.fold_expr_bin(expr_bin),
+        Expr::Call(expr_call) => folder.fold_expr_call(expr_call),
+        Expr::Lit(expr_lit) => folder.fold_expr_lit(expr_lit),
+        Expr::Ident(ident) => folder.fold_ident(ident),
+        Expr::Struct(expr_struct) => folder.fold_expr_struct(expr_struct),
+        Expr::Field(expr_field) => folder.fold_expr_field(expr_field),
+        Expr::MethodCall(expr_method_call) => folder.fold_expr_method_call(expr_method_call),
+        Expr::Tuple(expr_tuple) => folder.fold_expr_tuple(expr_tuple),
+        Expr::TupleIndex(expr_tuple_index) => folder.fold_expr_tuple_index(expr_tuple_index),
+        Expr::Ref(expr_ref) => folder.fold_expr_ref(expr_ref),
+        Expr::Unary(expr_unary) => folder.fold_expr_unary(expr_unary),
+        Expr::Block(expr_block) => folder.fold_expr_block(expr_block),
+        Expr::If(expr_if) => folder.fold_expr_if(expr_if),
+        Expr::Cmp(expr_cmp) => folder.fold_expr_cmp(expr_cmp),
+        Expr::Match(expr_match) => folder.fold_expr_match(expr_match),
+    }
+}
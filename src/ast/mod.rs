@@ -1,6 +1,14 @@
 #![allow(dead_code)]
+pub mod fold;
+pub mod render;
 pub mod visitor;
-use crate::{lexer::Token, shared::Span};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::{
+    lexer::Token,
+    shared::{Name, Span},
+};
 
 #[derive(Debug)]
 pub struct File {
@@ -8,16 +16,41 @@ pub struct File {
     pub span: Span,
 }
 
+impl File {
+    /// Merge several files into one, keeping their items in order. The files share a single global namespace.
+    pub fn merge(files: Vec<File>) -> File {
+        let span = match (files.first(), files.last()) {
+            (Some(first), Some(last)) => Span::new(first.span.start.clone(), last.span.end.clone()),
+            _ => Span::single(1, 1),
+        };
+
+        File {
+            items: files.into_iter().flat_map(|file| file.items).collect(),
+            span,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Item {
     Fn(ItemFn),
     Struct(ItemStruct),
     Impl(ItemImpl),
+    Const(ItemConst),
 }
 
 /// Represents a function item (declaration).
 #[derive(Debug)]
 pub struct ItemFn {
+    /// The doc comments preceding this function, in declaration order.
+    pub docs: Vec<String>,
+
+    /// The attributes preceding this function (such as `@test`).
+    pub attrs: Vec<Attr>,
+
+    /// Whether this function is public.
+    pub vis: Visibility,
+
     /// The `fn` keyword.
     pub kw: Token,
 
@@ -92,6 +125,12 @@ pub struct Param {
 /// Represents a struct item (declaration).
 #[derive(Debug)]
 pub struct ItemStruct {
+    /// The doc comments preceding this struct, in declaration order.
+    pub docs: Vec<String>,
+
+    /// Whether this struct is public.
+    pub vis: Visibility,
+
     // The `struct` keyword
     pub kw: Token,
 
@@ -101,14 +140,58 @@ pub struct ItemStruct {
     /// The struct fields.
     pub fields: Fields,
 
+    /// The trailing semicolon, which only follows positional fields (`struct Pair(i32, i32);`).
+    pub semi: Option<Token>,
+
     /// The span of the entire struct.
     pub span: Span,
 }
 
+/// Represents a constant item (`const N: i32 = 3;`), whose value is computed at compile time.
+#[derive(Debug)]
+pub struct ItemConst {
+    /// The doc comments preceding this constant, in declaration order.
+    pub docs: Vec<String>,
+
+    /// The `const` keyword.
+    pub kw: Token,
+
+    /// The constant identifier.
+    pub ident: Ident,
+
+    /// The colon following the identifier.
+    pub colon: Token,
+
+    /// The type of this constant.
+    pub ty: Ty,
+
+    /// The `=` symbol.
+    pub eq: Token,
+
+    /// The expression computing the value of this constant.
+    pub expr: Expr,
+
+    /// The trailing semicolon.
+    pub semi: Token,
+
+    /// The span of the entire constant.
+    pub span: Span,
+}
+
 /// Represents either a list of named fields, or a list of positional fields.
 #[derive(Debug)]
 pub enum Fields {
     Named(FieldsNamed),
+    Unnamed(FieldsUnnamed),
+}
+
+impl Fields {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Named(fields_named) => &fields_named.span,
+            Self::Unnamed(fields_unnamed) => &fields_unnamed.span,
+        }
+    }
 }
 
 /// Represents a list of named fields.
@@ -127,9 +210,31 @@ pub struct FieldsNamed {
     pub span: Span,
 }
 
-//// Represents a single named field.
+/// Represents a list of positional fields, which are referred to by their index.
+#[derive(Debug)]
+pub struct FieldsUnnamed {
+    /// The left parenthesis.
+    pub lp: Token,
+
+    /// The type of every field, in order.
+    pub fields: Vec<Ty>,
+
+    /// The right parenthesis.
+    pub rp: Token,
+
+    /// The span of the entire list of fields.
+    pub span: Span,
+}
+
+/// Represents a single named field.
 #[derive(Debug)]
 pub struct FieldNamed {
+    /// The doc comments preceding this field, in declaration order.
+    pub docs: Vec<String>,
+
+    /// Whether this field is public.
+    pub vis: Visibility,
+
     /// The name of this field.
     pub ident: Ident,
 
@@ -146,6 +251,9 @@ pub struct FieldNamed {
 #[derive(Debug)]
 /// Represents an implementation.
 pub struct ItemImpl {
+    /// The doc comments preceding this implementation, in declaration order.
+    pub docs: Vec<String>,
+
     /// The `impl` keyword.
     pub kw: Token,
 
@@ -167,6 +275,9 @@ pub enum ImplItem {
 
 #[derive(Debug)]
 pub struct ImplItemFn {
+    /// The doc comments preceding this method, in declaration order.
+    pub docs: Vec<String>,
+
     /// The `fn` keyword.
     pub kw: Token,
 
@@ -197,19 +308,180 @@ pub struct ImplItemFn {
 
 #[derive(Debug, Clone)]
 pub struct Ident {
-    /// The raw string representation of this identifier.
-    pub repr: String,
+    /// The interned name of this identifier.
+    pub repr: Name,
 
     /// The identifier span.
     pub span: Span,
 }
 
+/// Represents a type, as written in the source.
+#[derive(Debug, Clone)]
+pub enum Ty {
+    /// A named type, such as `i32` or `Point`.
+    Named(Ident),
+
+    /// A tuple type, such as `(i32, i32)`. The unit type `()` is the empty tuple.
+    Tuple(TyTuple),
+
+    /// An array type, such as `[i32]` or `[i32; 3]`.
+    Array(TyArray),
+
+    /// A function type, such as `fn(i32) -> i32`.
+    Fn(TyFn),
+
+    /// A reference type, such as `&i32`.
+    Ref(TyRef),
+}
+
+/// Represents an attribute, which marks the function following it (`@test`).
 #[derive(Debug)]
-pub struct Ty {
-    /// The raw string representation of this type.
+pub struct Attr {
+    /// The `@` symbol.
+    pub at: Token,
+
+    /// The name of the attribute.
     pub ident: Ident,
 
-    /// The type span.
+    /// The span of the entire attribute.
+    pub span: Span,
+}
+
+/// Represents the visibility of an item or field, which is private unless it's declared with `pub`.
+#[derive(Debug)]
+pub enum Visibility {
+    /// Declared with the given `pub` keyword.
+    Public(Token),
+    Private,
+}
+
+impl Visibility {
+    /// Was this declared with `pub`?
+    pub fn is_public(&self) -> bool {
+        matches!(self, Self::Public(_))
+    }
+}
+
+impl ItemFn {
+    /// Is this function marked with `@test`?
+    pub fn is_test(&self) -> bool {
+        self.attrs.iter().any(|attr| attr.ident.repr == "test")
+    }
+}
+
+impl Item {
+    /// Return the identifier this item declares, if it declares one. Impl blocks only add to existing types.
+    pub fn ident(&self) -> Option<&Ident> {
+        match self {
+            Self::Fn(item_fn) => Some(&item_fn.ident),
+            Self::Struct(item_struct) => Some(&item_struct.ident),
+            Self::Const(item_const) => Some(&item_const.ident),
+            Self::Impl(_) => None,
+        }
+    }
+}
+
+impl Ty {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Named(ident) => &ident.span,
+            Self::Tuple(ty_tuple) => &ty_tuple.span,
+            Self::Array(ty_array) => &ty_array.span,
+            Self::Fn(ty_fn) => &ty_fn.span,
+            Self::Ref(ty_ref) => &ty_ref.span,
+        }
+    }
+}
+
+/// Renders the type the way it would be written in the source.
+impl Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named(ident) => write!(f, "{}", ident.repr),
+            Self::Tuple(ty_tuple) if ty_tuple.elems.len() == 1 => {
+                write!(f, "({},)", ty_tuple.elems[0])
+            }
+            Self::Tuple(ty_tuple) => write!(f, "({})", join(&ty_tuple.elems)),
+            Self::Array(ty_array) => match &ty_array.len {
+                Some(len) => write!(f, "[{}; {}]", ty_array.elem, len),
+                None => write!(f, "[{}]", ty_array.elem),
+            },
+            Self::Fn(ty_fn) => write!(f, "fn({}) -> {}", join(&ty_fn.params), ty_fn.ret),
+            Self::Ref(ty_ref) => write!(f, "&{}", ty_ref.ty),
+        }
+    }
+}
+
+/// Join a list of types with commas.
+fn join(tys: &[Ty]) -> String {
+    tys.iter()
+        .map(|ty| ty.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Represents a tuple type.
+#[derive(Debug, Clone)]
+pub struct TyTuple {
+    /// The left parenthesis.
+    pub lp: Token,
+
+    /// The types of the elements.
+    pub elems: Vec<Ty>,
+
+    /// The right parenthesis.
+    pub rp: Token,
+
+    /// The span of the entire type.
+    pub span: Span,
+}
+
+/// Represents an array type.
+#[derive(Debug, Clone)]
+pub struct TyArray {
+    /// The left bracket.
+    pub lb: Token,
+
+    /// The type of the elements.
+    pub elem: Box<Ty>,
+
+    /// The (optional) length, which must be computable at compile time. It's shared rather than boxed since types
+    /// are cloned, but expressions aren't.
+    pub len: Option<Rc<Expr>>,
+
+    /// The right bracket.
+    pub rb: Token,
+
+    /// The span of the entire type.
+    pub span: Span,
+}
+
+/// Represents a reference type.
+#[derive(Debug, Clone)]
+pub struct TyRef {
+    /// The `&` symbol.
+    pub amp: Token,
+
+    /// The type being referred to.
+    pub ty: Box<Ty>,
+
+    /// The span of the entire type.
+    pub span: Span,
+}
+
+/// Represents a function type.
+#[derive(Debug, Clone)]
+pub struct TyFn {
+    /// The `fn` keyword.
+    pub kw: Token,
+
+    /// The types of the parameters.
+    pub params: Vec<Ty>,
+
+    /// The return type.
+    pub ret: Box<Ty>,
+
+    /// The span of the entire type.
     pub span: Span,
 }
 
@@ -221,6 +493,9 @@ pub struct Block {
     /// The statements in this block
     pub stmts: Vec<Stmt>,
 
+    /// The (optional) tail expression, which has no trailing semicolon and produces the value of the block.
+    pub tail: Option<Box<Expr>>,
+
     /// The right curly brace.
     pub rc: Token,
 
@@ -229,10 +504,153 @@ pub struct Block {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Stmt {
     Local(Local),
+    Assign(Assign),
     Expr(Expr),
     Return(Return),
+    Break(Break),
+}
+
+/// Represents an assignment to a variable that's already been declared (`x = x + 1`).
+#[derive(Debug)]
+pub struct Assign {
+    /// The variable being assigned to.
+    pub ident: Ident,
+
+    /// The `=` symbol.
+    pub eq: Token,
+
+    /// The new value of the variable.
+    pub expr: Expr,
+
+    /// The span of the entire assignment.
+    pub span: Span,
+}
+
+/// Represents a `break` statement (`break 'outer value;`), which exits the enclosing block with the given label. The
+/// value is optional, and defaults to `()`.
+#[derive(Debug)]
+pub struct Break {
+    /// The `break` keyword.
+    pub kw: Token,
+
+    /// The label of the block being exited.
+    pub label: BlockLabel,
+
+    /// The (optional) value of the block being exited.
+    pub expr: Option<Expr>,
+
+    /// The span of the entire break statement.
+    pub span: Span,
+}
+
+/// Represents a block label (`'outer`).
+#[derive(Debug, Clone)]
+pub struct BlockLabel {
+    /// The name of the label, without the leading quote.
+    pub name: Name,
+
+    /// The span of the label, including the leading quote.
+    pub span: Span,
+}
+
+/// Represents a block used as an expression (`{ ... }`), whose value is the value of its tail expression. Labeled
+/// blocks (`'outer: { ... }`) can also be exited early using `break 'outer value;`. Locals declared inside a block
+/// aren't visible outside of it.
+#[derive(Debug)]
+pub struct ExprBlock {
+    /// The label of this block, if it has one.
+    pub label: Option<BlockLabel>,
+
+    /// The colon after the label, if there is one.
+    pub colon: Option<Token>,
+
+    /// The block itself.
+    pub block: Block,
+
+    /// The span of the entire expression, including the label.
+    pub span: Span,
+}
+
+/// Represents a conditional expression (`if cond { ... } else { ... }`). The condition is a `bool`, which selects the
+/// `then` branch when it's `true`. Without an `else` branch, the value of the expression is `()`.
+#[derive(Debug)]
+pub struct ExprIf {
+    /// The `if` keyword.
+    pub kw: Token,
+
+    /// The condition.
+    pub cond: Box<Expr>,
+
+    /// The block evaluated when the condition holds.
+    pub then: Block,
+
+    /// The `else` keyword, if there is one.
+    pub else_kw: Option<Token>,
+
+    /// The block evaluated when the condition doesn't hold, if there is one. It's either a block expression or
+    /// another `if` expression, for `else if` chains.
+    pub els: Option<Box<Expr>>,
+
+    /// The span of the entire expression, including every branch.
+    pub span: Span,
+}
+
+/// Represents a `match` expression (`match x { 0 => a, _ => b }`). The arms are tried in order, and the body of the
+/// first one whose pattern matches the scrutinee is evaluated.
+#[derive(Debug)]
+pub struct ExprMatch {
+    /// The `match` keyword.
+    pub kw: Token,
+
+    /// The value being matched.
+    pub scrutinee: Box<Expr>,
+
+    /// The left curly brace.
+    pub lb: Token,
+
+    /// The arms, in the order they're tried.
+    pub arms: Vec<Arm>,
+
+    /// The right curly brace.
+    pub rb: Token,
+
+    pub span: Span,
+}
+
+/// Represents a single arm of a `match` expression (`pat => body`).
+#[derive(Debug)]
+pub struct Arm {
+    /// The pattern the scrutinee is tested against.
+    pub pat: Pat,
+
+    /// The `=>` symbol.
+    pub arrow: Token,
+
+    /// The expression evaluated when the pattern matches.
+    pub body: Expr,
+
+    pub span: Span,
+}
+
+/// Represents a pattern. For now, the only patterns are integer literals and the wildcard `_`, which matches anything.
+#[derive(Debug)]
+pub enum Pat {
+    Lit(LitNum),
+
+    /// The wildcard, along with its span.
+    Wild(Span),
+}
+
+impl Pat {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Lit(lit_num) => &lit_num.span,
+            Self::Wild(span) => span,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -240,8 +658,8 @@ pub struct Return {
     /// The `return` keyword.
     pub kw: Token,
 
-    /// The expression being returned.
-    pub expr: Expr,
+    /// The (optional) expression being returned. A bare `return` returns `()`.
+    pub expr: Option<Expr>,
 
     /// The span of the entire return statement.
     pub span: Span,
@@ -254,6 +672,16 @@ pub enum Expr {
     Struct(ExprStruct),
     Lit(ExprLit),
     Ident(Ident),
+    Field(ExprField),
+    MethodCall(ExprMethodCall),
+    Tuple(ExprTuple),
+    TupleIndex(ExprTupleIndex),
+    Ref(ExprRef),
+    Unary(ExprUnary),
+    Block(ExprBlock),
+    If(ExprIf),
+    Cmp(ExprCmp),
+    Match(ExprMatch),
 }
 
 impl Expr {
@@ -264,10 +692,105 @@ impl Expr {
             Self::Struct(expr_struct) => &expr_struct.span,
             Self::Lit(expr_lit) => expr_lit.span(),
             Self::Ident(ident) => &ident.span,
+            Self::Field(expr_field) => &expr_field.span,
+            Self::MethodCall(expr_method_call) => &expr_method_call.span,
+            Self::Tuple(expr_tuple) => &expr_tuple.span,
+            Self::TupleIndex(expr_tuple_index) => &expr_tuple_index.span,
+            Self::Ref(expr_ref) => &expr_ref.span,
+            Self::Unary(expr_unary) => &expr_unary.span,
+            Self::Block(expr_block) => &expr_block.span,
+            Self::If(expr_if) => &expr_if.span,
+            Self::Match(expr_match) => &expr_match.span,
+            Self::Cmp(expr_cmp) => &expr_cmp.span,
         }
     }
 }
 
+/// Renders the expression the way it would be written in the source (up to whitespace).
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Call(ExprCall::Fn(call_fn)) => {
+                write!(
+                    f,
+                    "{}({})",
+                    call_fn.ident.repr,
+                    join_exprs(&call_fn.args.args)
+                )
+            }
+            Self::Binary(expr_bin) => {
+                write!(f, "{} {} {}", expr_bin.lhs, expr_bin.op.kind, expr_bin.rhs)
+            }
+            Self::Cmp(expr_cmp) => {
+                write!(f, "{} {} {}", expr_cmp.lhs, expr_cmp.op.kind, expr_cmp.rhs)
+            }
+            Self::Struct(expr_struct) => {
+                let args = expr_struct
+                    .args
+                    .args
+                    .iter()
+                    .map(|arg| format!("{}: {}", arg.ident.repr, arg.expr))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "{} {{ {} }}", expr_struct.ident.repr, args)
+            }
+            Self::Lit(ExprLit::Num(lit_num)) => write!(f, "{}", lit_num.raw),
+            Self::Lit(ExprLit::Bool(lit_bool)) => write!(f, "{}", lit_bool.value),
+            Self::Ident(ident) => write!(f, "{}", ident.repr),
+            Self::Field(expr_field) => write!(f, "{}.{}", expr_field.expr, expr_field.ident.repr),
+            Self::MethodCall(expr_method_call) => write!(
+                f,
+                "{}.{}({})",
+                expr_method_call.receiver,
+                expr_method_call.ident.repr,
+                join_exprs(&expr_method_call.args.args)
+            ),
+            Self::Tuple(expr_tuple) if expr_tuple.elems.len() == 1 => {
+                write!(f, "({},)", expr_tuple.elems[0])
+            }
+            Self::Tuple(expr_tuple) => write!(f, "({})", join_exprs(&expr_tuple.elems)),
+            Self::TupleIndex(expr_tuple_index) => {
+                write!(
+                    f,
+                    "{}.{}",
+                    expr_tuple_index.expr, expr_tuple_index.index.value
+                )
+            }
+            Self::Ref(expr_ref) => write!(f, "&{}", expr_ref.expr),
+            // Binary operators bind looser than unary ones, so `-(1 + 2)` needs its parentheses back, and nested
+            // operators are kept apart so that `- -x` doesn't read as a decrement
+            Self::Unary(expr_unary) => match &*expr_unary.expr {
+                Expr::Binary(_) => write!(f, "{}({})", expr_unary.op.kind, expr_unary.expr),
+                Expr::Unary(_) => write!(f, "{} {}", expr_unary.op.kind, expr_unary.expr),
+                expr => write!(f, "{}{}", expr_unary.op.kind, expr),
+            },
+            Self::Block(expr_block) => match &expr_block.label {
+                Some(label) => write!(f, "'{}: {{ ... }}", label.name),
+                None => write!(f, "{{ ... }}"),
+            },
+            Self::If(expr_if) => {
+                write!(f, "if {} {{ ... }}", expr_if.cond)?;
+
+                match &expr_if.els {
+                    Some(els) => write!(f, " else {els}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Match(expr_match) => write!(f, "match {} {{ ... }}", expr_match.scrutinee),
+        }
+    }
+}
+
+/// Join a list of expressions with commas.
+fn join_exprs(exprs: &[Expr]) -> String {
+    exprs
+        .iter()
+        .map(|expr| expr.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug)]
 pub struct ExprStruct {
     pub ident: Ident,
@@ -281,6 +804,105 @@ pub struct ExprStruct {
     pub span: Span,
 }
 
+/// Represents a field access (`expr.field`).
+#[derive(Debug)]
+pub struct ExprField {
+    /// The expression whose field is being accessed.
+    pub expr: Box<Expr>,
+
+    /// The `.` symbol.
+    pub dot: Token,
+
+    /// The name of the field.
+    pub ident: Ident,
+
+    /// The span of the entire field access.
+    pub span: Span,
+}
+
+/// Represents a method call (`receiver.method(args)`).
+#[derive(Debug)]
+pub struct ExprMethodCall {
+    /// The expression the method is called on.
+    pub receiver: Box<Expr>,
+
+    /// The `.` symbol.
+    pub dot: Token,
+
+    /// The name of the method being called.
+    pub ident: Ident,
+
+    /// The left parenthesis.
+    pub lp: Token,
+
+    /// The list of arguments, not including the receiver.
+    pub args: ArgList,
+
+    /// The right parenthesis.
+    pub rp: Token,
+
+    /// The span of the entire method call.
+    pub span: Span,
+}
+
+/// Represents a tuple expression (`(a, b)`). The unit value `()` is the empty tuple.
+#[derive(Debug)]
+pub struct ExprTuple {
+    /// The left parenthesis.
+    pub lp: Token,
+
+    /// The elements of the tuple.
+    pub elems: Vec<Expr>,
+
+    /// The right parenthesis.
+    pub rp: Token,
+
+    /// The span of the entire tuple.
+    pub span: Span,
+}
+
+/// Represents indexing into a tuple (`expr.0`).
+#[derive(Debug)]
+pub struct ExprTupleIndex {
+    /// The tuple being indexed.
+    pub expr: Box<Expr>,
+
+    /// The `.` symbol.
+    pub dot: Token,
+
+    /// The index of the element.
+    pub index: LitNum,
+
+    /// The span of the entire expression.
+    pub span: Span,
+}
+
+/// Represents taking a reference to a value (`&expr`).
+#[derive(Debug)]
+pub struct ExprRef {
+    /// The `&` symbol.
+    pub amp: Token,
+
+    /// The expression being referred to.
+    pub expr: Box<Expr>,
+
+    /// The span of the entire expression.
+    pub span: Span,
+}
+
+/// Represents a prefix operator applied to an expression (`-expr`).
+#[derive(Debug)]
+pub struct ExprUnary {
+    /// The operator.
+    pub op: UnaryOp,
+
+    /// The operand.
+    pub expr: Box<Expr>,
+
+    /// The span of the entire expression.
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct NamedArgList {
     pub args: Vec<NamedArg>,
@@ -302,20 +924,36 @@ pub struct NamedArg {
 #[derive(Debug)]
 pub enum ExprLit {
     Num(LitNum),
+    Bool(LitBool),
 }
 
 impl ExprLit {
     pub fn span(&self) -> &Span {
         match self {
             Self::Num(lit_num) => &lit_num.span,
+            Self::Bool(lit_bool) => &lit_bool.span,
         }
     }
 }
 
 #[derive(Debug)]
 pub struct LitNum {
+    /// The value of the literal.
     pub value: i32,
 
+    /// The literal as written in the source, so `0x10`, `0b1_0000` and `16` can be told apart even though they
+    /// have the same value.
+    pub raw: Name,
+
+    pub span: Span,
+}
+
+/// Represents a boolean literal (`true` or `false`).
+#[derive(Debug)]
+pub struct LitBool {
+    /// The value of the literal.
+    pub value: bool,
+
     pub span: Span,
 }
 
@@ -327,11 +965,11 @@ pub struct Local {
     /// The identifier being locally bound.
     pub ident: Ident,
 
-    /// The semicolon following the identifier.
-    pub colon: Token,
+    /// The colon following the identifier, if the type is given.
+    pub colon: Option<Token>,
 
-    /// The type of this variable.
-    pub ty: Ty,
+    /// The type of this variable, if it's given. Otherwise, it's the type of the expression.
+    pub ty: Option<Ty>,
 
     /// The `=` symbol.
     pub eq: Token,
@@ -417,6 +1055,160 @@ pub struct BinaryOp {
 
 #[derive(Debug)]
 pub enum OpKind {
-    Add,      // +
-    Multiply, // *
+    Add,       // +
+    Subtract,  // -
+    Multiply,  // *
+    Divide,    // /
+    Remainder, // %
+}
+
+impl OpKind {
+    /// Applies this operator to two integers. Arithmetic wraps on overflow, division truncates towards zero, and
+    /// the remainder takes the sign of the dividend (so `-7 / 2` is `-3` and `-7 % 2` is `-1`). Returns `None` when
+    /// the right hand side of a division or remainder is zero, since the result is undefined.
+    pub fn apply(&self, lhs: i32, rhs: i32) -> Option<i32> {
+        match self {
+            Self::Add => Some(lhs.wrapping_add(rhs)),
+            Self::Subtract => Some(lhs.wrapping_sub(rhs)),
+            Self::Multiply => Some(lhs.wrapping_mul(rhs)),
+            Self::Divide if rhs != 0 => Some(lhs.wrapping_div(rhs)),
+            Self::Remainder if rhs != 0 => Some(lhs.wrapping_rem(rhs)),
+            Self::Divide | Self::Remainder => None,
+        }
+    }
+
+    /// Like `apply`, but also returns `None` if the result overflows.
+    pub fn checked_apply(&self, lhs: i32, rhs: i32) -> Option<i32> {
+        match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Subtract => lhs.checked_sub(rhs),
+            Self::Multiply => lhs.checked_mul(rhs),
+            Self::Divide => lhs.checked_div(rhs),
+            Self::Remainder => lhs.checked_rem(rhs),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnaryOp {
+    /// The kind of operator.
+    pub kind: UnaryOpKind,
+
+    /// The operator span.
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum UnaryOpKind {
+    Negate, // -
+    Not,    // !
+}
+
+impl UnaryOpKind {
+    /// Applies this operator to an integer, where `!` treats its operand as a boolean (`0` is `false` and anything
+    /// else is `true`) and produces `0` or `1`. Like binary arithmetic, negation wraps on overflow.
+    pub fn apply(&self, value: i32) -> i32 {
+        match self {
+            Self::Negate => value.wrapping_neg(),
+            Self::Not => (value == 0) as i32,
+        }
+    }
+
+    /// Like `apply`, but returns `None` if the result overflows (which only happens when negating `i32::MIN`).
+    pub fn checked_apply(&self, value: i32) -> Option<i32> {
+        match self {
+            Self::Negate => value.checked_neg(),
+            Self::Not => Some(self.apply(value)),
+        }
+    }
+}
+
+impl Display for UnaryOpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Negate => write!(f, "-"),
+            Self::Not => write!(f, "!"),
+        }
+    }
+}
+
+/// Represents a comparison (`lhs == rhs` or `lhs != rhs`), which produces a `bool`. Unlike arithmetic, comparisons
+/// work on values of any comparable type, and tuples and structs are equal when all of their elements or fields are.
+#[derive(Debug)]
+pub struct ExprCmp {
+    /// The left hand side of this comparison.
+    pub lhs: Box<Expr>,
+
+    /// The operator.
+    pub op: CmpOp,
+
+    /// The right hand side of this comparison.
+    pub rhs: Box<Expr>,
+
+    /// The span of this comparison.
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct CmpOp {
+    /// The kind of operator.
+    pub kind: CmpOpKind,
+
+    /// The operator span.
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum CmpOpKind {
+    Equal,        // ==
+    NotEqual,     // !=
+    Less,         // <
+    LessEqual,    // <=
+    Greater,      // >
+    GreaterEqual, // >=
+}
+
+impl CmpOpKind {
+    /// Applies this operator to two values of the same type. Values that can't be ordered are neither less nor
+    /// greater than each other.
+    pub fn apply<T: PartialOrd>(&self, lhs: &T, rhs: &T) -> bool {
+        match self {
+            Self::Equal => lhs == rhs,
+            Self::NotEqual => lhs != rhs,
+            Self::Less => lhs < rhs,
+            Self::LessEqual => lhs <= rhs,
+            Self::Greater => lhs > rhs,
+            Self::GreaterEqual => lhs >= rhs,
+        }
+    }
+
+    /// Whether this operator orders its operands, rather than comparing them for equality.
+    pub fn is_ordering(&self) -> bool {
+        !matches!(self, Self::Equal | Self::NotEqual)
+    }
+}
+
+impl Display for CmpOpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Equal => write!(f, "=="),
+            Self::NotEqual => write!(f, "!="),
+            Self::Less => write!(f, "<"),
+            Self::LessEqual => write!(f, "<="),
+            Self::Greater => write!(f, ">"),
+            Self::GreaterEqual => write!(f, ">="),
+        }
+    }
+}
+
+impl Display for OpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Add => write!(f, "+"),
+            Self::Subtract => write!(f, "-"),
+            Self::Multiply => write!(f, "*"),
+            Self::Divide => write!(f, "/"),
+            Self::Remainder => write!(f, "%"),
+        }
+    }
 }
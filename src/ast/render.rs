@@ -0,0 +1,416 @@
+use std::fmt::Write;
+
+use super::visitor::{self, Visit};
+use super::{
+    Arm, Assign, Block, Break, CallFn, Expr, ExprBin, ExprBlock, ExprCmp, ExprField, ExprIf,
+    ExprMatch, ExprMethodCall, ExprRef, ExprStruct, ExprTuple, ExprTupleIndex, ExprUnary,
+    FieldNamed, FieldsUnnamed, File, ImplItemFn, ItemConst, ItemFn, ItemImpl, ItemStruct, LitBool,
+    LitNum, Local, Param, Pat, Return, Ty, Visibility,
+};
+use crate::shared::Span;
+
+/// Renders a syntax tree as a compact tree, one node per line. Every line names the kind of node, followed by its
+/// most important attributes (such as identifiers, operators and literal values) and its span. Children are
+/// indented one level further than their parent, in the order they appear in the source. Doc comments (when the
+/// parser kept them) come first among the children of what they document, as `Doc` lines without a span.
+pub struct AstRenderer {
+    /// The lines rendered so far.
+    out: String,
+
+    /// The depth of the node being rendered.
+    depth: usize,
+}
+
+impl AstRenderer {
+    /// Render the syntax tree of an entire program.
+    pub fn render(file: &File) -> String {
+        let mut renderer = AstRenderer {
+            out: String::new(),
+            depth: 0,
+        };
+
+        renderer.visit_file(file);
+        renderer.out
+    }
+
+    /// Write the line for a node, then render its children one level deeper.
+    fn node(&mut self, line: String, span: &Span, children: impl FnOnce(&mut Self)) {
+        let _ = writeln!(
+            self.out,
+            "{}{line} @ {}",
+            "  ".repeat(self.depth),
+            span_readable(span)
+        );
+
+        self.depth += 1;
+        children(self);
+        self.depth -= 1;
+    }
+
+    /// Write a line for every doc comment, at the current depth.
+    fn docs(&mut self, docs: &[String]) {
+        for doc in docs {
+            let _ = writeln!(self.out, "{}Doc {doc:?}", "  ".repeat(self.depth));
+        }
+    }
+
+    /// Render a block as a node of its own.
+    fn block(&mut self, block: &Block) {
+        self.node(String::from("Block"), &block.span, |renderer| {
+            visitor::visit_block(renderer, block)
+        });
+    }
+}
+
+impl<'a> Visit<'a> for AstRenderer {
+    fn visit_file(&mut self, file: &'a File) {
+        self.node(String::from("File"), &file.span, |renderer| {
+            visitor::visit_file(renderer, file)
+        });
+    }
+
+    fn visit_item_fn(&mut self, item_fn: &'a ItemFn) {
+        let params = item_fn.params.params.iter().map(param_readable).collect();
+        let line = signature(
+            pub_readable(&item_fn.vis),
+            &item_fn.ident.repr,
+            params,
+            &item_fn.ty,
+        );
+
+        self.node(line, &item_fn.span, |renderer| {
+            renderer.docs(&item_fn.docs);
+            renderer.block(&item_fn.body)
+        });
+    }
+
+    fn visit_item_struct(&mut self, item_struct: &'a ItemStruct) {
+        let line = format!(
+            "{}Struct {}",
+            pub_readable(&item_struct.vis),
+            item_struct.ident.repr
+        );
+
+        self.node(line, &item_struct.span, |renderer| {
+            renderer.docs(&item_struct.docs);
+            visitor::visit_item_struct(renderer, item_struct)
+        });
+    }
+
+    fn visit_field_named(&mut self, field_named: &'a FieldNamed) {
+        let line = format!(
+            "{}Field {}: {}",
+            pub_readable(&field_named.vis),
+            field_named.ident.repr,
+            field_named.ty
+        );
+
+        self.node(line, &field_named.span, |renderer| {
+            renderer.docs(&field_named.docs)
+        });
+    }
+
+    fn visit_fields_unnamed(&mut self, fields_unnamed: &'a FieldsUnnamed) {
+        for (index, ty) in fields_unnamed.fields.iter().enumerate() {
+            self.node(format!("Field {index}: {ty}"), ty.span(), |_| {});
+        }
+    }
+
+    fn visit_item_impl(&mut self, item_impl: &'a ItemImpl) {
+        self.node(
+            format!("Impl {}", item_impl.ident.repr),
+            &item_impl.span,
+            |renderer| {
+                renderer.docs(&item_impl.docs);
+                visitor::visit_item_impl(renderer, item_impl)
+            },
+        );
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'a ImplItemFn) {
+        let mut params = Vec::new();
+        if impl_item_fn.params.receiver.is_some() {
+            params.push(String::from("self"));
+        }
+        params.extend(impl_item_fn.params.params.iter().map(param_readable));
+
+        let line = signature("", &impl_item_fn.ident.repr, params, &impl_item_fn.ty);
+
+        self.node(line, &impl_item_fn.span, |renderer| {
+            renderer.docs(&impl_item_fn.docs);
+            renderer.block(&impl_item_fn.body)
+        });
+    }
+
+    fn visit_item_const(&mut self, item_const: &'a ItemConst) {
+        let line = format!("Const {}: {}", item_const.ident.repr, item_const.ty);
+
+        self.node(line, &item_const.span, |renderer| {
+            renderer.docs(&item_const.docs);
+            renderer.visit_expr(&item_const.expr)
+        });
+    }
+
+    fn visit_local(&mut self, local: &'a Local) {
+        let line = match &local.ty {
+            Some(ty) => format!("Let {}: {}", local.ident.repr, ty),
+            None => format!("Let {}", local.ident.repr),
+        };
+
+        self.node(line, &local.span, |renderer| {
+            renderer.visit_expr(&local.expr)
+        });
+    }
+
+    fn visit_assign(&mut self, assign: &'a Assign) {
+        self.node(
+            format!("Assign {}", assign.ident.repr),
+            &assign.span,
+            |renderer| renderer.visit_expr(&assign.expr),
+        );
+    }
+
+    fn visit_ret(&mut self, ret: &'a Return) {
+        self.node(String::from("Return"), &ret.span, |renderer| {
+            visitor::visit_ret(renderer, ret)
+        });
+    }
+
+    fn visit_brk(&mut self, brk: &'a Break) {
+        self.node(
+            format!("Break '{}", brk.label.name),
+            &brk.span,
+            |renderer| visitor::visit_brk(renderer, brk),
+        );
+    }
+
+    /// Identifiers are only rendered as expressions. Everywhere else, they're an attribute of their parent.
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Ident(ident) => self.node(format!("Ident {}", ident.repr), &ident.span, |_| {}),
+            expr => visitor::visit_expr(self, expr),
+        }
+    }
+
+    fn visit_expr_bin(&mut self, expr_bin: &'a ExprBin) {
+        self.node(
+            format!("Binary {}", expr_bin.op.kind),
+            &expr_bin.span,
+            |renderer| visitor::visit_expr_bin(renderer, expr_bin),
+        );
+    }
+
+    fn visit_expr_cmp(&mut self, expr_cmp: &'a ExprCmp) {
+        self.node(
+            format!("Cmp {}", expr_cmp.op.kind),
+            &expr_cmp.span,
+            |renderer| visitor::visit_expr_cmp(renderer, expr_cmp),
+        );
+    }
+
+    fn visit_expr_unary(&mut self, expr_unary: &'a ExprUnary) {
+        self.node(
+            format!("Unary {}", expr_unary.op.kind),
+            &expr_unary.span,
+            |renderer| visitor::visit_expr_unary(renderer, expr_unary),
+        );
+    }
+
+    fn visit_call_fn(&mut self, call_fn: &'a CallFn) {
+        self.node(
+            format!("Call {}", call_fn.ident.repr),
+            &call_fn.span,
+            |renderer| visitor::visit_call_fn(renderer, call_fn),
+        );
+    }
+
+    fn visit_lit_num(&mut self, lit_num: &'a LitNum) {
+        self.node(format!("Lit {}", lit_num.raw), &lit_num.span, |_| {});
+    }
+
+    fn visit_lit_bool(&mut self, lit_bool: &'a LitBool) {
+        self.node(format!("Lit {}", lit_bool.value), &lit_bool.span, |_| {});
+    }
+
+    /// Every argument gets its own node, so that the name of the field it initializes isn't lost.
+    fn visit_expr_struct(&mut self, expr_struct: &'a ExprStruct) {
+        self.node(
+            format!("Struct {}", expr_struct.ident.repr),
+            &expr_struct.span,
+            |renderer| {
+                for arg in &expr_struct.args.args {
+                    renderer.node(format!("Arg {}", arg.ident.repr), &arg.span, |renderer| {
+                        renderer.visit_expr(&arg.expr)
+                    });
+                }
+            },
+        );
+    }
+
+    fn visit_expr_field(&mut self, expr_field: &'a ExprField) {
+        self.node(
+            format!("Field .{}", expr_field.ident.repr),
+            &expr_field.span,
+            |renderer| visitor::visit_expr_field(renderer, expr_field),
+        );
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &'a ExprMethodCall) {
+        self.node(
+            format!("MethodCall .{}", expr_method_call.ident.repr),
+            &expr_method_call.span,
+            |renderer| visitor::visit_expr_method_call(renderer, expr_method_call),
+        );
+    }
+
+    fn visit_expr_tuple(&mut self, expr_tuple: &'a ExprTuple) {
+        self.node(String::from("Tuple"), &expr_tuple.span, |renderer| {
+            visitor::visit_expr_tuple(renderer, expr_tuple)
+        });
+    }
+
+    fn visit_expr_tuple_index(&mut self, expr_tuple_index: &'a ExprTupleIndex) {
+        self.node(
+            format!("TupleIndex .{}", expr_tuple_index.index.value),
+            &expr_tuple_index.span,
+            |renderer| visitor::visit_expr_tuple_index(renderer, expr_tuple_index),
+        );
+    }
+
+    fn visit_expr_ref(&mut self, expr_ref: &'a ExprRef) {
+        self.node(String::from("Ref"), &expr_ref.span, |renderer| {
+            visitor::visit_expr_ref(renderer, expr_ref)
+        });
+    }
+
+    /// A block expression and the block inside it are rendered as a single node.
+    fn visit_expr_block(&mut self, expr_block: &'a ExprBlock) {
+        let line = match &expr_block.label {
+            Some(label) => format!("Block '{}", label.name),
+            None => String::from("Block"),
+        };
+
+        self.node(line, &expr_block.span, |renderer| {
+            visitor::visit_block(renderer, &expr_block.block)
+        });
+    }
+
+    fn visit_expr_if(&mut self, expr_if: &'a ExprIf) {
+        self.node(String::from("If"), &expr_if.span, |renderer| {
+            renderer.visit_expr(&expr_if.cond);
+            renderer.block(&expr_if.then);
+
+            if let Some(els) = &expr_if.els {
+                renderer.visit_expr(els);
+            }
+        });
+    }
+
+    fn visit_expr_match(&mut self, expr_match: &'a ExprMatch) {
+        self.node(String::from("Match"), &expr_match.span, |renderer| {
+            visitor::visit_expr_match(renderer, expr_match)
+        });
+    }
+
+    fn visit_arm(&mut self, arm: &'a Arm) {
+        let line = match &arm.pat {
+            Pat::Lit(lit_num) => format!("Arm {}", lit_num.raw),
+            Pat::Wild(_) => String::from("Arm _"),
+        };
+
+        self.node(line, &arm.span, |renderer| renderer.visit_expr(&arm.body));
+    }
+}
+
+/// The signature of a function, as shown on its node (`Fn add(a: i32, b: i32) -> i32`).
+fn signature(vis: &str, ident: &str, params: Vec<String>, ty: &Ty) -> String {
+    format!("{vis}Fn {ident}({}) -> {ty}", params.join(", "))
+}
+
+fn param_readable(param: &Param) -> String {
+    format!("{}: {}", param.ident.repr, param.ty)
+}
+
+fn pub_readable(vis: &Visibility) -> &'static str {
+    match vis {
+        Visibility::Public(_) => "pub ",
+        Visibility::Private => "",
+    }
+}
+
+/// A span as `line:column-line:column`, or just `line:column` if it covers a single character.
+fn span_readable(span: &Span) -> String {
+    let (start, end) = (&span.start, &span.end);
+
+    match start == end {
+        true => format!("{}:{}", start.line, start.column),
+        false => format!(
+            "{}:{}-{}:{}",
+            start.line, start.column, end.line, end.column
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Parse a program, keeping its doc comments, and render its syntax tree.
+    fn render(src: &str) -> String {
+        let tokens = match Lexer::from_chars(src.chars()).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("the program should lex, but: {}", err.reason),
+        };
+
+        match Parser::new(&tokens).with_docs().parse_file() {
+            (file, errors) if errors.is_empty() => AstRenderer::render(&file),
+            (_, errors) => panic!("the program should parse, but: {}", errors[0].reason),
+        }
+    }
+
+    #[test]
+    fn children_are_indented_beneath_their_parent() {
+        let src =
+            "struct P { x: i32 }\nfn main() -> i32 {\n    let p = P { x: 1 };\n    -p.x + 2\n}";
+
+        assert_eq!(
+            render(src),
+            "\
+File @ 1:1-5:1
+  Struct P @ 1:1-1:19
+    Field x: i32 @ 1:12-1:17
+  Fn main() -> i32 @ 2:1-5:1
+    Block @ 2:18-5:1
+      Let p @ 3:5-3:22
+        Struct P @ 3:13-3:22
+          Arg x @ 3:17-3:20
+            Lit 1 @ 3:20
+      Binary + @ 4:5-4:12
+        Unary - @ 4:5-4:8
+          Field .x @ 4:6-4:8
+            Ident p @ 4:6
+        Lit 2 @ 4:12
+"
+        );
+    }
+
+    #[test]
+    fn doc_comments_come_first_among_the_children() {
+        let src = "/// The answer.\nconst A: i32 = 42;\nfn main() -> i32 { A }";
+
+        assert_eq!(
+            render(src),
+            "\
+File @ 1:1-3:22
+  Const A: i32 @ 2:1-2:18
+    Doc \"The answer.\"
+    Lit 42 @ 2:16-2:17
+  Fn main() -> i32 @ 3:1-3:22
+    Block @ 3:18-3:22
+      Ident A @ 3:20
+"
+        );
+    }
+}
@@ -2,9 +2,10 @@
 use paste::paste;
 
 use super::{
-    Block, CallFn, Expr, ExprBin, ExprCall, ExprLit, ExprStruct, FieldNamed, Fields, FieldsNamed,
-    File, Ident, ImplItem, ImplItemFn, Item, ItemFn, ItemImpl, ItemStruct, LitNum, Local, Return,
-    Stmt, Ty,
+    Arm, Assign, Block, Break, CallFn, Expr, ExprBin, ExprBlock, ExprCall, ExprCmp, ExprField,
+    ExprIf, ExprLit, ExprMatch, ExprMethodCall, ExprRef, ExprStruct, ExprTuple, ExprTupleIndex,
+    ExprUnary, FieldNamed, Fields, FieldsNamed, FieldsUnnamed, File, Ident, ImplItem, ImplItemFn,
+    Item, ItemConst, ItemFn, ItemImpl, ItemStruct, LitBool, LitNum, Local, Pat, Return, Stmt, Ty,
 };
 
 /// This macro generates the `Visitor` trait. Unfortunately, you still have to manually implement each `visit_*` function
@@ -15,7 +16,7 @@ macro_rules! visitor {
             $(
                 paste! {
                     fn [<visit_ $arg>] (&mut self, $arg: &'a $ty) {
-                        concat_idents!(visit_, $arg) (self, $arg);
+                        [<visit_ $arg>](self, $arg);
                     }
                 }
             )*
@@ -31,14 +32,17 @@ visitor! {
     item_struct: ItemStruct,
     fields: Fields,
     fields_named: FieldsNamed,
+    fields_unnamed: FieldsUnnamed,
     field_named: FieldNamed,
     item_impl: ItemImpl,
+    item_const: ItemConst,
     impl_item: ImplItem,
     impl_item_fn: ImplItemFn,
     ident: Ident,
     block: Block,
     stmt: Stmt,
     local: Local,
+    assign: Assign,
     expr: Expr,
     ty: Ty,
 
@@ -46,10 +50,23 @@ visitor! {
     expr_struct: ExprStruct,
     expr_call: ExprCall,
     expr_lit: ExprLit,
+    expr_field: ExprField,
+    expr_method_call: ExprMethodCall,
+    expr_tuple: ExprTuple,
+    expr_tuple_index: ExprTupleIndex,
+    expr_ref: ExprRef,
+    expr_unary: ExprUnary,
+    expr_block: ExprBlock,
+    expr_if: ExprIf,
+    expr_cmp: ExprCmp,
+    expr_match: ExprMatch,
+    arm: Arm,
 
     call_fn: CallFn,
     lit_num: LitNum,
-    ret: Return
+    lit_bool: LitBool,
+    ret: Return,
+    brk: Break
 }
 
 pub fn visit_file<'a>(visitor: &mut impl Visit<'a>, program: &'a File) {
@@ -60,12 +77,19 @@ pub fn visit_file<'a>(visitor: &mut impl Visit<'a>, program: &'a File) {
 
 pub fn visit_item<'a>(visitor: &mut impl Visit<'a>, item: &'a Item) {
     match item {
-        Item::Fn(item_fn) => visitor.visit_item_fn(&item_fn),
+        Item::Fn(item_fn) => visitor.visit_item_fn(item_fn),
         Item::Struct(item_struct) => visitor.visit_item_struct(item_struct),
         Item::Impl(item_impl) => visitor.visit_item_impl(item_impl),
+        Item::Const(item_const) => visitor.visit_item_const(item_const),
     }
 }
 
+pub fn visit_item_const<'a>(visitor: &mut impl Visit<'a>, item_const: &'a ItemConst) {
+    visitor.visit_ident(&item_const.ident);
+    visitor.visit_ty(&item_const.ty);
+    visitor.visit_expr(&item_const.expr);
+}
+
 pub fn visit_item_fn<'a>(visitor: &mut impl Visit<'a>, item_fn: &'a ItemFn) {
     visitor.visit_ident(&item_fn.ident);
     visitor.visit_block(&item_fn.body);
@@ -79,6 +103,7 @@ pub fn visit_item_struct<'a>(visitor: &mut impl Visit<'a>, item_struct: &'a Item
 pub fn visit_fields<'a>(visitor: &mut impl Visit<'a>, fields: &'a Fields) {
     match fields {
         Fields::Named(fields_named) => visitor.visit_fields_named(fields_named),
+        Fields::Unnamed(fields_unnamed) => visitor.visit_fields_unnamed(fields_unnamed),
     }
 }
 
@@ -88,6 +113,12 @@ pub fn visit_fields_named<'a>(visitor: &mut impl Visit<'a>, fields_named: &'a Fi
     }
 }
 
+pub fn visit_fields_unnamed<'a>(visitor: &mut impl Visit<'a>, fields_unnamed: &'a FieldsUnnamed) {
+    for ty in &fields_unnamed.fields {
+        visitor.visit_ty(ty);
+    }
+}
+
 pub fn visit_field_named<'a>(visitor: &mut impl Visit<'a>, field_named: &'a FieldNamed) {
     visitor.visit_ident(&field_named.ident);
     visitor.visit_ty(&field_named.ty);
@@ -120,22 +151,35 @@ pub fn visit_block<'a>(visitor: &mut impl Visit<'a>, block: &'a Block) {
     for stmt in &block.stmts {
         visitor.visit_stmt(stmt)
     }
+
+    if let Some(tail) = &block.tail {
+        visitor.visit_expr(tail)
+    }
 }
 
 pub fn visit_stmt<'a>(visitor: &mut impl Visit<'a>, stmt: &'a Stmt) {
     match stmt {
         Stmt::Local(local) => visitor.visit_local(local),
+        Stmt::Assign(assign) => visitor.visit_assign(assign),
         Stmt::Expr(expr) => visitor.visit_expr(expr),
         Stmt::Return(ret) => visitor.visit_ret(ret),
+        Stmt::Break(brk) => visitor.visit_brk(brk),
     }
 }
 
 pub fn visit_local<'a>(visitor: &mut impl Visit<'a>, local: &'a Local) {
     visitor.visit_ident(&local.ident);
-    visitor.visit_ty(&local.ty);
+    if let Some(ty) = &local.ty {
+        visitor.visit_ty(ty);
+    }
     visitor.visit_expr(&local.expr)
 }
 
+pub fn visit_assign<'a>(visitor: &mut impl Visit<'a>, assign: &'a Assign) {
+    visitor.visit_ident(&assign.ident);
+    visitor.visit_expr(&assign.expr)
+}
+
 pub fn visit_expr<'a>(visitor: &mut impl Visit<'a>, expr: &'a Expr) {
     match expr {
         Expr::Binary(expr_bin) => visitor.visit_expr_bin(expr_bin),
@@ -143,6 +187,16 @@ pub fn visit_expr<'a>(visitor: &mut impl Visit<'a>, expr: &'a Expr) {
         Expr::Lit(expr_lit) => visitor.visit_expr_lit(expr_lit),
         Expr::Ident(ident) => visitor.visit_ident(ident),
         Expr::Struct(expr_struct) => visitor.visit_expr_struct(expr_struct),
+        Expr::Field(expr_field) => visitor.visit_expr_field(expr_field),
+        Expr::MethodCall(expr_method_call) => visitor.visit_expr_method_call(expr_method_call),
+        Expr::Tuple(expr_tuple) => visitor.visit_expr_tuple(expr_tuple),
+        Expr::TupleIndex(expr_tuple_index) => visitor.visit_expr_tuple_index(expr_tuple_index),
+        Expr::Ref(expr_ref) => visitor.visit_expr_ref(expr_ref),
+        Expr::Unary(expr_unary) => visitor.visit_expr_unary(expr_unary),
+        Expr::Block(expr_block) => visitor.visit_expr_block(expr_block),
+        Expr::If(expr_if) => visitor.visit_expr_if(expr_if),
+        Expr::Cmp(expr_cmp) => visitor.visit_expr_cmp(expr_cmp),
+        Expr::Match(expr_match) => visitor.visit_expr_match(expr_match),
     }
 }
 
@@ -155,8 +209,84 @@ pub fn visit_expr_bin<'a>(visitor: &mut impl Visit<'a>, expr_bin: &'a ExprBin) {
     visitor.visit_expr(&expr_bin.rhs);
 }
 
+pub fn visit_expr_cmp<'a>(visitor: &mut impl Visit<'a>, expr_cmp: &'a ExprCmp) {
+    visitor.visit_expr(&expr_cmp.lhs);
+    visitor.visit_expr(&expr_cmp.rhs);
+}
+
 pub fn visit_expr_struct<'a>(visitor: &mut impl Visit<'a>, expr_struct: &'a ExprStruct) {
-    // Nothing for now
+    visitor.visit_ident(&expr_struct.ident);
+
+    for arg in &expr_struct.args.args {
+        visitor.visit_expr(&arg.expr);
+    }
+}
+
+pub fn visit_expr_field<'a>(visitor: &mut impl Visit<'a>, expr_field: &'a ExprField) {
+    visitor.visit_expr(&expr_field.expr);
+    visitor.visit_ident(&expr_field.ident);
+}
+
+pub fn visit_expr_method_call<'a>(
+    visitor: &mut impl Visit<'a>,
+    expr_method_call: &'a ExprMethodCall,
+) {
+    visitor.visit_expr(&expr_method_call.receiver);
+    visitor.visit_ident(&expr_method_call.ident);
+
+    for arg in &expr_method_call.args.args {
+        visitor.visit_expr(arg);
+    }
+}
+
+pub fn visit_expr_tuple<'a>(visitor: &mut impl Visit<'a>, expr_tuple: &'a ExprTuple) {
+    for elem in &expr_tuple.elems {
+        visitor.visit_expr(elem);
+    }
+}
+
+pub fn visit_expr_tuple_index<'a>(
+    visitor: &mut impl Visit<'a>,
+    expr_tuple_index: &'a ExprTupleIndex,
+) {
+    visitor.visit_expr(&expr_tuple_index.expr);
+}
+
+pub fn visit_expr_ref<'a>(visitor: &mut impl Visit<'a>, expr_ref: &'a ExprRef) {
+    visitor.visit_expr(&expr_ref.expr);
+}
+
+pub fn visit_expr_unary<'a>(visitor: &mut impl Visit<'a>, expr_unary: &'a ExprUnary) {
+    visitor.visit_expr(&expr_unary.expr);
+}
+
+pub fn visit_expr_block<'a>(visitor: &mut impl Visit<'a>, expr_block: &'a ExprBlock) {
+    visitor.visit_block(&expr_block.block);
+}
+
+pub fn visit_expr_if<'a>(visitor: &mut impl Visit<'a>, expr_if: &'a ExprIf) {
+    visitor.visit_expr(&expr_if.cond);
+    visitor.visit_block(&expr_if.then);
+
+    if let Some(els) = &expr_if.els {
+        visitor.visit_expr(els);
+    }
+}
+
+pub fn visit_expr_match<'a>(visitor: &mut impl Visit<'a>, expr_match: &'a ExprMatch) {
+    visitor.visit_expr(&expr_match.scrutinee);
+
+    for arm in &expr_match.arms {
+        visitor.visit_arm(arm);
+    }
+}
+
+pub fn visit_arm<'a>(visitor: &mut impl Visit<'a>, arm: &'a Arm) {
+    if let Pat::Lit(lit_num) = &arm.pat {
+        visitor.visit_lit_num(lit_num);
+    }
+
+    visitor.visit_expr(&arm.body);
 }
 
 pub fn visit_expr_call<'a>(visitor: &mut impl Visit<'a>, expr_call: &'a ExprCall) {
@@ -168,17 +298,34 @@ pub fn visit_expr_call<'a>(visitor: &mut impl Visit<'a>, expr_call: &'a ExprCall
 pub fn visit_expr_lit<'a>(visitor: &mut impl Visit<'a>, expr_lit: &'a ExprLit) {
     match expr_lit {
         ExprLit::Num(lit_num) => visitor.visit_lit_num(lit_num),
+        ExprLit::Bool(lit_bool) => visitor.visit_lit_bool(lit_bool),
     }
 }
 
 pub fn visit_call_fn<'a>(visitor: &mut impl Visit<'a>, call_fn: &'a CallFn) {
     visitor.visit_ident(&call_fn.ident);
+
+    for arg in &call_fn.args.args {
+        visitor.visit_expr(arg);
+    }
 }
 
 pub fn visit_lit_num<'a>(visitor: &mut impl Visit<'a>, lit_num: &'a LitNum) {
     // Nothing to do here
 }
 
+pub fn visit_lit_bool<'a>(visitor: &mut impl Visit<'a>, lit_bool: &'a LitBool) {
+    // Nothing to do here
+}
+
 pub fn visit_ret<'a>(visitor: &mut impl Visit<'a>, ret: &'a Return) {
-    visitor.visit_expr(&ret.expr);
+    if let Some(expr) = &ret.expr {
+        visitor.visit_expr(expr);
+    }
+}
+
+pub fn visit_brk<'a>(visitor: &mut impl Visit<'a>, brk: &'a Break) {
+    if let Some(expr) = &brk.expr {
+        visitor.visit_expr(expr);
+    }
 }
@@ -0,0 +1,65 @@
+use std::fmt::Display;
+
+use crate::asm::TARGETS;
+
+/// The names of the language features enabled in this build. None are behind feature flags yet, so every build
+/// supports the same language and this is empty.
+pub const FEATURES: &[&str] = &[];
+
+/// Describes the capabilities of this build of the compiler.
+#[derive(Debug, Clone)]
+pub struct CompilerInfo {
+    /// The version of the compiler.
+    pub version: &'static str,
+
+    /// The names of all available assembly targets.
+    pub targets: Vec<&'static str>,
+
+    /// The names of all enabled language features.
+    pub features: Vec<&'static str>,
+}
+
+/// Return information about this build of the compiler.
+pub fn compiler_info() -> CompilerInfo {
+    CompilerInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        targets: TARGETS.to_vec(),
+        features: FEATURES.to_vec(),
+    }
+}
+
+impl Display for CompilerInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.version)?;
+        writeln!(f, "targets: {}", list(&self.targets))?;
+        write!(f, "features: {}", list(&self.features))
+    }
+}
+
+fn list(names: &[&str]) -> String {
+    match names.is_empty() {
+        true => String::from("none"),
+        false => names.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn risc_v_is_a_target() {
+        assert!(compiler_info().targets.contains(&"risc-v"));
+    }
+
+    #[test]
+    fn versions_list_targets_and_features() {
+        let info = CompilerInfo {
+            version: "1.2.3",
+            targets: vec!["risc-v"],
+            features: vec![],
+        };
+
+        assert_eq!(info.to_string(), "1.2.3\ntargets: risc-v\nfeatures: none");
+    }
+}
@@ -0,0 +1,858 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::ast::{
+    Block, CallFn, Expr, ExprBin, ExprCall, ExprField, ExprLit, ExprMethodCall, ExprStruct,
+    ExprTuple, ExprTupleIndex, Fields, File, ImplItem, ImplItemFn, Item, ItemFn, OpKind, Pat, Stmt,
+    UnaryOpKind,
+};
+use crate::ir::fold::eval_consts;
+use crate::shared::{Builtin, Halt, Name, Span, SymbolTable};
+
+/// Represents an error that occured while interpreting a program.
+pub struct InterpError {
+    /// The cause of this error.
+    pub reason: String,
+
+    /// The (optional) span of this error.
+    pub span: Option<Span>,
+}
+
+/// Describes why evaluation stopped before producing a value.
+enum Unwind {
+    /// A runtime error occured.
+    Error(InterpError),
+
+    /// A `return` statement was executed, producing the returned value.
+    Return(Value),
+
+    /// A `break` statement was executed, exiting the block with the given label with a value.
+    Break(Name, Value),
+
+    /// The `exit` builtin was called, ending the program with the given exit code.
+    Exit(i32),
+}
+
+impl From<InterpError> for Unwind {
+    fn from(err: InterpError) -> Self {
+        Unwind::Error(err)
+    }
+}
+
+/// Represents the result of interpreting. Returning from a function and breaking out of a block unwind through the
+/// expressions being evaluated just like errors do, until they reach the function or block they exit. Exiting the
+/// program unwinds all the way to the top.
+type InterpResult<T> = Result<T, Unwind>;
+
+/// Turn the result of evaluating the body of a function into its value, or the error that stopped it.
+fn settle(result: InterpResult<Value>) -> Result<Value, InterpError> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(Unwind::Error(err)) => Err(err),
+
+        // Type checking makes sure every `break` is inside the block it exits
+        Err(Unwind::Return(_) | Unwind::Break(..)) => Err(InterpError {
+            reason: String::from("Attempt to break out of a function"),
+            span: None,
+        }),
+
+        Err(Unwind::Exit(code)) => Err(InterpError {
+            reason: format!("Attempt to exit with code {code} outside of a running program"),
+            span: None,
+        }),
+    }
+}
+
+/// Represents a value produced at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unit,
+    Int(i32),
+    Bool(bool),
+    /// A struct value, along with the name of its type (used to find its methods).
+    Struct(Name, HashMap<Name, Value>),
+
+    /// A tuple with at least one element. The empty tuple is `Unit`.
+    Tuple(Vec<Value>),
+}
+
+impl PartialOrd for Value {
+    /// Only integers are ordered. Every other value is only equal or unequal to another.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Int(lhs), Self::Int(rhs)) => lhs.partial_cmp(rhs),
+            _ if self == other => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Struct(ident, fields) => {
+                // Fields are stored without their declaration order, so they're sorted by name instead
+                let mut fields = fields.iter().collect::<Vec<_>>();
+                fields.sort_by_key(|(name, _)| name.as_str());
+
+                write!(f, "{ident} {{")?;
+                for (index, (name, value)) in fields.into_iter().enumerate() {
+                    let sep = if index == 0 { " " } else { ", " };
+                    write!(f, "{sep}{name}: {value}")?;
+                }
+                write!(f, " }}")
+            }
+            Self::Tuple(elems) if elems.len() == 1 => write!(f, "({},)", elems[0]),
+            Self::Tuple(elems) => {
+                write!(f, "(")?;
+                for (index, elem) in elems.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{elem}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Runs a program by walking its abstract syntax tree. The program is assumed to have passed semantic analysis and
+/// type checking already. Arithmetic wraps on overflow, just like the generated code would.
+pub struct Interpreter<'a> {
+    /// Map from function names to their declarations.
+    functions: HashMap<&'a str, &'a ItemFn>,
+
+    /// Map from type names to their methods, keyed by name.
+    methods: HashMap<&'a str, HashMap<&'a str, &'a ImplItemFn>>,
+
+    /// The names of the tuple structs, which are built by calling them like functions.
+    tuple_structs: HashSet<&'a str>,
+
+    /// The values of every constant.
+    consts: HashMap<Name, i32>,
+
+    /// The locals of the function currently being executed.
+    locals: SymbolTable<'a, Value>,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Create a new interpreter for the provided AST.
+    pub fn new(ast: &'a File) -> Self {
+        let mut functions = HashMap::new();
+        let mut methods: HashMap<_, HashMap<_, _>> = HashMap::new();
+        let mut tuple_structs = HashSet::new();
+
+        for item in &ast.items {
+            match item {
+                Item::Fn(item_fn) => {
+                    functions.insert(item_fn.ident.repr.as_str(), item_fn);
+                }
+
+                Item::Impl(item_impl) => {
+                    for ImplItem::Fn(impl_item_fn) in &item_impl.items {
+                        methods
+                            .entry(item_impl.ident.repr.as_str())
+                            .or_default()
+                            .insert(impl_item_fn.ident.repr.as_str(), impl_item_fn);
+                    }
+                }
+
+                Item::Struct(item_struct) => {
+                    if let Fields::Unnamed(_) = item_struct.fields {
+                        tuple_structs.insert(item_struct.ident.repr.as_str());
+                    }
+                }
+
+                Item::Const(_) => {}
+            }
+        }
+
+        Interpreter {
+            functions,
+            methods,
+            tuple_structs,
+            consts: eval_consts(ast),
+            locals: SymbolTable::new(),
+        }
+    }
+
+    /// Run the `main` function and return the exit code of the program.
+    pub fn run(&mut self) -> Result<i32, InterpError> {
+        let main = match self.functions.get("main") {
+            Some(main) => *main,
+            None => {
+                return Err(InterpError {
+                    reason: String::from("Could not find the main function"),
+                    span: None,
+                })
+            }
+        };
+
+        match self.call(Vec::new(), &main.body, Vec::new()) {
+            // Exiting skips the rest of the program, no matter how deep inside it the call is
+            Err(Unwind::Exit(code)) => Ok(code),
+
+            result => match settle(result)? {
+                Value::Int(code) => Ok(code),
+
+                // Falling off the end of `main` results in an exit code of zero
+                _ => Ok(0),
+            },
+        }
+    }
+
+    /// Evaluate a block as the body of a function without parameters and return its value. Since the block isn't a
+    /// whole program, calling `exit` inside it is an error.
+    pub fn eval(&mut self, block: &'a Block) -> Result<Value, InterpError> {
+        self.eval_with(block, Vec::new())
+    }
+
+    /// Evaluate a block like `eval` does, but with the given locals already bound to their values.
+    pub fn eval_with(
+        &mut self,
+        block: &'a Block,
+        locals: Vec<(&'a str, Value)>,
+    ) -> Result<Value, InterpError> {
+        let (names, values) = locals.into_iter().unzip();
+
+        let result = self.call(names, block, values);
+        settle(result)
+    }
+
+    /// Call a function (or method) with the provided arguments and return its result. The arguments are bound to
+    /// the parameter names in order.
+    fn call(
+        &mut self,
+        params: Vec<&'a str>,
+        body: &'a Block,
+        args: Vec<Value>,
+    ) -> InterpResult<Value> {
+        let mut locals = SymbolTable::new();
+
+        for (param, arg) in params.into_iter().zip(args) {
+            locals.insert(param, arg);
+        }
+
+        // Every function gets its own set of locals
+        let caller = std::mem::replace(&mut self.locals, locals);
+        let result = self.eval_block(body);
+        self.locals = caller;
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(unwind) => Err(unwind),
+        }
+    }
+
+    /// Execute every statement in a block, followed by its tail expression.
+    fn eval_block(&mut self, block: &'a Block) -> InterpResult<Value> {
+        for stmt in &block.stmts {
+            self.eval_stmt(stmt)?;
+        }
+
+        match &block.tail {
+            Some(tail) => self.eval_expr(tail),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn eval_stmt(&mut self, stmt: &'a Stmt) -> InterpResult<()> {
+        match stmt {
+            Stmt::Local(local) => {
+                let value = self.eval_expr(&local.expr)?;
+                self.locals.insert(&local.ident.repr, value);
+
+                Ok(())
+            }
+
+            // The variable is updated in the scope it was declared in, even if that's outside the current block
+            Stmt::Assign(assign) => {
+                let value = self.eval_expr(&assign.expr)?;
+
+                match self.locals.update(&assign.ident.repr, value) {
+                    true => Ok(()),
+                    false => Err(InterpError {
+                        reason: format!("Cannot find '{}' in this scope", assign.ident.repr),
+                        span: Some(assign.ident.span.clone()),
+                    }
+                    .into()),
+                }
+            }
+
+            Stmt::Expr(expr) => {
+                self.eval_expr(expr)?;
+                Ok(())
+            }
+
+            Stmt::Return(ret) => {
+                let value = match &ret.expr {
+                    Some(expr) => self.eval_expr(expr)?,
+                    None => Value::Unit,
+                };
+
+                Err(Unwind::Return(value))
+            }
+
+            Stmt::Break(brk) => {
+                let value = match &brk.expr {
+                    Some(expr) => self.eval_expr(expr)?,
+                    None => Value::Unit,
+                };
+
+                Err(Unwind::Break(brk.label.name, value))
+            }
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &'a Expr) -> InterpResult<Value> {
+        match expr {
+            Expr::Binary(expr_bin) => self.eval_expr_bin(expr_bin),
+            Expr::Call(expr_call) => self.eval_expr_call(expr_call),
+            Expr::Struct(expr_struct) => self.eval_expr_struct(expr_struct),
+            Expr::Field(expr_field) => self.eval_expr_field(expr_field),
+            Expr::MethodCall(expr_method_call) => self.eval_expr_method_call(expr_method_call),
+            Expr::Tuple(expr_tuple) => self.eval_expr_tuple(expr_tuple),
+            Expr::TupleIndex(expr_tuple_index) => self.eval_expr_tuple_index(expr_tuple_index),
+
+            // A `break` targeting this block ends it early, producing the value of the block
+            Expr::Block(expr_block) => {
+                self.locals.up();
+                let result = self.eval_block(&expr_block.block);
+                self.locals.down();
+
+                match (result, &expr_block.label) {
+                    (Err(Unwind::Break(name, value)), Some(label)) if name == label.name => {
+                        Ok(value)
+                    }
+                    (result, _) => result,
+                }
+            }
+
+            Expr::If(expr_if) => match self.eval_cond(&expr_if.cond)? {
+                true => {
+                    self.locals.up();
+                    let result = self.eval_block(&expr_if.then);
+                    self.locals.down();
+
+                    result
+                }
+                false => match &expr_if.els {
+                    Some(els) => self.eval_expr(els),
+                    None => Ok(Value::Unit),
+                },
+            },
+
+            // Type checking makes sure there's a wildcard arm, so some arm always matches
+            Expr::Match(expr_match) => {
+                let value = self.eval_int(&expr_match.scrutinee)?;
+
+                let arm = expr_match.arms.iter().find(|arm| match &arm.pat {
+                    Pat::Lit(lit_num) => lit_num.value == value,
+                    Pat::Wild(_) => true,
+                });
+
+                match arm {
+                    Some(arm) => self.eval_expr(&arm.body),
+                    None => Err(InterpError {
+                        reason: format!("No arm of this 'match' matches the value {value}"),
+                        span: Some(expr_match.span.clone()),
+                    }
+                    .into()),
+                }
+            }
+
+            // Values of every comparable type can be compared directly, field by field for structs, and integers can
+            // be ordered as well
+            Expr::Cmp(expr_cmp) => {
+                let lhs = self.eval_expr(&expr_cmp.lhs)?;
+                let rhs = self.eval_expr(&expr_cmp.rhs)?;
+
+                Ok(Value::Bool(expr_cmp.op.kind.apply(&lhs, &rhs)))
+            }
+
+            // Values can't be modified, so a reference behaves exactly like the value it refers to
+            Expr::Ref(expr_ref) => self.eval_expr(&expr_ref.expr),
+
+            Expr::Unary(expr_unary) => match expr_unary.op.kind {
+                UnaryOpKind::Negate => Ok(Value::Int(
+                    expr_unary.op.kind.apply(self.eval_int(&expr_unary.expr)?),
+                )),
+                UnaryOpKind::Not => Ok(Value::Bool(!self.eval_cond(&expr_unary.expr)?)),
+            },
+
+            Expr::Lit(expr_lit) => match expr_lit {
+                ExprLit::Num(lit_num) => Ok(Value::Int(lit_num.value)),
+                ExprLit::Bool(lit_bool) => Ok(Value::Bool(lit_bool.value)),
+            },
+
+            Expr::Ident(ident) => match self.locals.find(&ident.repr) {
+                Some(value) => Ok(value),
+                None => match self.consts.get(&ident.repr) {
+                    Some(value) => Ok(Value::Int(*value)),
+                    None => Err(InterpError {
+                        reason: format!("Cannot find '{}' in this scope", ident.repr),
+                        span: Some(ident.span.clone()),
+                    }
+                    .into()),
+                },
+            },
+        }
+    }
+
+    fn eval_expr_bin(&mut self, expr_bin: &'a ExprBin) -> InterpResult<Value> {
+        let lhs = self.eval_int(&expr_bin.lhs)?;
+        let rhs = self.eval_int(&expr_bin.rhs)?;
+
+        match expr_bin.op.kind.apply(lhs, rhs) {
+            Some(value) => Ok(Value::Int(value)),
+            None => Err(InterpError {
+                reason: match expr_bin.op.kind {
+                    OpKind::Remainder => {
+                        String::from("Attempt to calculate the remainder with a divisor of zero")
+                    }
+                    _ => String::from("Attempt to divide by zero"),
+                },
+                span: Some(expr_bin.span.clone()),
+            }
+            .into()),
+        }
+    }
+
+    fn eval_expr_call(&mut self, expr_call: &'a ExprCall) -> InterpResult<Value> {
+        match expr_call {
+            ExprCall::Fn(call_fn) => self.eval_call_fn(call_fn),
+        }
+    }
+
+    fn eval_call_fn(&mut self, call_fn: &'a CallFn) -> InterpResult<Value> {
+        let item_fn = match self.functions.get(call_fn.ident.repr.as_str()) {
+            Some(item_fn) => *item_fn,
+            None if self.tuple_structs.contains(call_fn.ident.repr.as_str()) => {
+                return self.eval_tuple_struct(call_fn)
+            }
+            None => return self.eval_builtin(call_fn),
+        };
+
+        let mut args = Vec::new();
+        for arg in &call_fn.args.args {
+            args.push(self.eval_expr(arg)?);
+        }
+
+        let params = item_fn
+            .params
+            .params
+            .iter()
+            .map(|param| param.ident.repr.as_str())
+            .collect();
+
+        self.call(params, &item_fn.body, args)
+    }
+
+    /// Build a tuple struct, whose fields are named by their index.
+    fn eval_tuple_struct(&mut self, call_fn: &'a CallFn) -> InterpResult<Value> {
+        let mut fields = HashMap::new();
+
+        for (index, arg) in call_fn.args.args.iter().enumerate() {
+            fields.insert(Name::intern(&index.to_string()), self.eval_expr(arg)?);
+        }
+
+        Ok(Value::Struct(call_fn.ident.repr, fields))
+    }
+
+    /// Call a function from the prelude.
+    fn eval_builtin(&mut self, call_fn: &'a CallFn) -> InterpResult<Value> {
+        let Some(builtin) = Builtin::find(&call_fn.ident.repr) else {
+            return Err(InterpError {
+                reason: format!("Undefined function '{}'", call_fn.ident.repr),
+                span: Some(call_fn.ident.span.clone()),
+            }
+            .into());
+        };
+
+        let mut args = Vec::new();
+        for arg in &call_fn.args.args {
+            args.push(match self.eval_expr(arg)? {
+                Value::Int(value) => value,
+                Value::Bool(value) => value as i32,
+                _ => {
+                    return Err(InterpError {
+                        reason: String::from("Expected an integer or a boolean value"),
+                        span: Some(arg.span().clone()),
+                    }
+                    .into())
+                }
+            });
+        }
+
+        if args.len() != builtin.params.len() {
+            return Err(InterpError {
+                reason: format!(
+                    "Function '{}' takes {} arguments but {} were supplied",
+                    builtin.name,
+                    builtin.params.len(),
+                    args.len()
+                ),
+                span: Some(call_fn.span.clone()),
+            }
+            .into());
+        }
+
+        match (builtin.eval)(&args) {
+            Ok(Some(value)) => Ok(Value::Int(value)),
+            Ok(None) => Ok(Value::Unit),
+            Err(Halt::Exit(code)) => Err(Unwind::Exit(code)),
+            Err(Halt::Trap(reason)) => Err(InterpError {
+                reason,
+                span: Some(call_fn.span.clone()),
+            }
+            .into()),
+        }
+    }
+
+    fn eval_expr_method_call(
+        &mut self,
+        expr_method_call: &'a ExprMethodCall,
+    ) -> InterpResult<Value> {
+        let receiver = self.eval_expr(&expr_method_call.receiver)?;
+        let ident = &expr_method_call.ident;
+
+        let method = match &receiver {
+            Value::Struct(name, _) => self
+                .methods
+                .get(name.as_str())
+                .and_then(|methods| methods.get(ident.repr.as_str()))
+                .copied(),
+
+            _ => None,
+        };
+
+        let method = match method {
+            Some(method) => method,
+            None => {
+                return Err(InterpError {
+                    reason: format!("Undefined method '{}'", ident.repr),
+                    span: Some(ident.span.clone()),
+                }
+                .into())
+            }
+        };
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+
+        // The receiver is passed as `self`, ahead of the other arguments
+        if method.params.receiver.is_some() {
+            params.push("self");
+            args.push(receiver);
+        }
+
+        for param in &method.params.params {
+            params.push(param.ident.repr.as_str());
+        }
+
+        for arg in &expr_method_call.args.args {
+            args.push(self.eval_expr(arg)?);
+        }
+
+        self.call(params, &method.body, args)
+    }
+
+    fn eval_expr_field(&mut self, expr_field: &'a ExprField) -> InterpResult<Value> {
+        let ident = &expr_field.ident;
+
+        match self.eval_expr(&expr_field.expr)? {
+            Value::Struct(_, mut fields) => match fields.remove(&ident.repr) {
+                Some(value) => Ok(value),
+                None => Err(InterpError {
+                    reason: format!("No field '{}'", ident.repr),
+                    span: Some(ident.span.clone()),
+                }
+                .into()),
+            },
+
+            _ => Err(InterpError {
+                reason: String::from("Expected a struct value"),
+                span: Some(expr_field.expr.span().clone()),
+            }
+            .into()),
+        }
+    }
+
+    fn eval_expr_struct(&mut self, expr_struct: &'a ExprStruct) -> InterpResult<Value> {
+        let mut fields = HashMap::new();
+
+        for arg in &expr_struct.args.args {
+            fields.insert(arg.ident.repr, self.eval_expr(&arg.expr)?);
+        }
+
+        Ok(Value::Struct(expr_struct.ident.repr, fields))
+    }
+
+    fn eval_expr_tuple(&mut self, expr_tuple: &'a ExprTuple) -> InterpResult<Value> {
+        if expr_tuple.elems.is_empty() {
+            return Ok(Value::Unit);
+        }
+
+        let mut elems = Vec::new();
+        for elem in &expr_tuple.elems {
+            elems.push(self.eval_expr(elem)?);
+        }
+
+        Ok(Value::Tuple(elems))
+    }
+
+    fn eval_expr_tuple_index(
+        &mut self,
+        expr_tuple_index: &'a ExprTupleIndex,
+    ) -> InterpResult<Value> {
+        let index = &expr_tuple_index.index;
+
+        match self.eval_expr(&expr_tuple_index.expr)? {
+            Value::Tuple(mut elems) if (index.value as usize) < elems.len() => {
+                Ok(elems.swap_remove(index.value as usize))
+            }
+
+            Value::Struct(_, mut fields) => {
+                match fields.remove(&Name::intern(&index.value.to_string())) {
+                    Some(value) => Ok(value),
+                    None => Err(InterpError {
+                        reason: format!("Tuple index {} is out of range", index.value),
+                        span: Some(index.span.clone()),
+                    }
+                    .into()),
+                }
+            }
+
+            _ => Err(InterpError {
+                reason: format!("Tuple index {} is out of range", index.value),
+                span: Some(index.span.clone()),
+            }
+            .into()),
+        }
+    }
+
+    /// Evaluate the condition of an `if`, which must produce a boolean. Integers aren't treated as booleans.
+    fn eval_cond(&mut self, expr: &'a Expr) -> InterpResult<bool> {
+        match self.eval_expr(expr)? {
+            Value::Bool(value) => Ok(value),
+            _ => Err(InterpError {
+                reason: String::from("Expected a boolean value"),
+                span: Some(expr.span().clone()),
+            }
+            .into()),
+        }
+    }
+
+    /// Evaluate an expression that must produce an integer.
+    fn eval_int(&mut self, expr: &'a Expr) -> InterpResult<i32> {
+        match self.eval_expr(expr)? {
+            Value::Int(value) => Ok(value),
+            _ => Err(InterpError {
+                reason: String::from("Expected an integer value"),
+                span: Some(expr.span().clone()),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    /// Run an entire program, which must parse, returning its exit code.
+    fn run(src: &str) -> i32 {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        match Interpreter::new(&file).run() {
+            Ok(code) => code,
+            Err(err) => panic!("the program should run, but: {}", err.reason),
+        }
+    }
+
+    /// Run an entire program, which must parse, returning the runtime error that stops it.
+    fn fail(src: &str) -> String {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        match Interpreter::new(&file).run() {
+            Ok(_) => panic!("the program should fail"),
+            Err(err) => err.reason,
+        }
+    }
+
+    #[test]
+    fn division_truncates_and_the_remainder_takes_the_sign_of_the_dividend() {
+        assert_eq!(run("fn main() -> i32 { -7 / 2 }"), -3);
+        assert_eq!(run("fn main() -> i32 { -7 % 2 }"), -1);
+        assert_eq!(run("fn main() -> i32 { 7 % -2 }"), 1);
+    }
+
+    #[test]
+    fn dividing_by_zero_traps() {
+        assert_eq!(
+            fail("fn main() -> i32 { let zero = 0; 1 / zero }"),
+            "Attempt to divide by zero"
+        );
+        assert_eq!(
+            fail("fn main() -> i32 { 1 % 0 }"),
+            "Attempt to calculate the remainder with a divisor of zero"
+        );
+    }
+
+    #[test]
+    fn integers_are_ordered() {
+        assert_eq!(run("fn main() -> i32 { if 1 < 2 { 1 } else { 0 } }"), 1);
+        assert_eq!(run("fn main() -> i32 { if 2 <= 2 { 1 } else { 0 } }"), 1);
+        assert_eq!(run("fn main() -> i32 { if 2 > 3 { 1 } else { 0 } }"), 0);
+        assert_eq!(run("fn main() -> i32 { if -1 >= 0 { 1 } else { 0 } }"), 0);
+    }
+
+    #[test]
+    fn boolean_literals_select_branches() {
+        assert_eq!(run("fn main() -> i32 { if true { 1 } else { 0 } }"), 1);
+        assert_eq!(
+            run("fn main() -> i32 { let b = false; if b == false { 2 } else { 3 } }"),
+            2
+        );
+    }
+
+    const POINTS: &str = "
+        struct Point { x: i32, y: i32 }
+
+        fn main() -> i32 {
+            let a = Point { x: 1, y: 2 };
+            let b = Point { y: 2, x: 1 };
+            let c = Point { x: 1, y: 3 };
+            COND
+        }
+    ";
+
+    #[test]
+    fn structs_are_compared_field_by_field() {
+        let cond =
+            |cond: &str| run(&POINTS.replace("COND", &format!("if {cond} {{ 1 }} else {{ 0 }}")));
+
+        assert_eq!(cond("a == b"), 1);
+        assert_eq!(cond("a == c"), 0);
+        assert_eq!(cond("a != c"), 1);
+        assert_eq!(cond("a != b"), 0);
+    }
+
+    #[test]
+    fn an_i32_main_that_runs_off_its_end_exits_with_zero() {
+        assert_eq!(run("fn main() -> i32 {}"), 0);
+        assert_eq!(run("fn main() -> i32 { let x = 5; }"), 0);
+    }
+
+    #[test]
+    fn assertions_trap_when_their_condition_is_false() {
+        assert_eq!(run("fn main() -> i32 { assert(1 < 2); 7 }"), 7);
+
+        let file = parse_str("fn main() -> i32 { assert(2 < 1); 7 }")
+            .ok()
+            .unwrap();
+        match Interpreter::new(&file).run() {
+            Ok(_) => panic!("the assertion should fail"),
+            Err(err) => assert_eq!(err.reason, "Assertion failed"),
+        }
+    }
+
+    #[test]
+    fn checked_multiplication_traps_on_overflow() {
+        assert_eq!(run("fn main() -> i32 { checked_mul(6, 7) }"), 42);
+
+        let file = parse_str("fn main() -> i32 { checked_mul(65536, 65536) }")
+            .ok()
+            .unwrap();
+        match Interpreter::new(&file).run() {
+            Ok(_) => panic!("the multiplication should overflow"),
+            Err(err) => assert_eq!(err.reason, "Arithmetic overflow in 'checked_mul'"),
+        }
+    }
+
+    #[test]
+    fn saturating_addition_stops_at_the_bounds() {
+        assert_eq!(run("fn main() -> i32 { saturating_add(2, 3) }"), 5);
+        assert_eq!(
+            run("fn main() -> i32 { saturating_add(2147483647, 1) }"),
+            i32::MAX
+        );
+        assert_eq!(
+            run("fn main() -> i32 { saturating_add(-2147483648, -1) }"),
+            i32::MIN
+        );
+    }
+
+    #[test]
+    fn min_and_max_pick_the_smaller_and_larger_argument() {
+        assert_eq!(run("fn main() -> i32 { max(3, 7) }"), 7);
+        assert_eq!(run("fn main() -> i32 { min(3, 7) }"), 3);
+        assert_eq!(run("fn main() -> i32 { min(max(-2, -5), 4) }"), -2);
+    }
+
+    #[test]
+    fn exit_stops_the_program_with_its_code() {
+        assert_eq!(run("fn main() -> i32 { exit(7); assert(false); 1 }"), 7);
+        assert_eq!(
+            run("fn quit() -> () { exit(7); }\nfn main() -> i32 { quit(); 1 / 0 }"),
+            7
+        );
+    }
+
+    #[test]
+    fn logical_not_flips_booleans() {
+        assert_eq!(run("fn main() -> i32 { if !false { 1 } else { 0 } }"), 1);
+        assert_eq!(run("fn main() -> i32 { if !!true { 1 } else { 0 } }"), 1);
+        assert_eq!(run("fn main() -> i32 { if !(1 == 1) { 1 } else { 0 } }"), 0);
+    }
+
+    #[test]
+    fn programs_exit_with_the_value_of_main() {
+        let cases = [
+            ("fn main() -> i32 { 1 + 2 }", 3),
+            ("fn main() -> i32 { let x = 4; x * x }", 16),
+            ("fn main() -> i32 { return 7; }", 7),
+            ("fn main() -> () {}", 0),
+            (
+                "fn double(x: i32) -> i32 { x + x }\nfn main() -> i32 { double(21) }",
+                42,
+            ),
+        ];
+
+        for (src, code) in cases {
+            assert_eq!(run(src), code, "{src}");
+        }
+    }
+
+    #[test]
+    fn breaking_out_of_a_labeled_block_yields_the_value() {
+        assert_eq!(
+            run("fn main() -> i32 { 'label: { if true { break 'label 5; } 1 } }"),
+            5
+        );
+        assert_eq!(
+            run("fn main() -> i32 { let x = 'outer: { 'inner: { break 'outer 2; } 3 }; x * 10 }"),
+            20
+        );
+    }
+
+    #[test]
+    fn tuple_structs_are_built_and_indexed_like_tuples() {
+        assert_eq!(
+            run("struct Pair(i32, i32);\nfn sum(p: Pair) -> i32 { p.0 + p.1 }\nfn main() -> i32 { sum(Pair(3, 4)) }"),
+            7
+        );
+    }
+}
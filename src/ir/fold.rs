@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use crate::ast::fold::Fold;
+use crate::ast::{
+    ExprBin, ExprBlock, ExprCall, ExprCmp, ExprField, ExprIf, ExprLit, ExprMatch, ExprMethodCall,
+    ExprRef, ExprStruct, ExprTuple, ExprTupleIndex, ExprUnary, File, Ident, Item, OpKind,
+};
+use crate::shared::{Diagnostic, Name};
+
+/// Computes the value of expressions made up entirely of integer literals and constants, producing `Ok(None)` for
+/// anything else (including a division by zero, which is left to trap at runtime). Unlike at runtime, arithmetic
+/// that overflows is reported as an error instead of wrapping. Programs containing such expressions are rejected,
+/// so a folded value always matches the value the interpreter would compute.
+pub struct ConstFolder<'c> {
+    /// The values of the constants that have been computed so far.
+    consts: &'c HashMap<Name, i32>,
+}
+
+impl<'c> ConstFolder<'c> {
+    /// Create a folder that can see the provided constants.
+    pub fn new(consts: &'c HashMap<Name, i32>) -> Self {
+        ConstFolder { consts }
+    }
+}
+
+/// Compute the value of every constant in the program. Constants may refer to each other in any order, so they're
+/// computed in rounds until no more values can be found. Constants whose value can't be computed (including those
+/// that depend on themselves) are left out, and reported during type checking.
+pub fn eval_consts(file: &File) -> HashMap<Name, i32> {
+    let mut consts = HashMap::new();
+    let mut pending = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Const(item_const) => Some(item_const),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    loop {
+        let before = pending.len();
+
+        pending.retain(
+            |item_const| match ConstFolder::new(&consts).fold_expr(&item_const.expr) {
+                Ok(Some(value)) => {
+                    consts.insert(item_const.ident.repr, value);
+                    false
+                }
+                _ => true,
+            },
+        );
+
+        if pending.len() == before {
+            return consts;
+        }
+    }
+}
+
+impl<'a> Fold<'a> for ConstFolder<'_> {
+    type Output = Result<Option<i32>, Diagnostic>;
+
+    fn fold_expr_bin(&mut self, expr_bin: &'a ExprBin) -> Self::Output {
+        let lhs = self.fold_expr(&expr_bin.lhs)?;
+        let rhs = self.fold_expr(&expr_bin.rhs)?;
+
+        let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+            return Ok(None);
+        };
+
+        match expr_bin.op.kind {
+            OpKind::Divide | OpKind::Remainder if rhs == 0 => Ok(None),
+            _ => match expr_bin.op.kind.checked_apply(lhs, rhs) {
+                Some(value) => Ok(Some(value)),
+                None => Err(
+                    Diagnostic::error(String::from("This arithmetic operation overflows"))
+                        .with_span(expr_bin.span.clone())
+                        .with_note(format!(
+                            "The result doesn't fit in an 'i32', which ranges from {} to {}",
+                            i32::MIN,
+                            i32::MAX
+                        )),
+                ),
+            },
+        }
+    }
+
+    fn fold_expr_call(&mut self, _: &'a ExprCall) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_lit(&mut self, expr_lit: &'a ExprLit) -> Self::Output {
+        match expr_lit {
+            ExprLit::Num(lit_num) => Ok(Some(lit_num.value)),
+
+            // Booleans aren't integers, just like the result of a comparison
+            ExprLit::Bool(_) => Ok(None),
+        }
+    }
+
+    fn fold_expr_struct(&mut self, _: &'a ExprStruct) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_ident(&mut self, ident: &'a Ident) -> Self::Output {
+        Ok(self.consts.get(&ident.repr).copied())
+    }
+
+    fn fold_expr_field(&mut self, _: &'a ExprField) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_method_call(&mut self, _: &'a ExprMethodCall) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_tuple(&mut self, _: &'a ExprTuple) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_tuple_index(&mut self, _: &'a ExprTupleIndex) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_ref(&mut self, _: &'a ExprRef) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_unary(&mut self, expr_unary: &'a ExprUnary) -> Self::Output {
+        let Some(value) = self.fold_expr(&expr_unary.expr)? else {
+            return Ok(None);
+        };
+
+        match expr_unary.op.kind.checked_apply(value) {
+            Some(value) => Ok(Some(value)),
+            None => Err(
+                Diagnostic::error(String::from("This arithmetic operation overflows"))
+                    .with_span(expr_unary.span.clone())
+                    .with_note(format!(
+                        "The result doesn't fit in an 'i32', which ranges from {} to {}",
+                        i32::MIN,
+                        i32::MAX
+                    )),
+            ),
+        }
+    }
+
+    fn fold_expr_block(&mut self, _: &'a ExprBlock) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_if(&mut self, _: &'a ExprIf) -> Self::Output {
+        Ok(None)
+    }
+
+    /// Comparisons produce a `bool`, which isn't an integer.
+    fn fold_expr_cmp(&mut self, _: &'a ExprCmp) -> Self::Output {
+        Ok(None)
+    }
+
+    fn fold_expr_match(&mut self, _: &'a ExprMatch) -> Self::Output {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    /// Fold an expression, written as the tail of `main`, without any constants in scope.
+    fn try_fold(src: &str) -> Result<Option<i32>, Diagnostic> {
+        let file = match parse_str(&format!("fn main() -> i32 {{ {src} }}")) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let tail = match &file.items[0] {
+            Item::Fn(item_fn) => item_fn.body.tail.as_ref().expect("main should have a tail"),
+            _ => panic!("the program should be a single function"),
+        };
+
+        ConstFolder::new(&HashMap::new()).fold_expr(tail)
+    }
+
+    /// Fold an expression that should be accepted.
+    fn fold(src: &str) -> Option<i32> {
+        match try_fold(src) {
+            Ok(value) => value,
+            Err(err) => panic!("the expression should fold, but: {}", err.reason),
+        }
+    }
+
+    /// Fold an expression that should be rejected.
+    fn reject(src: &str) -> Diagnostic {
+        match try_fold(src) {
+            Ok(_) => panic!("the expression should be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn division_truncates_and_the_remainder_takes_the_sign_of_the_dividend() {
+        assert_eq!(fold("-7 / 2"), Some(-3));
+        assert_eq!(fold("-7 % 2"), Some(-1));
+        assert_eq!(fold("7 % -2"), Some(1));
+        assert_eq!(fold("7 / 2 * 2 + 7 % 2"), Some(7));
+    }
+
+    #[test]
+    fn dividing_by_zero_is_left_for_runtime() {
+        assert_eq!(fold("1 / 0"), None);
+        assert_eq!(fold("1 % 0"), None);
+    }
+
+    #[test]
+    fn doubled_negation_folds_to_its_operand() {
+        assert_eq!(fold("- - 5"), Some(5));
+        assert_eq!(fold("--5 + 1"), Some(6));
+    }
+
+    #[test]
+    fn logical_not_is_left_for_runtime() {
+        assert_eq!(fold("!!true"), None);
+    }
+
+    #[test]
+    fn overflowing_arithmetic_is_rejected() {
+        let err = reject("2000000000 + 2000000000");
+        assert_eq!(err.reason, "This arithmetic operation overflows");
+        assert_eq!(
+            err.notes,
+            ["The result doesn't fit in an 'i32', which ranges from -2147483648 to 2147483647"]
+        );
+
+        let span = err.span.expect("the error should have a span");
+        assert_eq!((span.start.column, span.end.column), (20, 42));
+    }
+}
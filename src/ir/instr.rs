@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use super::Index;
 
 /// Represents a label, which identifies the start of a chunk of code. Labels are used for many purposes,
@@ -10,10 +12,17 @@ pub struct Label(pub Index);
 #[derive(Clone)]
 pub enum Addr {
     Name(Index),
-    Const(Index),
+    Const(Const),
     Temp(Index),
 }
 
+/// Represents a constant. Every kind of literal is interned into its own program-wide pool, so the index is only
+/// meaningful together with the kind of the constant. Integers are the only literals so far.
+#[derive(Clone)]
+pub enum Const {
+    Int(Index),
+}
+
 #[derive(Clone)]
 #[allow(dead_code)]
 pub enum Instr {
@@ -23,6 +32,13 @@ pub enum Instr {
     Param(ParamInstr),
     Call(CallInstr),
     Return(RetInstr),
+    CJump(CJumpInstr),
+    Jump(JumpInstr),
+    Nop(NopInstr),
+    Struct(StructInstr),
+    Tuple(TupleInstr),
+    Extract(ExtractInstr),
+    Field(FieldInstr),
 }
 
 impl Instr {
@@ -33,8 +49,16 @@ impl Instr {
             Instr::Unary(un) => &un.da,
             Instr::Copy(cop) => &cop.da,
             Instr::Call(call) => &call.da,
+            Instr::Struct(strct) => &strct.da,
+            Instr::Tuple(tuple) => &tuple.da,
+            Instr::Extract(extract) => &extract.da,
+            Instr::Field(field) => &field.da,
             Instr::Param(_) => panic!("Parameter instructions don't have a destination address!"),
             Instr::Return(_) => panic!("Return instructions don't have a destination address!"),
+            Instr::CJump(_) | Instr::Jump(_) => {
+                panic!("Jump instructions don't have a destination address!")
+            }
+            Instr::Nop(_) => panic!("No-op instructions don't have a destination address!"),
         }
     }
 
@@ -47,6 +71,13 @@ impl Instr {
             Instr::Call(call) => call.label = Some(label),
             Instr::Param(param) => param.label = Some(label),
             Instr::Return(ret) => ret.label = Some(label),
+            Instr::CJump(cjump) => cjump.label = Some(label),
+            Instr::Jump(jump) => jump.label = Some(label),
+            Instr::Nop(nop) => nop.label = Some(label),
+            Instr::Struct(strct) => strct.label = Some(label),
+            Instr::Tuple(tuple) => tuple.label = Some(label),
+            Instr::Extract(extract) => extract.label = Some(label),
+            Instr::Field(field) => field.label = Some(label),
         }
     }
 }
@@ -110,11 +141,48 @@ pub struct UnInstr {
     pub ad: Addr,
 }
 
-/// Represents an operator. This is different from the source level operator construct.
+/// Represents an operator. This is different from the source level operator construct. Comparison operators produce
+/// one if the comparison holds and zero otherwise, and the logical operators take and produce such values.
 #[derive(Clone)]
+#[allow(dead_code)]
 pub enum Op {
-    Plus, // +
-    Mult, // *
+    Plus,        // +
+    Minus,       // -
+    Mult,        // *
+    CheckedMult, // * (trapping if the product overflows)
+    Div,         // /
+    Rem,         // %
+    Lt,          // <
+    Le,          // <=
+    Gt,          // >
+    Ge,          // >=
+    Eq,          // ==
+    Ne,          // !=
+    And,         // &&
+    Or,          // ||
+    Neg,         // - (unary)
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Plus => write!(f, "+"),
+            Self::Minus => write!(f, "-"),
+            Self::Mult => write!(f, "*"),
+            Self::CheckedMult => write!(f, "*?"),
+            Self::Div => write!(f, "/"),
+            Self::Rem => write!(f, "%"),
+            Self::Lt => write!(f, "<"),
+            Self::Le => write!(f, "<="),
+            Self::Gt => write!(f, ">"),
+            Self::Ge => write!(f, ">="),
+            Self::Eq => write!(f, "=="),
+            Self::Ne => write!(f, "!="),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+            Self::Neg => write!(f, "-"),
+        }
+    }
 }
 
 /// Represents an instruction of the form `<name|temp> = <addr>`.
@@ -169,7 +237,7 @@ pub struct CallInstr {
     /// The function label.
     pub fl: Label,
 
-    /// The number of parameters.
+    /// The number of parameters, which is the length of the argument list at the call site.
     pub n: usize,
 }
 
@@ -183,3 +251,119 @@ impl CallInstr {
         }
     }
 }
+
+/// Represents an instruction of the form `if ad goto tl else el`, which jumps to `tl` if the address holds a
+/// non-zero value and to `el` otherwise.
+#[derive(Clone)]
+pub struct CJumpInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The address being tested.
+    pub ad: Addr,
+
+    /// The label jumped to when the address is non-zero.
+    pub tl: Label,
+
+    /// The label jumped to when the address is zero.
+    pub el: Label,
+}
+
+impl CJumpInstr {
+    pub fn new(ad: Addr, tl: Label, el: Label) -> Self {
+        CJumpInstr {
+            label: None,
+            ad,
+            tl,
+            el,
+        }
+    }
+}
+
+/// Represents an instruction of the form `goto tl`.
+#[derive(Clone)]
+pub struct JumpInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The label being jumped to.
+    pub tl: Label,
+}
+
+impl JumpInstr {
+    pub fn new(tl: Label) -> Self {
+        JumpInstr { label: None, tl }
+    }
+}
+
+/// Represents an instruction that does nothing. It's used to give a label to a point in the code that isn't
+/// followed by any other instruction yet, such as the end of a conditional.
+#[derive(Clone)]
+pub struct NopInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+}
+
+/// Represents an instruction of the form `<name|temp> = <struct> { <field>: <addr>, ... }`, which builds a struct
+/// value. Structs are passed around and returned by value, so choosing how aggregates are actually laid out and
+/// returned (in registers or through memory) is left to each backend.
+#[derive(Clone)]
+pub struct StructInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The destination address, which absolutely **cannot** be a constant.
+    pub da: Addr,
+
+    /// The name of the struct, interned in the string pool.
+    pub ty: Index,
+
+    /// The fields in the order they were written, each with its name interned in the string pool.
+    pub fields: Vec<(Index, Addr)>,
+}
+
+/// Represents an instruction of the form `<name|temp> = (<addr>, ...)`, which builds a tuple. Like structs, tuples
+/// are aggregates passed around by value.
+#[derive(Clone)]
+pub struct TupleInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The destination address, which absolutely **cannot** be a constant.
+    pub da: Addr,
+
+    /// The elements, in order.
+    pub elems: Vec<Addr>,
+}
+
+/// Represents an instruction of the form `<name|temp> = <addr>.<index>`, which reads one element of a tuple.
+#[derive(Clone)]
+pub struct ExtractInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The destination address, which absolutely **cannot** be a constant.
+    pub da: Addr,
+
+    /// The address of the tuple.
+    pub ad: Addr,
+
+    /// The index of the element being read.
+    pub index: usize,
+}
+
+/// Represents an instruction of the form `<name|temp> = <addr>.<field>`, which reads one field of a struct.
+#[derive(Clone)]
+pub struct FieldInstr {
+    /// The optional label.
+    pub label: Option<Label>,
+
+    /// The destination address, which absolutely **cannot** be a constant.
+    pub da: Addr,
+
+    /// The address of the struct.
+    pub ad: Addr,
+
+    /// The name of the field being read, interned in the string pool.
+    pub field: Index,
+}
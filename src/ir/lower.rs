@@ -1,21 +1,22 @@
+use crate::ast::fold::Fold;
 use crate::ast::visitor::*;
 use crate::ast::*;
+use crate::ir::fold::{eval_consts, ConstFolder};
 use crate::ir::instr::*;
-use crate::shared::{Index, Pool};
+use crate::resolution::Type;
+use crate::sema::typeck::Types;
+use crate::shared::{Index, Mapper, Name, Pool, PRELUDE};
+use std::collections::HashMap;
 
-use super::mapper::Mapper;
 use super::IRRoot;
 
-/// Groups pools for various literals into one central pool.
+/// Groups pools for various literals into one central pool. A single pool is shared by every function in the
+/// program, so each distinct literal is interned exactly once.
 #[derive(Clone)]
-#[allow(dead_code)]
 pub struct LoweringPool<'a> {
-    /// The integer interner.
+    /// The integer interner. Booleans are lowered to `0` and `1`, so they're interned here as well.
     pub integers: Pool<i32>,
 
-    /// The boolean interner.
-    pub booleans: Pool<bool>,
-
     /// The string interner.
     pub strings: Pool<&'a str>,
 }
@@ -25,7 +26,6 @@ impl LoweringPool<'_> {
     pub fn new() -> Self {
         LoweringPool {
             integers: Pool::new(),
-            booleans: Pool::new(),
             strings: Pool::new(),
         }
     }
@@ -36,41 +36,94 @@ pub struct LoweringEngine<'a> {
     /// The source abstract syntax tree.
     ast: &'a File,
 
+    /// The types of the expressions in the tree, computed while type checking it.
+    types: &'a Types<'a>,
+
     /// The generated instructions.
     instrs: Vec<Instr>,
 
-    /// Map from names to their indices.
+    /// Map from local names to their indices. Every function gets its own scope, which is dropped once the function
+    /// has been lowered.
     name_map: Mapper<'a>,
 
-    /// Map from functions to their labels.
+    /// Map from functions to their labels. This map is never scoped, so every label is visible from every function
+    /// (including those defined earlier in the program).
     fn_map: Mapper<'a>,
 
     /// The lowering pool.
     pool: LoweringPool<'a>,
 
+    /// The values of every constant, which are substituted wherever the constant is used.
+    consts: HashMap<Name, i32>,
+
+    /// The labeled blocks enclosing the current expression, innermost last, along with the address that holds the
+    /// value of each block and the label that follows it.
+    blocks: Vec<(Name, Addr, Label)>,
+
+    /// The struct implemented by the enclosing `impl` block, whose methods are being lowered.
+    receiver: Option<Name>,
+
+    /// Whether the function being lowered is a `main` declared to return `i32`, which exits with code zero wherever
+    /// it returns `()` instead (just like type checking and the interpreter allow).
+    exit: bool,
+
     /// The next available temporary address.
     next_temp: Index,
+
+    /// The next available label. Labels for branches are handed out after every function has received one.
+    next_label: Index,
 }
 
 impl<'a> LoweringEngine<'a> {
-    /// Create a new generator instance.
-    pub fn new(ast: &'a File) -> Self {
+    /// Create a new generator instance. Only programs that type check can be lowered, and `types` must be the result
+    /// of type checking `ast`.
+    pub fn new(ast: &'a File, types: &'a Types<'a>) -> Self {
         LoweringEngine {
             ast,
+            types,
             instrs: Vec::new(),
             name_map: Mapper::new(),
             fn_map: Mapper::new(),
             pool: LoweringPool::new(),
+            consts: eval_consts(ast),
+            blocks: Vec::new(),
+            receiver: None,
+            exit: false,
             next_temp: 0,
+            next_label: 0,
         }
     }
 
     /// Generate IR for the provided AST.
-    pub fn lower(&mut self) -> IRRoot {
+    pub fn lower(&mut self) -> IRRoot<'a> {
+        // Give every function a label up front, so calls to functions defined further down can be lowered
+        for item in &self.ast.items {
+            match item {
+                Item::Fn(item_fn) => {
+                    self.fn_map.insert(&item_fn.ident.repr);
+                }
+
+                Item::Impl(item_impl) => {
+                    for ImplItem::Fn(impl_item_fn) in &item_impl.items {
+                        self.fn_map
+                            .insert(method_name(item_impl.ident.repr, &impl_item_fn.ident));
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        // Builtins are called by label like any other function, but their bodies are left to the target
+        for builtin in PRELUDE {
+            self.fn_map.insert(builtin.name);
+        }
+
+        self.next_label = self.fn_map.next;
         self.visit_file(self.ast);
 
         IRRoot {
-            last_label: self.fn_map.next - 1,
+            last_label: self.next_label.saturating_sub(1),
             interner: self.pool.clone(),
             instrs: self.instrs.clone(),
         }
@@ -80,6 +133,17 @@ impl<'a> LoweringEngine<'a> {
     fn process_expr(&mut self, expr: &'a Expr) -> Index {
         match expr {
             Expr::Binary(expr_bin) => {
+                // Binary expressions made up entirely of literals are replaced by their value
+                if let Ok(Some(value)) = ConstFolder::new(&self.consts).fold_expr_bin(expr_bin) {
+                    let index = self.pool.integers.insert(value);
+
+                    let da = Addr::Temp(self.temp());
+                    let ad = Addr::Const(Const::Int(index));
+
+                    self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+                    return self.instrs.len() - 1;
+                }
+
                 // Generate an instruction for the left, getting its index
                 let li = self.process_expr(&expr_bin.lhs);
 
@@ -91,7 +155,10 @@ impl<'a> LoweringEngine<'a> {
 
                 let op = match expr_bin.op.kind {
                     OpKind::Add => Op::Plus,
+                    OpKind::Subtract => Op::Minus,
                     OpKind::Multiply => Op::Mult,
+                    OpKind::Divide => Op::Div,
+                    OpKind::Remainder => Op::Rem,
                 };
 
                 let la = self.instrs[li].da().clone();
@@ -102,7 +169,93 @@ impl<'a> LoweringEngine<'a> {
                 self.instrs.len() - 1
             }
 
+            Expr::Unary(expr_unary) => {
+                // Like binary expressions, `- -5` is replaced by its value
+                if let Ok(Some(value)) = ConstFolder::new(&self.consts).fold_expr_unary(expr_unary)
+                {
+                    let index = self.pool.integers.insert(value);
+
+                    let da = Addr::Temp(self.temp());
+                    let ad = Addr::Const(Const::Int(index));
+
+                    self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+                    return self.instrs.len() - 1;
+                }
+
+                let i = self.process_expr(&expr_unary.expr);
+                let ad = self.instrs[i].da().clone();
+                let da = Addr::Temp(self.temp());
+
+                let op = match expr_unary.op.kind {
+                    UnaryOpKind::Negate => Op::Neg,
+
+                    // Booleans are `0` or `1`, so `!b` is the same as `b == 0`
+                    UnaryOpKind::Not => {
+                        let za = Addr::Const(Const::Int(self.pool.integers.insert(0)));
+
+                        self.instrs
+                            .push(Instr::Binary(BinInstr::new(da, ad, Op::Eq, za)));
+                        return self.instrs.len() - 1;
+                    }
+                };
+
+                self.instrs.push(Instr::Unary(UnInstr {
+                    label: None,
+                    da,
+                    op,
+                    ad,
+                }));
+                self.instrs.len() - 1
+            }
+
             Expr::Call(expr_call) => match expr_call {
+                // `min` and `max` are simple enough to lower in place, as a comparison and a branch
+                ExprCall::Fn(expr_call_fn) if expr_call_fn.ident.repr == "min" => {
+                    self.lower_min_max(expr_call_fn, Op::Lt)
+                }
+                ExprCall::Fn(expr_call_fn) if expr_call_fn.ident.repr == "max" => {
+                    self.lower_min_max(expr_call_fn, Op::Gt)
+                }
+
+                // Targets check the product for overflow right where it's computed, so the call is lowered in place
+                ExprCall::Fn(expr_call_fn) if expr_call_fn.ident.repr == "checked_mul" => {
+                    let li = self.process_expr(&expr_call_fn.args.args[0]);
+                    let ri = self.process_expr(&expr_call_fn.args.args[1]);
+
+                    let la = self.instrs[li].da().clone();
+                    let ra = self.instrs[ri].da().clone();
+
+                    let da = Addr::Temp(self.temp());
+
+                    self.instrs
+                        .push(Instr::Binary(BinInstr::new(da, la, Op::CheckedMult, ra)));
+                    self.instrs.len() - 1
+                }
+
+                // A tuple struct is built just like a struct whose fields are named by their index
+                ExprCall::Fn(expr_call_fn) if self.constructs(expr, expr_call_fn) => {
+                    let mut fields = Vec::new();
+
+                    for (index, arg) in expr_call_fn.args.args.iter().enumerate() {
+                        let i = self.process_expr(arg);
+                        let ad = self.instrs[i].da().clone();
+
+                        let name = Name::intern(&index.to_string());
+                        fields.push((self.pool.strings.insert(name.as_str()), ad));
+                    }
+
+                    let da = Addr::Temp(self.temp());
+                    let ty = self.pool.strings.insert(expr_call_fn.ident.repr.as_str());
+
+                    self.instrs.push(Instr::Struct(StructInstr {
+                        label: None,
+                        da,
+                        ty,
+                        fields,
+                    }));
+                    self.instrs.len() - 1
+                }
+
                 ExprCall::Fn(expr_call_fn) => {
                     // First, we need to add a parameter instruction for every argument passed to this function
                     self.process_args(&expr_call_fn.args);
@@ -119,37 +272,234 @@ impl<'a> LoweringEngine<'a> {
             },
 
             Expr::Ident(ident) => {
-                let index = self.name_map.find(&ident.repr);
-
                 let da = Addr::Temp(self.temp());
-                let ad = Addr::Name(index);
+
+                let ad = match self.consts.get(&ident.repr) {
+                    Some(value) => Addr::Const(Const::Int(self.pool.integers.insert(*value))),
+                    None => Addr::Name(self.name_map.find(&ident.repr)),
+                };
 
                 self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
                 self.instrs.len() - 1
             }
 
-            Expr::Lit(expr_lit) => match expr_lit {
-                ExprLit::Num(lit_num) => {
-                    let index = self.pool.integers.insert(lit_num.value);
+            Expr::Lit(expr_lit) => {
+                // Booleans are represented by 0 and 1, just like the result of a comparison
+                let value = match expr_lit {
+                    ExprLit::Num(lit_num) => lit_num.value,
+                    ExprLit::Bool(lit_bool) => lit_bool.value as i32,
+                };
 
-                    let da = Addr::Temp(self.temp());
-                    let ad = Addr::Const(index);
+                let index = self.pool.integers.insert(value);
 
-                    self.instrs.push(Instr::Copy(CopyInstr {
-                        label: None,
-                        da,
-                        ad,
-                    }));
-                    self.instrs.len() - 1
+                let da = Addr::Temp(self.temp());
+                let ad = Addr::Const(Const::Int(index));
+
+                self.instrs.push(Instr::Copy(CopyInstr {
+                    label: None,
+                    da,
+                    ad,
+                }));
+                self.instrs.len() - 1
+            }
+
+            Expr::Struct(expr_struct) => {
+                let mut fields = Vec::new();
+
+                for arg in &expr_struct.args.args {
+                    let i = self.process_expr(&arg.expr);
+                    let ad = self.instrs[i].da().clone();
+
+                    fields.push((self.pool.strings.insert(arg.ident.repr.as_str()), ad));
                 }
-            },
+
+                let da = Addr::Temp(self.temp());
+                let ty = self.pool.strings.insert(expr_struct.ident.repr.as_str());
+
+                self.instrs.push(Instr::Struct(StructInstr {
+                    label: None,
+                    da,
+                    ty,
+                    fields,
+                }));
+                self.instrs.len() - 1
+            }
+
+            Expr::Tuple(expr_tuple) => {
+                let mut elems = Vec::new();
+
+                for elem in &expr_tuple.elems {
+                    let i = self.process_expr(elem);
+                    elems.push(self.instrs[i].da().clone());
+                }
+
+                let da = Addr::Temp(self.temp());
+
+                self.instrs.push(Instr::Tuple(TupleInstr {
+                    label: None,
+                    da,
+                    elems,
+                }));
+                self.instrs.len() - 1
+            }
+
+            Expr::TupleIndex(expr_tuple_index) => {
+                let i = self.process_expr(&expr_tuple_index.expr);
+                let ad = self.instrs[i].da().clone();
+
+                match self.types.of(&expr_tuple_index.expr) {
+                    // The fields of a tuple struct are named by their index
+                    Some(Type::Struct(_)) => {
+                        self.lower_field(&ad, &expr_tuple_index.index.value.to_string());
+                    }
+
+                    _ => {
+                        let da = Addr::Temp(self.temp());
+
+                        self.instrs.push(Instr::Extract(ExtractInstr {
+                            label: None,
+                            da,
+                            ad,
+                            index: expr_tuple_index.index.value as usize,
+                        }));
+                    }
+                }
+                self.instrs.len() - 1
+            }
+
+            Expr::Block(expr_block) => {
+                let da = Addr::Temp(self.temp());
+                let jl = self.label();
+
+                if let Some(label) = &expr_block.label {
+                    self.blocks.push((label.name, da.clone(), jl.clone()));
+                }
+
+                self.name_map.up();
+
+                for stmt in &expr_block.block.stmts {
+                    self.visit_stmt(stmt);
+                }
+
+                match &expr_block.block.tail {
+                    Some(tail) => {
+                        let i = self.process_expr(tail);
+                        let ad = self.instrs[i].da().clone();
+
+                        self.instrs
+                            .push(Instr::Copy(CopyInstr::new(da.clone(), ad)));
+                    }
+
+                    // A block that always exits early never runs off its end
+                    None => match expr_block.block.stmts.last() {
+                        Some(Stmt::Break(_) | Stmt::Return(_)) => {}
+                        _ => self.lower_unit(da.clone()),
+                    },
+                }
+
+                self.name_map.down();
+
+                if expr_block.label.is_some() {
+                    self.blocks.pop();
+                }
+
+                // Every `break` targeting this block jumps here, once it has stored the value of the block
+                let result = Addr::Temp(self.temp());
+                self.instrs.push(Instr::Copy(CopyInstr {
+                    label: Some(jl),
+                    da: result,
+                    ad: da,
+                }));
+                self.instrs.len() - 1
+            }
+
+            Expr::If(expr_if) => self.lower_if(expr_if),
+            Expr::Match(expr_match) => self.lower_match(expr_match),
+
+            Expr::Cmp(expr_cmp) => {
+                let li = self.process_expr(&expr_cmp.lhs);
+                let ri = self.process_expr(&expr_cmp.rhs);
+
+                let la = self.instrs[li].da().clone();
+                let ra = self.instrs[ri].da().clone();
+
+                let op = match expr_cmp.op.kind {
+                    CmpOpKind::Equal | CmpOpKind::NotEqual => {
+                        // Type checking records the type of both sides, which is the same
+                        let ty = self.types.of(&expr_cmp.lhs).unwrap();
+                        let ne = matches!(expr_cmp.op.kind, CmpOpKind::NotEqual);
+
+                        self.lower_eq(la, ra, ty, ne);
+                        return self.instrs.len() - 1;
+                    }
+
+                    CmpOpKind::Less => Op::Lt,
+                    CmpOpKind::LessEqual => Op::Le,
+                    CmpOpKind::Greater => Op::Gt,
+                    CmpOpKind::GreaterEqual => Op::Ge,
+                };
+
+                let da = Addr::Temp(self.temp());
+
+                self.instrs
+                    .push(Instr::Binary(BinInstr::new(da, la, op, ra)));
+                self.instrs.len() - 1
+            }
+
+            Expr::MethodCall(expr_method_call) => {
+                // Type checking only accepts method calls on structs
+                let strct = match self.types.of(&expr_method_call.receiver) {
+                    Some(Type::Struct(strct)) => Name::intern(strct.path()),
+                    _ => unreachable!("methods can only be called on structs"),
+                };
+
+                // The receiver is passed as the first parameter, ahead of the arguments
+                let i = self.process_expr(&expr_method_call.receiver);
+                let ad = self.instrs[i].da().clone();
+
+                self.instrs
+                    .push(Instr::Param(ParamInstr { label: None, ad }));
+                self.process_args(&expr_method_call.args);
+
+                let da = Addr::Temp(self.temp());
+                let fl = Label(
+                    self.fn_map
+                        .find(method_name(strct, &expr_method_call.ident)),
+                );
+
+                self.instrs.push(Instr::Call(CallInstr::new(
+                    da,
+                    fl,
+                    expr_method_call.args.len() + 1,
+                )));
+                self.instrs.len() - 1
+            }
+
+            Expr::Field(expr_field) => {
+                let i = self.process_expr(&expr_field.expr);
+                let ad = self.instrs[i].da().clone();
+
+                self.lower_field(&ad, &expr_field.ident.repr);
+                self.instrs.len() - 1
+            }
+
+            // Values can't be modified, so a reference is just a copy of the value it refers to
+            Expr::Ref(expr_ref) => {
+                let i = self.process_expr(&expr_ref.expr);
+                let ad = self.instrs[i].da().clone();
+
+                let da = Addr::Temp(self.temp());
+
+                self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+                self.instrs.len() - 1
+            }
         }
     }
 
     fn process_args(&mut self, args: &'a ArgList) {
         for arg in &args.args {
             // Generate an instruction for the expression, getting its index
-            let i = self.process_expr(&arg);
+            let i = self.process_expr(arg);
 
             // Get the destination address of this expression
             let ad = self.instrs[i].da().clone();
@@ -160,6 +510,370 @@ impl<'a> LoweringEngine<'a> {
         }
     }
 
+    /// Lower a comparison of two values of type `ty` for equality, or for inequality if `ne` is set, and return the
+    /// address holding the result. Tuples and structs are compared field by field: they're equal if every field is,
+    /// so the comparisons of the fields are combined with `&&` (or with `||`, since they differ if any field does).
+    fn lower_eq(&mut self, la: Addr, ra: Addr, ty: &Type, ne: bool) -> Addr {
+        let (op, join) = if ne {
+            (Op::Ne, Op::Or)
+        } else {
+            (Op::Eq, Op::And)
+        };
+
+        let mut fields = Vec::new();
+        match ty {
+            // A reference is a copy of the value it refers to
+            Type::Ref(ty) => return self.lower_eq(la, ra, ty, ne),
+
+            Type::Tuple(elems) => {
+                for (index, elem) in elems.iter().enumerate() {
+                    let lf = self.lower_extract(&la, index);
+                    let rf = self.lower_extract(&ra, index);
+
+                    fields.push(self.lower_eq(lf, rf, elem, ne));
+                }
+            }
+
+            // Fields are compared in the order of their names, so the same struct is always compared the same way
+            Type::Struct(strct) => {
+                let mut names: Vec<&String> = strct.fields.keys().collect();
+                names.sort();
+
+                for name in names {
+                    // Every field of a comparable struct has a known type
+                    let ty = self.types.field(strct, name).unwrap();
+
+                    let lf = self.lower_field(&la, name);
+                    let rf = self.lower_field(&ra, name);
+
+                    fields.push(self.lower_eq(lf, rf, &ty, ne));
+                }
+            }
+
+            _ => {
+                let da = Addr::Temp(self.temp());
+
+                self.instrs
+                    .push(Instr::Binary(BinInstr::new(da.clone(), la, op, ra)));
+                return da;
+            }
+        }
+
+        let mut fields = fields.into_iter();
+        let Some(mut result) = fields.next() else {
+            // Structs without fields are always equal
+            let value = self.pool.integers.insert(if ne { 0 } else { 1 });
+            let da = Addr::Temp(self.temp());
+
+            self.instrs.push(Instr::Copy(CopyInstr::new(
+                da.clone(),
+                Addr::Const(Const::Int(value)),
+            )));
+            return da;
+        };
+
+        for field in fields {
+            let da = Addr::Temp(self.temp());
+
+            self.instrs.push(Instr::Binary(BinInstr::new(
+                da.clone(),
+                result,
+                join.clone(),
+                field,
+            )));
+            result = da;
+        }
+
+        result
+    }
+
+    /// Read the element at `index` of the tuple in `ad` into a new temporary address.
+    fn lower_extract(&mut self, ad: &Addr, index: usize) -> Addr {
+        let da = Addr::Temp(self.temp());
+
+        self.instrs.push(Instr::Extract(ExtractInstr {
+            label: None,
+            da: da.clone(),
+            ad: ad.clone(),
+            index,
+        }));
+        da
+    }
+
+    /// Read the field `name` of the struct in `ad` into a new temporary address.
+    /// Whether a call builds a tuple struct instead of calling a function. Types and functions share one namespace,
+    /// so a call has the type of the struct it names only if it builds that struct.
+    fn constructs(&self, expr: &Expr, expr_call_fn: &CallFn) -> bool {
+        matches!(
+            self.types.of(expr),
+            Some(Type::Struct(strct)) if strct.is_tuple() && strct.path() == expr_call_fn.ident.repr.as_str()
+        )
+    }
+
+    fn lower_field(&mut self, ad: &Addr, name: &str) -> Addr {
+        let da = Addr::Temp(self.temp());
+        let field = self.pool.strings.insert(Name::intern(name).as_str());
+
+        self.instrs.push(Instr::Field(FieldInstr {
+            label: None,
+            da: da.clone(),
+            ad: ad.clone(),
+            field,
+        }));
+        da
+    }
+
+    /// Lower a conditional. The condition is evaluated into an address which the conditional jump tests, so a
+    /// non-zero value (such as the result of a comparison that holds) selects the `then` block. Both branches store
+    /// their value in the same address, which is copied once they join again.
+    fn lower_if(&mut self, expr_if: &'a ExprIf) -> Index {
+        let i = self.process_expr(&expr_if.cond);
+        let ad = self.instrs[i].da().clone();
+
+        let da = Addr::Temp(self.temp());
+
+        let tl = self.label();
+        let el = self.label();
+        let jl = self.label();
+
+        self.instrs
+            .push(Instr::CJump(CJumpInstr::new(ad, tl.clone(), el.clone())));
+
+        self.lower_branch(&expr_if.then, tl, da.clone());
+        self.instrs.push(Instr::Jump(JumpInstr::new(jl.clone())));
+
+        // The label needs an instruction to sit on, even if the branch turns out to be empty
+        self.instrs.push(Instr::Nop(NopInstr { label: Some(el) }));
+
+        match &expr_if.els {
+            Some(els) => {
+                let i = self.process_expr(els);
+                let ad = self.instrs[i].da().clone();
+
+                self.instrs
+                    .push(Instr::Copy(CopyInstr::new(da.clone(), ad)));
+            }
+            None => self.lower_unit(da.clone()),
+        }
+
+        let result = Addr::Temp(self.temp());
+        self.instrs.push(Instr::Copy(CopyInstr {
+            label: Some(jl),
+            da: result,
+            ad: da,
+        }));
+        self.instrs.len() - 1
+    }
+
+    /// Lower a call to `min` or `max`. The left argument is picked if comparing it with the right one using `op`
+    /// holds, and the right one otherwise, so ties go to the right argument.
+    fn lower_min_max(&mut self, call_fn: &'a CallFn, op: Op) -> Index {
+        let li = self.process_expr(&call_fn.args.args[0]);
+        let ri = self.process_expr(&call_fn.args.args[1]);
+
+        let la = self.instrs[li].da().clone();
+        let ra = self.instrs[ri].da().clone();
+
+        let ca = Addr::Temp(self.temp());
+        let da = Addr::Temp(self.temp());
+
+        let tl = self.label();
+        let el = self.label();
+        let jl = self.label();
+
+        self.instrs.push(Instr::Binary(BinInstr::new(
+            ca.clone(),
+            la.clone(),
+            op,
+            ra.clone(),
+        )));
+        self.instrs
+            .push(Instr::CJump(CJumpInstr::new(ca, tl.clone(), el.clone())));
+
+        self.instrs.push(Instr::Copy(CopyInstr {
+            label: Some(tl),
+            da: da.clone(),
+            ad: la,
+        }));
+        self.instrs.push(Instr::Jump(JumpInstr::new(jl.clone())));
+
+        self.instrs.push(Instr::Copy(CopyInstr {
+            label: Some(el),
+            da: da.clone(),
+            ad: ra,
+        }));
+
+        let result = Addr::Temp(self.temp());
+        self.instrs.push(Instr::Copy(CopyInstr {
+            label: Some(jl),
+            da: result,
+            ad: da,
+        }));
+        self.instrs.len() - 1
+    }
+
+    /// Lower a `match`. Each literal arm compares the scrutinee with its literal and jumps to its body if they're
+    /// equal, or on to the next arm otherwise. The wildcard arm matches anything, so the arms after it are never
+    /// reached and aren't lowered at all. Like the branches of a conditional, every arm stores its value in the same
+    /// address.
+    fn lower_match(&mut self, expr_match: &'a ExprMatch) -> Index {
+        let i = self.process_expr(&expr_match.scrutinee);
+        let sa = self.instrs[i].da().clone();
+
+        let da = Addr::Temp(self.temp());
+        let jl = self.label();
+
+        for arm in &expr_match.arms {
+            let el = match &arm.pat {
+                Pat::Lit(lit_num) => {
+                    let tl = self.label();
+                    let el = self.label();
+
+                    let ca = Addr::Temp(self.temp());
+                    let la = Addr::Const(Const::Int(self.pool.integers.insert(lit_num.value)));
+
+                    self.instrs.push(Instr::Binary(BinInstr::new(
+                        ca.clone(),
+                        sa.clone(),
+                        Op::Eq,
+                        la,
+                    )));
+                    self.instrs
+                        .push(Instr::CJump(CJumpInstr::new(ca, tl.clone(), el.clone())));
+                    self.instrs.push(Instr::Nop(NopInstr { label: Some(tl) }));
+
+                    Some(el)
+                }
+                Pat::Wild(_) => None,
+            };
+
+            let i = self.process_expr(&arm.body);
+            let ad = self.instrs[i].da().clone();
+
+            self.instrs
+                .push(Instr::Copy(CopyInstr::new(da.clone(), ad)));
+            self.instrs.push(Instr::Jump(JumpInstr::new(jl.clone())));
+
+            match el {
+                // The label needs an instruction to sit on, even if no arm follows
+                Some(el) => self.instrs.push(Instr::Nop(NopInstr { label: Some(el) })),
+                None => break,
+            }
+        }
+
+        let result = Addr::Temp(self.temp());
+        self.instrs.push(Instr::Copy(CopyInstr {
+            label: Some(jl),
+            da: result,
+            ad: da,
+        }));
+        self.instrs.len() - 1
+    }
+
+    /// Lower one branch of a conditional, starting at `label`, storing its value in `da`.
+    fn lower_branch(&mut self, block: &'a Block, label: Label, da: Addr) {
+        // The label needs an instruction to sit on, even if the branch turns out to be empty
+        self.instrs
+            .push(Instr::Nop(NopInstr { label: Some(label) }));
+
+        self.name_map.up();
+
+        for stmt in &block.stmts {
+            self.visit_stmt(stmt);
+        }
+
+        match &block.tail {
+            Some(tail) => {
+                let i = self.process_expr(tail);
+                let ad = self.instrs[i].da().clone();
+
+                self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+            }
+
+            // A branch that always exits early never runs off its end
+            None => match block.stmts.last() {
+                Some(Stmt::Break(_) | Stmt::Return(_)) => {}
+                _ => self.lower_unit(da),
+            },
+        }
+
+        self.name_map.down();
+    }
+
+    /// Store the unit value `()` in the given address.
+    fn lower_unit(&mut self, da: Addr) {
+        self.instrs.push(Instr::Tuple(TupleInstr {
+            label: None,
+            da,
+            elems: Vec::new(),
+        }));
+    }
+
+    /// Lower the body of a function, labeling its first instruction with `label`.
+    fn lower_fn(&mut self, label: Index, body: &'a Block) {
+        // Take note of the next available instruction index
+        let index = self.instrs.len();
+
+        // Process all the statements in this function declaration
+        for stmt in &body.stmts {
+            self.visit_stmt(stmt);
+        }
+
+        // The tail expression is returned from the function, and a function that runs off its end returns `()`
+        match &body.tail {
+            Some(tail) => self.lower_ret(Some(tail)),
+
+            None => match body.stmts.last() {
+                Some(Stmt::Return(_)) => {}
+                _ => self.lower_ret(None),
+            },
+        }
+
+        // Add the function label to the first instruction of the body, which always exists since every body ends by
+        // returning
+        self.instrs.get_mut(index).unwrap().set_label(Label(label));
+    }
+
+    /// Return the value of `expr` from the function being lowered, or `()` if there's no expression.
+    fn lower_ret(&mut self, expr: Option<&'a Expr>) {
+        let ad = expr.map(|expr| {
+            let i = self.process_expr(expr);
+            self.instrs[i].da().clone()
+        });
+
+        // Type checking records the type of every returned expression
+        let unit = expr.is_none_or(
+            |expr| matches!(self.types.of(expr), Some(Type::Primitive(ty)) if ty == "()"),
+        );
+
+        let ad = match ad {
+            _ if self.exit && unit => Addr::Const(Const::Int(self.pool.integers.insert(0))),
+            Some(ad) => ad,
+
+            None => {
+                let ad = Addr::Temp(self.temp());
+                self.lower_unit(ad.clone());
+                ad
+            }
+        };
+
+        self.instrs.push(Instr::Return(RetInstr::new(ad)));
+    }
+
+    /// Give every parameter a name, in order, like any other local.
+    fn bind_params(&mut self, params: &'a [Param]) {
+        for param in params {
+            self.name_map.insert(&param.ident.repr);
+        }
+    }
+
+    /// Get the next free label.
+    fn label(&mut self) -> Label {
+        let index = self.next_label;
+        self.next_label += 1;
+        Label(index)
+    }
+
     /// Get the next free temporary address.
     fn temp(&mut self) -> Index {
         let index = self.next_temp;
@@ -179,6 +893,15 @@ impl<'a> Visit<'a> for LoweringEngine<'a> {
                 self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
             }
 
+            // The new value goes to the same address the variable was given when it was declared
+            Stmt::Assign(assign) => {
+                let i = self.process_expr(&assign.expr);
+                let ad = self.instrs[i].da().clone();
+
+                let da = Addr::Name(self.name_map.find(&assign.ident.repr));
+                self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+            }
+
             Stmt::Expr(expr) => {
                 let i = self.process_expr(expr);
                 let ad = self.instrs[i].da().clone();
@@ -187,34 +910,400 @@ impl<'a> Visit<'a> for LoweringEngine<'a> {
                 self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
             }
 
-            Stmt::Return(ret) => {
-                let i = self.process_expr(&ret.expr);
-                let ad = self.instrs[i].da().clone();
+            Stmt::Return(ret) => self.lower_ret(ret.expr.as_ref()),
+
+            Stmt::Break(brk) => {
+                // Type checking makes sure the label belongs to an enclosing block
+                let (da, jl) = self
+                    .blocks
+                    .iter()
+                    .rev()
+                    .find(|(name, _, _)| *name == brk.label.name)
+                    .map(|(_, da, jl)| (da.clone(), jl.clone()))
+                    .unwrap();
+
+                match &brk.expr {
+                    Some(expr) => {
+                        let i = self.process_expr(expr);
+                        let ad = self.instrs[i].da().clone();
 
-                self.instrs.push(Instr::Return(RetInstr::new(ad)));
+                        self.instrs.push(Instr::Copy(CopyInstr::new(da, ad)));
+                    }
+
+                    None => self.lower_unit(da),
+                }
+
+                self.instrs.push(Instr::Jump(JumpInstr::new(jl)));
             }
         };
     }
 
     fn visit_item_fn(&mut self, item_fn: &'a crate::ast::ItemFn) {
-        let ident = &item_fn.ident.repr;
-
-        // Move the name mapper up a level
+        // Move the name mapper up a level (the function mapper stays put, since labels are global)
         self.name_map.up();
+        self.bind_params(&item_fn.params.params);
 
-        // Conver the function name into a label
-        let label = self.fn_map.insert(ident);
+        self.exit = item_fn.ident.repr == "main"
+            && matches!(&item_fn.ty, Ty::Named(ident) if ident.repr == "i32");
 
-        // Take note of the next available instruction index
-        let index = self.instrs.len();
+        let label = self.fn_map.find(&item_fn.ident.repr);
+        self.lower_fn(label, &item_fn.body);
+        self.exit = false;
 
-        // Process all the statements in this function declaration
-        self.visit_block(&item_fn.body);
+        // Move the name mapper down a level
+        self.name_map.down();
+    }
 
-        // Add the function label to the first instruction of the body
-        self.instrs.get_mut(index).unwrap().set_label(Label(label));
+    fn visit_item_impl(&mut self, item_impl: &'a crate::ast::ItemImpl) {
+        self.receiver = Some(item_impl.ident.repr);
+        visit_item_impl(self, item_impl);
+        self.receiver = None;
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'a ImplItemFn) {
+        self.name_map.up();
+
+        // The receiver gets a name like any other local
+        if impl_item_fn.params.receiver.is_some() {
+            self.name_map.insert("self");
+        }
+        self.bind_params(&impl_item_fn.params.params);
+
+        let strct = self.receiver.unwrap();
+        let label = self.fn_map.find(method_name(strct, &impl_item_fn.ident));
+        self.lower_fn(label, &impl_item_fn.body);
 
-        // Move the name mapper down a level
         self.name_map.down();
     }
 }
+
+/// The name a method is labeled by. Methods of different structs can share a name, so the struct is included.
+fn method_name(strct: Name, method: &Ident) -> &'static str {
+    Name::intern(&format!("{}.{}", strct, method.repr)).as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+    use crate::resolution::Resolver;
+    use crate::sema::typeck::TypeCk;
+
+    /// Lower an entire program, which must parse and type check, and hand its IR to `f`.
+    fn lower<T>(src: &str, f: impl FnOnce(&IRRoot) -> T) -> T {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        let types = match TypeCk::new(&resolver).run(&file) {
+            Ok(types) => types,
+            Err(err) => panic!("the program should type check: {}", err.reason),
+        };
+
+        f(&LoweringEngine::new(&file, &types).lower())
+    }
+
+    /// Lower an entire program, which must parse and type check, and render its IR.
+    fn ir(src: &str) -> String {
+        lower(src, |root| {
+            let mut out = Vec::new();
+            root.render(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        })
+    }
+
+    /// The lines of rendered IR, without labels, indentation or the padding that lines up destinations.
+    fn lines(ir: &str) -> Vec<String> {
+        ir.lines()
+            .map(|line| match line.split_once(": ") {
+                Some((label, instr)) if label.starts_with('l') => instr,
+                _ => line,
+            })
+            .map(|instr| instr.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+
+    #[test]
+    fn methods_pass_self_as_the_first_argument() {
+        let ir = ir("
+            struct Rect { w: i32, h: i32 }
+
+            impl Rect {
+                fn width(self) -> i32 { 3 }
+                fn height(self) -> i32 { 4 }
+                fn area(self) -> i32 { self.width() * self.height() }
+            }
+
+            fn main() -> i32 { 0 }
+        ");
+
+        let lines = lines(&ir);
+        let width = lines
+            .iter()
+            .position(|line| *line == "t3 = call l0, 1")
+            .unwrap();
+        let height = lines
+            .iter()
+            .position(|line| *line == "t5 = call l1, 1")
+            .unwrap();
+
+        assert_eq!(lines[width - 1], "param t2");
+        assert_eq!(lines[height - 1], "param t4");
+    }
+
+    #[test]
+    fn comparisons_feed_conditional_branches() {
+        let ir = ir("fn main() -> i32 { if 1 < 2 { 10 } else { 20 } }");
+        let lines = lines(&ir);
+
+        let cmp = lines
+            .iter()
+            .position(|line| *line == "t2 = t0 < t1")
+            .unwrap();
+        assert_eq!(lines[cmp + 1], "if t2 goto l9 else l10");
+
+        // The branches start at the labels the conditional jumps to, which follow those of `main` and the builtins
+        let flat = ir.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(flat.contains("l9: nop t4 = 10"));
+        assert!(flat.contains("l10: nop t6 = 20"));
+    }
+
+    #[test]
+    fn functions_that_run_off_their_end_return_unit() {
+        let ir = ir("fn nothing() -> () {}\nfn main() -> () { nothing(); }");
+
+        assert!(ir.contains("l0: t0 = ()\n    ret t0\n"));
+        assert!(ir.ends_with("t3 = ()\n    ret t3\n"));
+    }
+
+    #[test]
+    fn params_are_named_in_order() {
+        let ir = ir("fn sub(a: i32, b: i32) -> i32 { a - b }\nfn main() -> i32 { sub(2, 1) }");
+
+        assert!(ir.contains("t0 = x0\n    t1 = x1\n    t2 = t0 - t1\n    ret t2"));
+    }
+
+    #[test]
+    fn methods_can_be_called_on_any_struct() {
+        let ir = ir("
+            struct P { x: i32, y: i32 }
+
+            impl P {
+                fn dist(self, q: P) -> i32 { (self.x - q.x) + (self.y - q.y) }
+            }
+
+            fn main() -> i32 {
+                let p = P { x: 1, y: 2 };
+                let q = P { x: 3, y: 4 };
+                p.dist(q)
+            }
+        ");
+        let lines = lines(&ir);
+
+        // The receiver goes first, then the arguments
+        let call = lines
+            .iter()
+            .position(|line| line.ends_with("= call l0, 2"))
+            .unwrap();
+        assert_eq!(
+            &lines[call - 4..call],
+            ["t17 = x2", "param t17", "t18 = x3", "param t18"]
+        );
+
+        // Fields are read from the receiver and the parameter alike
+        assert_eq!(
+            &lines[..4],
+            ["t0 = x0", "t1 = t0.x", "t2 = x1", "t3 = t2.x"]
+        );
+    }
+
+    #[test]
+    fn references_copy_the_value_they_refer_to() {
+        let ir = ir("fn get(r: &i32) -> i32 { 0 }\nfn main() -> i32 { let a = 5; get(&a) }");
+        let lines = lines(&ir);
+
+        let param = lines.iter().position(|line| *line == "param t3").unwrap();
+        assert_eq!(&lines[param - 2..param], ["t2 = x1", "t3 = t2"]);
+    }
+
+    #[test]
+    fn checked_multiplication_is_lowered_in_place() {
+        let ir = ir("fn main() -> i32 { checked_mul(6, 7) }");
+        let lines = lines(&ir);
+
+        assert!(lines.contains(&String::from("t2 = t0 *? t1")));
+        assert!(!lines.iter().any(|line| line.starts_with("param ")));
+    }
+
+    #[test]
+    fn literals_are_interned_once_for_the_whole_program() {
+        let file = match parse_str("fn answer() -> i32 { 42 }\nfn main() -> i32 { 42 }") {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        let types = match TypeCk::new(&resolver).run(&file) {
+            Ok(types) => types,
+            Err(err) => panic!("the program should type check: {}", err.reason),
+        };
+
+        let mut engine = LoweringEngine::new(&file, &types);
+        let ir = engine.lower();
+        let indices = ir
+            .instrs
+            .iter()
+            .filter_map(|instr| match instr {
+                Instr::Copy(CopyInstr {
+                    ad: Addr::Const(Const::Int(index)),
+                    ..
+                }) => Some(*index),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let answer = *ir.interner.integers.index_of(&42).unwrap();
+        assert_eq!(indices, [answer, answer]);
+    }
+
+    #[test]
+    fn the_example_program_lowers() {
+        let ir = ir(include_str!("../../examples/main.scoot"));
+        let lines = lines(&ir);
+
+        // Every function ends by returning
+        assert_eq!(
+            lines.iter().filter(|line| line.starts_with("ret ")).count(),
+            3
+        );
+        assert!(lines.last().unwrap().starts_with("ret "));
+    }
+
+    #[test]
+    fn builtins_are_labeled_after_the_program() {
+        let ir = ir("fn two() -> i32 { 2 }\nfn main() -> i32 { print(two()); 0 }");
+        let lines = lines(&ir);
+
+        // `print` is the first builtin, and follows `two` and `main`
+        let call = lines
+            .iter()
+            .position(|line| *line == "t2 = call l2, 1")
+            .unwrap();
+        assert_eq!(lines[call - 1], "param t1");
+    }
+
+    #[test]
+    fn structs_are_compared_field_by_field() {
+        let ir = ir("
+            struct Point { x: i32, y: i32 }
+
+            fn main() -> i32 {
+                let a = Point { x: 1, y: 2 };
+                let b = Point { x: 1, y: 3 };
+                if a == b { 1 } else { 0 }
+            }
+        ");
+        let lines = lines(&ir);
+
+        let cmp = lines.iter().position(|line| *line == "t8 = t6.x").unwrap();
+        assert_eq!(
+            &lines[cmp..cmp + 8],
+            [
+                "t8 = t6.x",
+                "t9 = t7.x",
+                "t10 = t8 == t9",
+                "t11 = t6.y",
+                "t12 = t7.y",
+                "t13 = t11 == t12",
+                "t14 = t10 && t13",
+                "if t14 goto l9 else l10",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_tuples_differ_if_any_element_does() {
+        let ir = ir("fn main() -> i32 { let a = (1, (2, 3)); if a != a { 1 } else { 0 } }");
+        let lines = lines(&ir);
+
+        let cmp = lines.iter().position(|line| *line == "t7 = t5.0").unwrap();
+        assert_eq!(
+            &lines[cmp..cmp + 14],
+            [
+                "t7 = t5.0",
+                "t8 = t6.0",
+                "t9 = t7 != t8",
+                "t10 = t5.1",
+                "t11 = t6.1",
+                "t12 = t10.0",
+                "t13 = t11.0",
+                "t14 = t12 != t13",
+                "t15 = t10.1",
+                "t16 = t11.1",
+                "t17 = t15 != t16",
+                "t18 = t14 || t17",
+                "t19 = t9 || t18",
+                "if t19 goto l9 else l10",
+            ]
+        );
+    }
+
+    #[test]
+    fn structs_without_fields_are_always_equal() {
+        let ir = ir("struct E {}\nfn main() -> i32 { let e = E {}; if e == e { 1 } else { 0 } }");
+        let lines = lines(&ir);
+
+        let cmp = lines
+            .iter()
+            .position(|line| line.starts_with("if "))
+            .unwrap();
+        assert_eq!(lines[cmp - 1], "t3 = 1");
+    }
+
+    #[test]
+    fn an_i32_main_that_runs_off_its_end_exits_with_zero() {
+        assert_eq!(lines(&ir("fn main() -> i32 {}")), ["ret 0"]);
+        assert_eq!(lines(&ir("fn main() -> i32 { return; }")), ["ret 0"]);
+
+        // The unit value of the tail is still computed, for its side effects
+        let lines = lines(&ir("fn main() -> i32 { print(1) }"));
+        assert_eq!(&lines[lines.len() - 2..], ["t1 = call l1, 1", "ret 0"]);
+    }
+
+    #[test]
+    fn calls_record_their_argument_count() {
+        let src = "fn add3(a: i32, b: i32, c: i32) -> i32 { a + b + c }\nfn main() -> i32 { add3(1, 2, 3) }";
+
+        lower(src, |root| {
+            let calls = root
+                .instrs
+                .iter()
+                .filter_map(|instr| match instr {
+                    Instr::Call(call) => Some(call.n),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(calls, [3]);
+        });
+    }
+
+    #[test]
+    fn calls_to_functions_defined_later_reach_their_label() {
+        let ir = ir("fn main() -> i32 { helper() }\nfn helper() -> i32 { 7 }");
+        let lines = ir.lines().map(str::trim).collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            ["l0: t0 = call l1, 0", "ret t0", "l1: t1 = 7", "ret t1"]
+        );
+    }
+}
@@ -1 +1,271 @@
-pub mod table;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, Write};
+
+use crate::shared::Index;
+
+pub mod fold;
+pub mod instr;
+pub mod lower;
+
+pub use instr::*;
+pub use lower::*;
+
+/// The IR representation of a program. Really just a fancy list of instructions right now. Later it will likely
+/// become much more complicated!
+pub struct IRRoot<'a> {
+    pub last_label: Index,
+    pub interner: LoweringPool<'a>,
+    pub instrs: Vec<Instr>,
+}
+
+impl IRRoot<'_> {
+    /// Write the program to a file, one instruction per line.
+    #[allow(dead_code)]
+    pub fn human_readable(&self, output: &str) -> io::Result<()> {
+        let mut file = File::create(output)?;
+        self.render(&mut file)
+    }
+
+    /// Write the program to the given stream, one instruction per line. Instructions with a label are prefixed by
+    /// it, and the rest are indented to line up with them. Destinations are padded to the same width, so the `=` of
+    /// every assignment lines up as well.
+    pub fn render<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        // Figure out how much padding is needed for the labels
+        // Note that we add three to account for the 'L' character, the colon, and the space
+        let max_length = self.last_label.to_string().len() + 3;
+        let label_padding = " ".repeat(max_length);
+
+        // Figure out how wide the widest destination is
+        let width = self
+            .instrs
+            .iter()
+            .filter(|instr| {
+                !matches!(
+                    instr,
+                    Instr::Param(_)
+                        | Instr::Return(_)
+                        | Instr::CJump(_)
+                        | Instr::Jump(_)
+                        | Instr::Nop(_)
+                )
+            })
+            .map(|instr| self.addr_readable(instr.da(), true).len())
+            .max()
+            .unwrap_or(0);
+
+        for instr in &self.instrs {
+            match instr {
+                Instr::Binary(bin) => {
+                    let da = self.addr_readable(&bin.da, true);
+                    let la = self.addr_readable(&bin.la, false);
+                    let op = &bin.op;
+                    let ra = self.addr_readable(&bin.ra, false);
+
+                    let pad = label(&bin.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = {la} {op} {ra}")?;
+                }
+
+                Instr::Unary(un) => {
+                    let da = self.addr_readable(&un.da, true);
+                    let ad = self.addr_readable(&un.ad, false);
+                    let op = &un.op;
+                    let pad = label(&un.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = {op}{ad}")?;
+                }
+
+                Instr::Copy(cop) => {
+                    let da = self.addr_readable(&cop.da, true);
+                    let ad = self.addr_readable(&cop.ad, false);
+                    let pad = label(&cop.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = {ad}")?;
+                }
+
+                Instr::Return(ret) => {
+                    let ad = self.addr_readable(&ret.ad, false);
+                    let pad = label(&ret.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}ret {ad}")?;
+                }
+
+                Instr::Call(call) => {
+                    let da = self.addr_readable(&call.da, false);
+                    let fl = self.label_readable(&call.fl);
+
+                    let pad = label(&call.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = call {fl}, {}", call.n)?;
+                }
+
+                Instr::Param(param) => {
+                    let ad = self.addr_readable(&param.ad, false);
+                    let pad = label(&param.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}param {ad}")?;
+                }
+
+                Instr::CJump(cjump) => {
+                    let ad = self.addr_readable(&cjump.ad, false);
+                    let tl = self.label_readable(&cjump.tl);
+                    let el = self.label_readable(&cjump.el);
+                    let pad = label(&cjump.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}if {ad} goto {tl} else {el}")?;
+                }
+
+                Instr::Jump(jump) => {
+                    let tl = self.label_readable(&jump.tl);
+                    let pad = label(&jump.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}goto {tl}")?;
+                }
+
+                Instr::Nop(nop) => {
+                    let pad = label(&nop.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}nop")?;
+                }
+
+                Instr::Struct(strct) => {
+                    let da = self.addr_readable(&strct.da, true);
+                    let ty = self.interner.strings.value_of(strct.ty).unwrap();
+                    let pad = label(&strct.label, max_length, &label_padding);
+
+                    let fields = strct
+                        .fields
+                        .iter()
+                        .map(|(field, ad)| {
+                            let field = self.interner.strings.value_of(*field).unwrap();
+                            format!("{field}: {}", self.addr_readable(ad, false))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    writeln!(out, "{pad}{da:width$} = {ty} {{ {fields} }}")?;
+                }
+
+                Instr::Tuple(tuple) => {
+                    let da = self.addr_readable(&tuple.da, true);
+                    let pad = label(&tuple.label, max_length, &label_padding);
+
+                    let elems = tuple
+                        .elems
+                        .iter()
+                        .map(|ad| self.addr_readable(ad, false))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    writeln!(out, "{pad}{da:width$} = ({elems})")?;
+                }
+
+                Instr::Extract(extract) => {
+                    let da = self.addr_readable(&extract.da, true);
+                    let ad = self.addr_readable(&extract.ad, false);
+                    let pad = label(&extract.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = {ad}.{}", extract.index)?;
+                }
+
+                Instr::Field(field) => {
+                    let da = self.addr_readable(&field.da, true);
+                    let ad = self.addr_readable(&field.ad, false);
+                    let name = self.interner.strings.value_of(field.field).unwrap();
+                    let pad = label(&field.label, max_length, &label_padding);
+
+                    writeln!(out, "{pad}{da:width$} = {ad}.{name}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turns an address into a human readable string.
+    fn addr_readable(&self, addr: &Addr, is_d: bool) -> String {
+        match addr {
+            Addr::Name(i) => format!("x{i}"),
+            Addr::Temp(i) => format!("t{i}"),
+            Addr::Const(Const::Int(i)) if !is_d => {
+                let value = self.interner.integers.value_of(*i).cloned().unwrap();
+                value.to_string()
+            }
+            _ => panic!("Constant cannot serve as a destination address"),
+        }
+    }
+
+    /// Turns a label into a human readable label string.
+    fn label_readable(&self, label: &Label) -> String {
+        format!("l{}", label.0)
+    }
+}
+
+/// Renders the program the same way `render` does, which makes it easy to inspect the lowered program without
+/// going through a stream.
+impl Display for IRRoot<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut buffer = Vec::new();
+        self.render(&mut buffer).map_err(|_| std::fmt::Error)?;
+
+        f.write_str(&String::from_utf8_lossy(&buffer))
+    }
+}
+
+fn label(label: &Option<Label>, max_len: usize, default: &str) -> String {
+    match label {
+        Some(label) => {
+            let l = format!("l{}:", label.0);
+            let space = max_len - l.len();
+            format!("{l}{}", " ".repeat(space))
+        }
+
+        None => default.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program made of copies from constants into the given destinations, in order.
+    fn copies(das: Vec<Addr>) -> IRRoot<'static> {
+        let mut interner = LoweringPool::new();
+        let instrs = das
+            .into_iter()
+            .map(|da| {
+                let ad = Addr::Const(Const::Int(interner.integers.insert(1)));
+                Instr::Copy(CopyInstr::new(da, ad))
+            })
+            .collect();
+
+        IRRoot {
+            last_label: 0,
+            interner,
+            instrs,
+        }
+    }
+
+    #[test]
+    fn human_readable_writes_the_rendered_program_to_a_file() {
+        let root = copies(vec![Addr::Temp(0), Addr::Name(1)]);
+
+        let path = std::env::temp_dir().join(format!("scooter-{}-ir.txt", std::process::id()));
+        root.human_readable(path.to_str().unwrap()).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(written, root.to_string());
+    }
+
+    #[test]
+    fn destinations_of_different_widths_line_up() {
+        let root = copies(vec![Addr::Temp(0), Addr::Temp(10), Addr::Name(100)]);
+
+        assert_eq!(
+            root.to_string(),
+            "    t0   = 1\n    t10  = 1\n    x100 = 1\n"
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use std::collections::VecDeque;
+use std::num::{IntErrorKind, ParseIntError};
+
 use super::{Token, TokenKind};
-use crate::shared::{Location, Span};
+use crate::shared::{Location, Name, Span};
 
 /// Represents an error that occured during lexing.
 pub struct LexError {
@@ -13,30 +16,76 @@ pub struct LexError {
 /// Represents the result of lexing.
 type LexResult<T> = Result<T, LexError>;
 
+/// Words reserved for features that haven't been implemented yet. Using them as identifiers is an error, so that
+/// programs don't silently change meaning once they become real keywords.
+const RESERVED: &[&str] = &["while", "loop", "enum", "type", "mut", "as"];
+
+/// How many characters the lexer can see at once: the current one, and up to three after it (to tell `////` apart
+/// from a doc comment).
+const WINDOW: usize = 4;
+
 /// Represents the lexing engine.
+///
+/// Identifiers are restricted to ASCII: they start with a letter or an underscore and continue with letters, digits
+/// and underscores. Letters and digits from other scripts (such as `café` or `日本語`) are rejected with an error
+/// pointing at the offending character, rather than being lexed as something else. Non-ASCII characters are still
+/// allowed in comments, and columns always count characters rather than bytes.
+///
+/// Characters are pulled from the source as they're needed, so it never has to be collected up front.
 pub struct Lexer<'a> {
-    /// The source code to be lexed.
-    source: &'a [char],
+    /// The characters that haven't reached the window yet.
+    source: Box<dyn Iterator<Item = char> + 'a>,
+
+    /// The current character followed by the next few, up to `WINDOW` characters. It's shorter near the end of the
+    /// input, and empty once the cursor has passed the end.
+    window: VecDeque<char>,
 
-    /// Our current index in the `source` slice.
-    index: usize,
+    /// The character just before the cursor, or `'\0'` at the start of the input.
+    previous: char,
 
     /// Our current line (starting at one).
     line: usize,
 
     /// Our current column (starting at one).
     column: usize,
+
+    /// How many columns a tab advances the cursor by.
+    tab_width: usize,
 }
 
 impl<'a> Lexer<'a> {
     /// Create a new lexer instance.
+    #[allow(dead_code)]
     pub fn new(source: &'a [char]) -> Self {
-        Lexer {
-            source,
-            index: 0,
+        Self::from_chars(source.iter().copied())
+    }
+
+    /// Create a lexer that pulls characters from an iterator (such as `str::chars`) as it goes.
+    pub fn from_chars(source: impl Iterator<Item = char> + 'a) -> Self {
+        let mut lexer = Lexer {
+            source: Box::new(source),
+            window: VecDeque::with_capacity(WINDOW),
+            previous: '\0',
             line: 1,
             column: 1,
-        }
+            tab_width: 1,
+        };
+
+        lexer.fill();
+        lexer
+    }
+
+    /// Start numbering lines at `line` rather than one, which is used when the input follows other source files.
+    pub fn with_first_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Count a tab as `width` columns rather than one, so columns match the ones an editor shows. The source map needs
+    /// the same width to find the characters a span points at when printing diagnostics.
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
     }
 
     /// Lex the entire input.
@@ -52,10 +101,50 @@ impl<'a> Lexer<'a> {
         Ok(tokens)
     }
 
+    /// Lex the entire input, carrying on after errors. The offending character (along with the rest of the word
+    /// it's part of) is skipped, so that a single mistake is only reported once. Returns every token that could be
+    /// lexed, followed by every error.
+    pub fn lex_all(&mut self) -> (Vec<Token>, Vec<LexError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            match self.next() {
+                Ok(token) if token.kind == TokenKind::EOF => return (tokens, errors),
+                Ok(token) => tokens.push(token),
+                Err(err) => {
+                    // Errors about a single character are reported without stepping over it
+                    if err.span.as_ref().map(|span| &span.start) == Some(&self.location()) {
+                        self.step(1);
+                    }
+
+                    errors.push(err);
+
+                    while self.on_same_line()
+                        && (self.current().is_alphanumeric() || self.current() == '_')
+                    {
+                        self.step(1);
+                    }
+                }
+            }
+        }
+    }
+
     /// Return the next token.
     pub fn next(&mut self) -> LexResult<Token> {
-        while self.current() != '\0' && self.current().is_whitespace() {
-            self.step(1);
+        // Skip whitespace and comments, in any order
+        loop {
+            while self.current() != '\0' && self.current().is_whitespace() {
+                self.step(1);
+            }
+
+            if self.current() == '/' && self.lookahead(1) == '*' {
+                self.skip_block_comment()?;
+            } else if self.current() == '/' && self.lookahead(1) == '/' && !self.at_doc_comment() {
+                self.skip_line_comment();
+            } else {
+                break;
+            }
         }
 
         if self.current() == '\0' {
@@ -66,19 +155,40 @@ impl<'a> Lexer<'a> {
         }
 
         let current = self.current();
-        if current.is_alphabetic() || current == '_' {
+        if current.is_alphanumeric() && !current.is_ascii() {
+            return Err(Self::non_ascii(current, self.location()));
+        }
+
+        if current.is_ascii_alphabetic() || current == '_' {
             let start = self.location();
             let mut end = self.location();
             let mut raw = String::from(self.current());
 
             self.step(1);
 
-            while self.current().is_alphanumeric() || self.current() == '_' {
-                raw += &self.current().to_string();
+            while self.on_same_line() && (self.current().is_alphanumeric() || self.current() == '_')
+            {
+                if !self.current().is_ascii() {
+                    return Err(Self::non_ascii(self.current(), self.location()));
+                }
+
+                raw.push(self.current());
                 end = self.location();
                 self.step(1);
             }
 
+            // Digits after a leading underscore (as in `_100`) are a number with a misplaced separator
+            if raw.chars().any(|c| c.is_ascii_digit())
+                && raw.chars().all(|c| c.is_ascii_digit() || c == '_')
+            {
+                return Err(LexError {
+                    reason: format!(
+                        "Invalid number literal {raw} (underscores must separate two digits)"
+                    ),
+                    span: Some(Span::single(start.line, start.column)),
+                });
+            }
+
             let span = Span::new(start, end);
             match raw.as_str() {
                 "fn" => Ok(Token::spanned(TokenKind::KwFn, span)),
@@ -87,28 +197,123 @@ impl<'a> Lexer<'a> {
                 "self" => Ok(Token::spanned(TokenKind::KwSelf, span)),
                 "let" => Ok(Token::spanned(TokenKind::KwLet, span)),
                 "return" => Ok(Token::spanned(TokenKind::KwRet, span)),
-                _ => Ok(Token::spanned(TokenKind::Ident(raw), span)),
+                "const" => Ok(Token::spanned(TokenKind::KwConst, span)),
+                "true" => Ok(Token::spanned(TokenKind::KwTrue, span)),
+                "false" => Ok(Token::spanned(TokenKind::KwFalse, span)),
+                "break" => Ok(Token::spanned(TokenKind::KwBreak, span)),
+                "if" => Ok(Token::spanned(TokenKind::KwIf, span)),
+                "else" => Ok(Token::spanned(TokenKind::KwElse, span)),
+                "pub" => Ok(Token::spanned(TokenKind::KwPub, span)),
+                "match" => Ok(Token::spanned(TokenKind::KwMatch, span)),
+                _ if RESERVED.contains(&raw.as_str()) => Err(LexError {
+                    reason: format!(
+                        "'{raw}' is a reserved keyword and can't be used as an identifier"
+                    ),
+                    span: Some(span),
+                }),
+                _ => Ok(Token::spanned(TokenKind::Ident(Name::intern(&raw)), span)),
             }
-        } else if current.is_numeric() {
+        } else if current == '0' && matches!(self.lookahead(1), 'x' | 'b') {
+            self.lex_radix()
+        } else if current.is_ascii_digit() {
             let start = self.location();
             let mut end = self.location();
             let mut raw = String::from(self.current());
 
+            // A number right after a dot is a tuple index, so in `t.0.1` the `0.1` isn't a float
+            let tuple_index = self.previous == '.';
+
             self.step(1);
 
-            while self.current().is_numeric() {
-                raw += &self.current().to_string();
+            // Underscores may be used to separate digits (e.g. `1_000_000`)
+            while self.on_same_line() && (self.current().is_ascii_digit() || self.current() == '_')
+            {
+                raw.push(self.current());
                 end = self.location();
                 self.step(1);
             }
 
-            let value: i32 = raw.parse().map_err(|_| LexError {
-                reason: format!("Couldn't convert {raw} into an i32"),
-                span: Some(Span::new(start.clone(), end.clone())),
-            })?;
+            let mut float = false;
+            if !tuple_index && self.at_adjacent('.') {
+                let next = self.lookahead(1);
+
+                if next.is_ascii_digit() {
+                    float = true;
+
+                    // Take the decimal point and any further digits, dots included, so that a malformed
+                    // literal like `1.2.3` is reported as a whole
+                    while self.on_same_line()
+                        && (self.current().is_ascii_digit()
+                            || self.current() == '_'
+                            || (self.current() == '.' && self.lookahead(1).is_ascii_digit()))
+                    {
+                        raw.push(self.current());
+                        end = self.location();
+                        self.step(1);
+                    }
+                } else if !(next.is_ascii_alphabetic() || next == '_') {
+                    raw.push('.');
+                    end = self.location();
+
+                    return Err(LexError {
+                        reason: format!(
+                            "Invalid number literal {raw} (a decimal point must be followed by digits)"
+                        ),
+                        span: Some(Span::new(start, end)),
+                    });
+                }
+            }
+
+            // Every underscore must have a digit on both sides, so `1_`, `1__0` and `1_.5` are rejected
+            let chars = raw.chars().collect::<Vec<char>>();
+            let misplaced = (0..chars.len()).find(|&i| {
+                chars[i] == '_' && !chars.get(i + 1).is_some_and(|next| next.is_ascii_digit())
+            });
+
+            if let Some(i) = misplaced {
+                return Err(LexError {
+                    reason: format!(
+                        "Invalid number literal {raw} (underscores must separate two digits)"
+                    ),
+                    span: Some(Span::single(start.line, start.column + i)),
+                });
+            }
+
+            if raw.matches('.').count() > 1 {
+                return Err(LexError {
+                    reason: format!(
+                        "Invalid number literal {raw} (a number can only have one decimal point)"
+                    ),
+                    span: Some(Span::new(start, end)),
+                });
+            }
+
+            let digits = raw.replace('_', "");
+
+            if float {
+                let value: f64 = digits.parse().map_err(|_| LexError {
+                    reason: format!("Couldn't convert {digits} into an f64"),
+                    span: Some(Span::new(start.clone(), end.clone())),
+                })?;
+
+                return Ok(Token::spanned(
+                    TokenKind::LitFloat(value),
+                    Span::new(start, end),
+                ));
+            }
+
+            let value: i32 = match digits.parse() {
+                Ok(value) => value,
+
+                // The magnitude of `i32::MIN` doesn't fit in an `i32`, so it's lexed as `i32::MIN` itself (which no
+                // other literal can produce). The parser only accepts it right after a minus sign.
+                Err(_) if digits.parse::<i64>() == Ok(-(i32::MIN as i64)) => i32::MIN,
+
+                Err(err) => return Err(int_error(&raw, err, Span::new(start, end))),
+            };
 
             Ok(Token::spanned(
-                TokenKind::LitNum(value),
+                TokenKind::LitNum(value, Name::intern(&raw)),
                 Span::new(start, end),
             ))
         } else {
@@ -129,6 +334,11 @@ impl<'a> Lexer<'a> {
                     kind = TokenKind::Star;
                 }
 
+                '%' => {
+                    self.expect('%')?;
+                    kind = TokenKind::Percent;
+                }
+
                 '(' => {
                     self.expect('(')?;
                     kind = TokenKind::LParen;
@@ -149,6 +359,16 @@ impl<'a> Lexer<'a> {
                     kind = TokenKind::RBrace;
                 }
 
+                '[' => {
+                    self.expect('[')?;
+                    kind = TokenKind::LBracket;
+                }
+
+                ']' => {
+                    self.expect(']')?;
+                    kind = TokenKind::RBracket;
+                }
+
                 ':' => {
                     self.expect(':')?;
                     kind = TokenKind::Colon;
@@ -161,7 +381,54 @@ impl<'a> Lexer<'a> {
 
                 '=' => {
                     self.expect('=')?;
-                    kind = TokenKind::Equal;
+
+                    if self.at_adjacent('=') {
+                        end = self.location();
+                        self.expect('=')?;
+                        kind = TokenKind::EqualEqual;
+                    } else if self.at_adjacent('>') {
+                        end = self.location();
+                        self.expect('>')?;
+                        kind = TokenKind::FatArrow;
+                    } else {
+                        kind = TokenKind::Equal;
+                    }
+                }
+
+                '!' => {
+                    self.expect('!')?;
+
+                    if self.at_adjacent('=') {
+                        end = self.location();
+                        self.expect('=')?;
+                        kind = TokenKind::BangEqual;
+                    } else {
+                        kind = TokenKind::Bang;
+                    }
+                }
+
+                '<' => {
+                    self.expect('<')?;
+
+                    if self.at_adjacent('=') {
+                        end = self.location();
+                        self.expect('=')?;
+                        kind = TokenKind::LessEqual;
+                    } else {
+                        kind = TokenKind::Less;
+                    }
+                }
+
+                '>' => {
+                    self.expect('>')?;
+
+                    if self.at_adjacent('=') {
+                        end = self.location();
+                        self.expect('=')?;
+                        kind = TokenKind::GreaterEqual;
+                    } else {
+                        kind = TokenKind::Greater;
+                    }
                 }
 
                 ',' => {
@@ -169,17 +436,80 @@ impl<'a> Lexer<'a> {
                     kind = TokenKind::Comma
                 }
 
+                // A dot between two digits is part of a float literal, which was lexed above, unless the digits are
+                // tuple indices (as in `t.0.1`)
+                '.' => {
+                    self.expect('.')?;
+                    kind = TokenKind::Dot;
+                }
+
+                '&' => {
+                    self.expect('&')?;
+                    kind = TokenKind::Ampersand;
+                }
+
+                '@' => {
+                    self.expect('@')?;
+                    kind = TokenKind::At;
+                }
+
+                // Labels, which are identifiers preceded by a single quote
+                '\'' => {
+                    self.expect('\'')?;
+
+                    if !(self.current().is_ascii_alphabetic() || self.current() == '_') {
+                        return Err(Self::unexpected('\'', Span::new(start.clone(), start)));
+                    }
+
+                    let mut raw = String::new();
+                    while self.on_same_line()
+                        && (self.current().is_ascii_alphanumeric() || self.current() == '_')
+                    {
+                        raw.push(self.current());
+                        end = self.location();
+                        self.step(1);
+                    }
+
+                    kind = TokenKind::Label(Name::intern(&raw));
+                }
+
+                // Doc comments, which run until the end of the line
+                '/' if self.at_doc_comment() => {
+                    let line = self.line;
+                    self.step(3);
+
+                    // A single space usually separates the slashes from the text
+                    if self.current() == ' ' {
+                        self.step(1);
+                    }
+
+                    let mut text = String::new();
+                    while self.current() != '\0' && self.line == line {
+                        text.push(self.current());
+                        end = self.location();
+                        self.step(1);
+                    }
+
+                    kind = TokenKind::DocComment(text);
+                }
+
+                // Comments were skipped above, so this slash must be on its own
+                '/' => {
+                    self.expect('/')?;
+                    kind = TokenKind::Slash;
+                }
+
                 // Single character lookahead (we need to look at the next one)
                 '-' => {
                     self.expect('-')?;
                     end = Location::new(self.line, self.column);
 
-                    let current = self.current();
-                    if current == '>' {
+                    if self.at_adjacent('>') {
                         self.expect('>')?;
                         kind = TokenKind::RArrow
                     } else {
-                        return Err(Self::unexpected(current, Span::new(start, end)));
+                        end = start.clone();
+                        kind = TokenKind::Minus;
                     }
                 }
 
@@ -192,6 +522,124 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Lex a hexadecimal (`0xFF`) or binary (`0b1010`) integer literal. Like decimal literals, they may contain
+    /// underscores between digits.
+    fn lex_radix(&mut self) -> LexResult<Token> {
+        let start = self.location();
+        let (radix, base) = match self.lookahead(1) {
+            'x' => (16, "hexadecimal"),
+            _ => (2, "binary"),
+        };
+
+        let mut raw = format!("0{}", self.lookahead(1));
+        let mut end = Location::new(self.line, self.column + 1);
+        self.step(2);
+
+        // Take every character that could belong to the literal, so that invalid digits are reported rather than
+        // being lexed as the start of an identifier
+        let mut digits = Vec::new();
+        while self.on_same_line()
+            && (self.current().is_ascii_alphanumeric() || self.current() == '_')
+        {
+            digits.push((self.current(), self.location()));
+            raw.push(self.current());
+            end = self.location();
+            self.step(1);
+        }
+
+        if digits.is_empty() {
+            return Err(LexError {
+                reason: format!("Expected {base} digits after '{raw}'"),
+                span: Some(Span::new(start, end)),
+            });
+        }
+
+        for (i, (c, location)) in digits.iter().enumerate() {
+            if *c == '_' {
+                let next = digits.get(i + 1).map(|(next, _)| next.is_digit(radix));
+
+                if i == 0 || next != Some(true) {
+                    return Err(LexError {
+                        reason: format!(
+                            "Invalid number literal {raw} (underscores must separate two digits)"
+                        ),
+                        span: Some(Span::new(location.clone(), location.clone())),
+                    });
+                }
+            } else if !c.is_digit(radix) {
+                return Err(LexError {
+                    reason: format!("Invalid digit '{c}' in {base} literal {raw}"),
+                    span: Some(Span::new(location.clone(), location.clone())),
+                });
+            }
+        }
+
+        let digits = digits.iter().map(|(c, _)| c).filter(|c| **c != '_');
+        let value = i32::from_str_radix(&digits.collect::<String>(), radix)
+            .map_err(|err| int_error(&raw, err, Span::new(start.clone(), end.clone())))?;
+
+        Ok(Token::spanned(
+            TokenKind::LitNum(value, Name::intern(&raw)),
+            Span::new(start, end),
+        ))
+    }
+
+    /// Skip a block comment (`/* ... */`), which may span several lines and contain other block comments. The comment
+    /// is stepped over one character at a time, so tokens following it on the same line keep their real columns.
+    fn skip_block_comment(&mut self) -> LexResult<()> {
+        let start = self.location();
+        self.step(2);
+
+        // Comments nest, so commenting out code that already contains a comment works as expected
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.current() == '/' && self.lookahead(1) == '*' {
+                depth += 1;
+                self.step(2);
+            } else if self.current() == '*' && self.lookahead(1) == '/' {
+                depth -= 1;
+                self.step(2);
+            } else if self.current() == '\0' {
+                return Err(LexError {
+                    reason: String::from("Unterminated block comment"),
+                    span: Some(Span::new(
+                        start.clone(),
+                        Location::new(start.line, start.column + 1),
+                    )),
+                });
+            } else {
+                self.step(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skip a line comment (`// ...`), which runs until the end of the line (or the end of the input).
+    fn skip_line_comment(&mut self) {
+        let line = self.line;
+
+        while self.current() != '\0' && self.line == line {
+            self.step(1);
+        }
+    }
+
+    /// Is the cursor at `c`, right after the previous character? Tokens made of several characters (such as `==`
+    /// and `->`) are only lexed as one when nothing, not even a line break, separates their characters.
+    fn at_adjacent(&self, c: char) -> bool {
+        self.on_same_line() && self.current() == c
+    }
+
+    /// Is the cursor at the start of a doc comment? Doc comments start with exactly three slashes, so `////` is an
+    /// ordinary comment.
+    fn at_doc_comment(&self) -> bool {
+        self.current() == '/'
+            && self.lookahead(1) == '/'
+            && self.lookahead(2) == '/'
+            && self.lookahead(3) != '/'
+    }
+
     /// Returns a `LexError` for an unexpected character with a span.
     pub fn unexpected(c: char, span: Span) -> LexError {
         LexError {
@@ -200,6 +648,16 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Returns a `LexError` for a letter or digit outside of ASCII, which can't be part of an identifier.
+    fn non_ascii(c: char, location: Location) -> LexError {
+        LexError {
+            reason: format!(
+                "Identifiers may only contain ASCII letters, digits and underscores, found '{c}'"
+            ),
+            span: Some(Span::new(location.clone(), location)),
+        }
+    }
+
     /// Return the current character.
     pub fn current(&self) -> char {
         self.lookahead(0)
@@ -210,20 +668,28 @@ impl<'a> Lexer<'a> {
         Location::new(self.line, self.column)
     }
 
-    /// Step to the next valid character.
+    /// Step to the next valid character. Line breaks are stepped over right away, and `\r\n` counts as a single
+    /// line break, so the cursor never rests on either character.
     fn step(&mut self, n: usize) {
         for _ in 0..n {
-            self.index += 1;
+            self.advance();
 
-            if self.index >= self.source.len() {
+            if self.window.is_empty() {
                 break;
             }
 
-            self.column += 1;
+            self.column += match self.previous {
+                '\t' => self.tab_width,
+                _ => 1,
+            };
 
-            if self.source[self.index] == '\n' {
-                while self.current() == '\n' {
-                    self.index += 1;
+            if self.at_line_break() {
+                while self.at_line_break() {
+                    if self.current() == '\r' {
+                        self.advance();
+                    }
+
+                    self.advance();
                     self.line += 1;
                 }
                 self.column = 1;
@@ -231,13 +697,40 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    /// Look `n` characters ahead of the cursor.
-    fn lookahead(&self, n: usize) -> char {
-        if self.index + n >= self.source.len() {
-            '\0'
-        } else {
-            self.source[self.index + n]
+    /// Is the cursor on the same line as the character before it? The cursor steps over line breaks right away, so
+    /// words have to check this to stop at the end of a line instead of running into the first word on the next one.
+    fn on_same_line(&self) -> bool {
+        self.previous != '\n'
+    }
+
+    /// Is the cursor at a line break (either `\n` or `\r\n`)?
+    fn at_line_break(&self) -> bool {
+        self.current() == '\n' || (self.current() == '\r' && self.lookahead(1) == '\n')
+    }
+
+    /// Move the cursor past the current character, without keeping track of the location.
+    fn advance(&mut self) {
+        if let Some(c) = self.window.pop_front() {
+            self.previous = c;
         }
+
+        self.fill();
+    }
+
+    /// Pull characters from the source until the window is full (or the source runs out).
+    fn fill(&mut self) {
+        while self.window.len() < WINDOW {
+            match self.source.next() {
+                Some(c) => self.window.push_back(c),
+                None => break,
+            }
+        }
+    }
+
+    /// Look `n` characters ahead of the cursor, where `n` is less than `WINDOW`.
+    fn lookahead(&self, n: usize) -> char {
+        debug_assert!(n < WINDOW);
+        self.window.get(n).copied().unwrap_or('\0')
     }
 
     /// Move the cursor if the character matches, return an error otherwise.
@@ -255,3 +748,286 @@ impl<'a> Lexer<'a> {
         }
     }
 }
+
+/// Explains why an integer literal couldn't be converted into an `i32`. Literals are never negative (a leading `-` is
+/// an operator), so the only way a well-formed literal can fail is by being too large.
+fn int_error(raw: &str, err: ParseIntError, span: Span) -> LexError {
+    let reason = match err.kind() {
+        IntErrorKind::PosOverflow => format!(
+            "Integer literal {raw} is too large for an 'i32', which ranges from {} to {}",
+            i32::MIN,
+            i32::MAX
+        ),
+        _ => format!("Couldn't convert {raw} into an i32"),
+    };
+
+    LexError {
+        reason,
+        span: Some(span),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lex an entire input, which must be valid.
+    fn tokens(src: &str) -> Vec<Token> {
+        match Lexer::from_chars(src.chars()).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("the input should lex: {}", err.reason),
+        }
+    }
+
+    /// Return where a span starts and ends, as `(line, column)` pairs.
+    fn bounds(span: &Option<Span>) -> ((usize, usize), (usize, usize)) {
+        let span = span.as_ref().expect("the span should be known");
+        (
+            (span.start.line, span.start.column),
+            (span.end.line, span.end.column),
+        )
+    }
+
+    /// Lex an input that should be rejected, returning the reason.
+    fn reason(src: &str) -> String {
+        match Lexer::from_chars(src.chars()).lex() {
+            Ok(_) => panic!("the input should be rejected"),
+            Err(err) => err.reason,
+        }
+    }
+
+    #[test]
+    fn underscores_separate_digits() {
+        assert_eq!(
+            tokens("1_000")[0].kind,
+            TokenKind::LitNum(1000, Name::intern("1_000"))
+        );
+
+        for src in ["1__0", "100_", "_1", "_100"] {
+            assert_eq!(
+                reason(src),
+                format!("Invalid number literal {src} (underscores must separate two digits)")
+            );
+        }
+    }
+
+    #[test]
+    fn literals_too_large_for_an_i32_are_rejected() {
+        assert_eq!(
+            reason("2147483649"),
+            "Integer literal 2147483649 is too large for an 'i32', which ranges from -2147483648 to 2147483647"
+        );
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let tokens = tokens("/* a /* b */\n c */ 1");
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(bounds(&tokens[0].span), ((2, 7), (2, 7)));
+    }
+
+    #[test]
+    fn unterminated_block_comments_point_at_their_start() {
+        match Lexer::from_chars("1 /* a /* b */".chars()).lex() {
+            Ok(_) => panic!("the input should be rejected"),
+            Err(err) => {
+                assert_eq!(err.reason, "Unterminated block comment");
+                assert_eq!(bounds(&err.span), ((1, 3), (1, 4)));
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn decimal_points_make_floats() {
+        assert_eq!(tokens("3.14")[0].kind, TokenKind::LitFloat(3.14));
+        assert_eq!(tokens("0.5")[0].kind, TokenKind::LitFloat(0.5));
+        assert_eq!(
+            tokens("123")[0].kind,
+            TokenKind::LitNum(123, Name::intern("123"))
+        );
+    }
+
+    #[test]
+    fn malformed_floats_are_rejected() {
+        assert_eq!(
+            reason("1.2.3"),
+            "Invalid number literal 1.2.3 (a number can only have one decimal point)"
+        );
+        assert_eq!(
+            reason("5."),
+            "Invalid number literal 5. (a decimal point must be followed by digits)"
+        );
+    }
+
+    #[test]
+    fn hexadecimal_and_binary_literals_keep_their_spelling() {
+        assert_eq!(
+            tokens("0xFF")[0].kind,
+            TokenKind::LitNum(255, Name::intern("0xFF"))
+        );
+        assert_eq!(
+            tokens("0b1010")[0].kind,
+            TokenKind::LitNum(10, Name::intern("0b1010"))
+        );
+        assert_eq!(reason("0b102"), "Invalid digit '2' in binary literal 0b102");
+        assert_eq!(
+            reason("0xFG"),
+            "Invalid digit 'G' in hexadecimal literal 0xFG"
+        );
+    }
+
+    #[test]
+    fn comparisons_are_single_tokens() {
+        let cases = [
+            ("==", TokenKind::EqualEqual),
+            ("!=", TokenKind::BangEqual),
+            ("<=", TokenKind::LessEqual),
+            (">=", TokenKind::GreaterEqual),
+        ];
+
+        for (src, kind) in cases {
+            let tokens = tokens(&format!("a {src} b"));
+
+            assert_eq!(tokens.len(), 3);
+            assert_eq!(tokens[1].kind, kind);
+            assert_eq!(bounds(&tokens[1].span), ((1, 3), (1, 4)));
+        }
+
+        assert_eq!(tokens("a < b")[1].kind, TokenKind::Less);
+        assert_eq!(tokens("a > b")[1].kind, TokenKind::Greater);
+        assert_eq!(tokens("a = b")[1].kind, TokenKind::Equal);
+        assert_eq!(tokens("!a")[0].kind, TokenKind::Bang);
+    }
+
+    #[test]
+    fn a_token_after_a_leading_tab_reports_the_expected_column() {
+        assert_eq!(bounds(&tokens("\tlet")[0].span), ((1, 2), (1, 4)));
+
+        let tokens = match Lexer::from_chars("\tlet x".chars()).with_tab_width(4).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("the input should lex: {}", err.reason),
+        };
+
+        assert_eq!(bounds(&tokens[0].span), ((1, 5), (1, 7)));
+        assert_eq!(bounds(&tokens[1].span), ((1, 9), (1, 9)));
+    }
+
+    #[test]
+    fn operators_do_not_span_line_breaks() {
+        let kinds = |src: &str| {
+            tokens(src)
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds("1 =\n= 1"),
+            [
+                TokenKind::LitNum(1, Name::intern("1")),
+                TokenKind::Equal,
+                TokenKind::Equal,
+                TokenKind::LitNum(1, Name::intern("1")),
+            ]
+        );
+        assert_eq!(kinds("-\n>"), [TokenKind::Minus, TokenKind::Greater]);
+        assert_eq!(kinds("<\r\n="), [TokenKind::Less, TokenKind::Equal]);
+        assert_eq!(kinds("!\n="), [TokenKind::Bang, TokenKind::Equal]);
+    }
+
+    #[test]
+    fn slices_and_iterators_lex_the_same_tokens() {
+        let sources = [
+            "fn main() -> i32 {\n    let x = 0xFF;\n    x >= 1 == true\n}",
+            "a =\n= b // comment\r\n'l: { break 'l 1.5; }",
+            "/* a\n/* b */ */ -\n> !\t=",
+        ];
+
+        for src in sources {
+            let chars = src.chars().collect::<Vec<_>>();
+            let from_slice = match Lexer::new(&chars).lex() {
+                Ok(tokens) => tokens,
+                Err(err) => panic!("the input should lex: {}", err.reason),
+            };
+
+            let summary = |tokens: Vec<Token>| {
+                tokens
+                    .into_iter()
+                    .map(|token| (token.kind, bounds(&token.span)))
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(summary(from_slice), summary(tokens(src)), "{src:?}");
+        }
+    }
+
+    #[test]
+    fn reserved_words_are_not_identifiers() {
+        assert_eq!(
+            reason("let while = 1;"),
+            "'while' is a reserved keyword and can't be used as an identifier"
+        );
+        assert_eq!(
+            tokens("whilst")[0].kind,
+            TokenKind::Ident(Name::intern("whilst"))
+        );
+    }
+
+    #[test]
+    fn identifiers_are_ascii() {
+        assert_eq!(
+            reason("let café = 1;"),
+            "Identifiers may only contain ASCII letters, digits and underscores, found 'é'"
+        );
+        assert_eq!(
+            reason("日本"),
+            "Identifiers may only contain ASCII letters, digits and underscores, found '日'"
+        );
+    }
+
+    #[test]
+    fn booleans_are_keywords_unless_they_start_a_longer_word() {
+        assert_eq!(tokens("true")[0].kind, TokenKind::KwTrue);
+        assert_eq!(tokens("false")[0].kind, TokenKind::KwFalse);
+        assert_eq!(
+            tokens("truey")[0].kind,
+            TokenKind::Ident(Name::intern("truey"))
+        );
+    }
+
+    #[test]
+    fn dots_between_names_are_tokens() {
+        let kinds = tokens("a.b")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Ident(Name::intern("a")),
+                TokenKind::Dot,
+                TokenKind::Ident(Name::intern("b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn crlf_line_breaks_start_a_new_line_at_column_one() {
+        let tokens = tokens("let a = 1;\r\nlet bc = 2;");
+
+        assert_eq!(tokens[6].kind, TokenKind::Ident(Name::intern("bc")));
+        assert_eq!(bounds(&tokens[6].span), ((2, 5), (2, 6)));
+    }
+
+    #[test]
+    fn very_long_identifiers_lex_as_one_token() {
+        let ident = "a".repeat(100_000);
+        let tokens = tokens(&format!("let {ident} = 1;"));
+
+        assert_eq!(tokens[1].kind, TokenKind::Ident(Name::intern(&ident)));
+        assert_eq!(bounds(&tokens[1].span), ((1, 5), (1, 100_004)));
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::shared::Span;
+use crate::shared::{Location, Name, Span};
 
 /// Represents a token.
 #[derive(Debug, Clone)]
@@ -22,28 +22,76 @@ impl Token {
     }
 }
 
+/// Given a list of tokens ordered by position, return the index of the token covering `location`. If `location`
+/// falls between two tokens (e.g. on whitespace), the index of the following token is returned instead. Every token
+/// must have a span, as those produced by the lexer do.
+pub fn token_at(tokens: &[Token], location: &Location) -> Option<usize> {
+    // Find the first token that doesn't end before the location
+    let index = tokens.partition_point(|token| {
+        let span = token
+            .span
+            .as_ref()
+            .expect("tokens from the lexer always have a span");
+        &span.end < location
+    });
+
+    if index < tokens.len() {
+        Some(index)
+    } else {
+        None
+    }
+}
+
 /// Represents a token kind.
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TokenKind {
-    KwFn,          // "fn"
-    KwStruct,      // "struct"
-    KwImpl,        // "impl"
-    KwSelf,        // "self"
-    KwLet,         // "let"
-    KwRet,         // "return"
-    Ident(String), // "foo", "bar", "baz"
-    LitNum(i32),   // "123", "0", "5555"
-    Plus,          // +
-    Star,          // *
-    Equal,         // =
-    Colon,         // :
-    Semicolon,     // ;
-    LParen,        // (
-    RParen,        // )
-    LBrace,        // {
-    RBrace,        // }
-    Comma,         // ,
-    RArrow,        // ->
+    KwFn,               // "fn"
+    KwStruct,           // "struct"
+    KwImpl,             // "impl"
+    KwSelf,             // "self"
+    KwLet,              // "let"
+    KwRet,              // "return"
+    KwConst,            // "const"
+    KwTrue,             // "true"
+    KwFalse,            // "false"
+    KwBreak,            // "break"
+    KwIf,               // "if"
+    KwElse,             // "else"
+    KwPub,              // "pub"
+    KwMatch,            // "match"
+    Ident(Name),        // "foo", "bar", "baz"
+    Label(Name),        // "'outer", "'a"
+    LitNum(i32, Name),  // "123", "0", "0x15b3", along with the literal as written
+    LitFloat(f64),      // "3.14", "0.5", "10.0"
+    Plus,               // +
+    Minus,              // -
+    Star,               // *
+    Slash,              // /
+    Percent,            // %
+    Equal,              // =
+    EqualEqual,         // ==
+    BangEqual,          // !=
+    Bang,               // !
+    Less,               // <
+    LessEqual,          // <=
+    Greater,            // >
+    GreaterEqual,       // >=
+    Colon,              // :
+    Semicolon,          // ;
+    LParen,             // (
+    RParen,             // )
+    LBrace,             // {
+    RBrace,             // }
+    LBracket,           // [
+    RBracket,           // ]
+    Comma,              // ,
+    Dot,                // .
+    Ampersand,          // &
+    At,                 // @
+    RArrow,             // ->
+    FatArrow,           // =>
+    DocComment(String), // "/// Some documentation"
     EOF,
 }
 
@@ -56,20 +104,78 @@ impl Display for TokenKind {
             Self::KwSelf => write!(f, "'self'"),
             Self::KwLet => write!(f, "'let'"),
             Self::KwRet => write!(f, "'return'"),
+            Self::KwConst => write!(f, "'const'"),
+            Self::KwTrue => write!(f, "'true'"),
+            Self::KwFalse => write!(f, "'false'"),
+            Self::KwBreak => write!(f, "'break'"),
+            Self::KwIf => write!(f, "'if'"),
+            Self::KwElse => write!(f, "'else'"),
+            Self::KwPub => write!(f, "'pub'"),
+            Self::KwMatch => write!(f, "'match'"),
             Self::Ident(str) => write!(f, "identifier '{str}'"),
-            Self::LitNum(lit) => write!(f, "literal number '{lit}'"),
+            Self::Label(name) => write!(f, "label '{name}"),
+            Self::LitNum(_, raw) => write!(f, "literal number '{raw}'"),
+            Self::LitFloat(lit) => write!(f, "literal float '{lit}'"),
             Self::Plus => write!(f, "'+'"),
+            Self::Minus => write!(f, "'-'"),
             Self::Star => write!(f, "'*'"),
+            Self::Slash => write!(f, "'/'"),
+            Self::Percent => write!(f, "'%'"),
             Self::Equal => write!(f, "'='"),
+            Self::EqualEqual => write!(f, "'=='"),
+            Self::BangEqual => write!(f, "'!='"),
+            Self::Bang => write!(f, "'!'"),
+            Self::Less => write!(f, "'<'"),
+            Self::LessEqual => write!(f, "'<='"),
+            Self::Greater => write!(f, "'>'"),
+            Self::GreaterEqual => write!(f, "'>='"),
             Self::Colon => write!(f, "':'"),
             Self::Semicolon => write!(f, "';'"),
             Self::LParen => write!(f, "'('"),
             Self::RParen => write!(f, "')'"),
             Self::LBrace => write!(f, "'{{'"),
             Self::RBrace => write!(f, "'}}'"),
+            Self::LBracket => write!(f, "'['"),
+            Self::RBracket => write!(f, "']'"),
             Self::Comma => write!(f, "','"),
+            Self::Dot => write!(f, "'.'"),
+            Self::Ampersand => write!(f, "'&'"),
+            Self::At => write!(f, "'@'"),
             Self::RArrow => write!(f, "'->'"),
+            Self::FatArrow => write!(f, "'=>'"),
+            Self::DocComment(_) => write!(f, "doc comment"),
             Self::EOF => write!(f, "<EOF>"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    #[test]
+    fn locations_find_the_token_covering_them() {
+        let tokens = Lexer::from_chars("let count = 1;".chars())
+            .lex()
+            .ok()
+            .unwrap();
+
+        // `count` spans columns 5 to 9
+        assert_eq!(token_at(&tokens, &Location::new(1, 5)), Some(1));
+        assert_eq!(token_at(&tokens, &Location::new(1, 7)), Some(1));
+        assert_eq!(token_at(&tokens, &Location::new(1, 9)), Some(1));
+    }
+
+    #[test]
+    fn whitespace_finds_the_following_token() {
+        let tokens = Lexer::from_chars("let count  = 1;".chars())
+            .lex()
+            .ok()
+            .unwrap();
+
+        assert_eq!(token_at(&tokens, &Location::new(1, 11)), Some(2));
+        assert_eq!(tokens[2].kind, TokenKind::Equal);
+        assert_eq!(token_at(&tokens, &Location::new(2, 1)), None);
+    }
+}
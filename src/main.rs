@@ -1,70 +1,247 @@
-#![feature(concat_idents)]
-
+mod asm;
 mod ast;
+mod info;
+mod interp;
 mod ir;
 mod lexer;
 mod parser;
+mod repl;
 mod resolution;
 mod sema;
 mod shared;
 mod utilities;
 
-use clap::Parser as ClapParser;
-// use ir::LoweringEngine;
+use clap::{CommandFactory, FromArgMatches, Parser as ClapParser, Subcommand, ValueEnum};
+use info::compiler_info;
+use interp::Interpreter;
+use ir::LoweringEngine;
 use resolution::Resolver;
 use sema::basic::Basic;
+use sema::dead_code::DeadCode;
+use sema::escape::Escape;
+use sema::recursion::Recursion;
 use sema::typeck::TypeCk;
 use sema::SemaEngine;
 
+use ast::render::AstRenderer;
+use ast::{File, Ident, Item};
+use shared::{Location, Name, SourceMap, Span};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
 use std::process::exit;
+use std::sync::OnceLock;
 
-use lexer::Lexer;
+use lexer::{token_at, Lexer};
 use parser::Parser;
-use utilities::error;
+use repl::Repl;
+use utilities::{abort, diagnostic, error, warning, Timer};
+
+/// Exit code used when the command line arguments or the source file are invalid.
+const EXIT_USAGE: i32 = 1;
+
+/// Exit code used when lexing or parsing fails.
+const EXIT_SYNTAX: i32 = 2;
+
+/// Exit code used when semantic analysis or type checking fails.
+const EXIT_SEMA: i32 = 3;
+
+/// Exit code used when interpreting the program fails.
+const EXIT_RUNTIME: i32 = 4;
 
 /// The Scooter compiler.
 #[derive(ClapParser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Path to the source file.
-    #[arg(short, long)]
-    source: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Paths to the source files. Their items are merged into one program, sharing a single global namespace.
+    #[arg(short, long, num_args = 1..)]
+    source: Vec<PathBuf>,
+
+    /// Interpret the program and exit with the code returned by `main`. The compiler's own exit codes (1 to 4) can be
+    /// returned by `main` as well, so only the error summary printed to standard error tells a failure apart.
+    #[arg(long)]
+    run: bool,
+
+    /// Run every function marked with `@test` and report which ones failed, exiting with a nonzero code if any did.
+    #[arg(long)]
+    test: bool,
+
+    /// Print every type and function known to the resolver.
+    #[arg(long)]
+    dump_symbols: bool,
+
+    /// Print the token at `LINE:COLUMN` (or the first one after it), for editors to select or look up.
+    #[arg(long, value_name = "LINE:COLUMN", value_parser = parse_location)]
+    token_at: Option<Location>,
+
+    /// Count a tab as this many columns in reported locations (and in `--token-at`), to match an editor.
+    #[arg(long, value_name = "WIDTH", default_value_t = 1)]
+    tab_width: usize,
+
+    /// Print how long each phase of the compiler took.
+    #[arg(long)]
+    time: bool,
+
+    /// Print an intermediate form of the program to standard output.
+    #[arg(long, value_enum)]
+    emit: Option<Emit>,
+
+    /// Print diagnostics without colors. Colors are also left out when standard error isn't a terminal.
+    #[arg(long)]
+    no_color: bool,
+}
+
+/// Modes other than compiling source files.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start an interactive session, which evaluates expressions as they're entered.
+    Repl,
+}
+
+/// The intermediate forms of a program that can be printed with `--emit`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Emit {
+    /// The syntax tree, one node per line.
+    Ast,
+
+    /// The lowered intermediate representation.
+    Ir,
 }
 
 fn main() {
-    let args = Args::parse();
+    // `--version` also lists the targets and features of this build, which clap needs as a static string
+    static VERSION: OnceLock<String> = OnceLock::new();
+    let version = VERSION.get_or_init(|| compiler_info().to_string());
 
-    let source = match args.source {
-        Some(source) => source,
-        None => PathBuf::from("."),
+    let matches = Args::command().long_version(version.as_str()).get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    if args.no_color || !io::stderr().is_terminal() {
+        colored::control::set_override(false);
+    }
+
+    if let Some(Command::Repl) = args.command {
+        if let Err(err) = Repl::new().run() {
+            error(
+                format!("Couldn't read input: {err}"),
+                &SourceMap::new(),
+                None,
+            );
+            abort(1, EXIT_USAGE);
+        }
+
+        return;
+    }
+
+    let paths = match args.source.is_empty() {
+        true => vec![PathBuf::from(".")],
+        false => args.source,
     };
 
-    // Read the source file
-    let source = fs::read_to_string(source).unwrap();
+    // Read the source files
+    let mut texts = Vec::new();
+    for path in &paths {
+        match fs::read_to_string(path) {
+            Ok(text) => texts.push(text),
+            Err(err) => {
+                error(
+                    format!("Couldn't read {}: {err}", path.display()),
+                    &SourceMap::new(),
+                    None,
+                );
+                abort(1, EXIT_USAGE);
+            }
+        }
+    }
 
-    // We'll begin by lexing the source
-    let slice = source.chars().collect::<Vec<char>>();
+    let mut timer = Timer::new();
 
-    let mut lexer = Lexer::new(&slice);
-    let tokens = match lexer.lex() {
-        Ok(tokens) => tokens,
-        Err(err) => {
+    // We'll begin by lexing every file, numbering the lines of each one after those of the file before it
+    let mut source = SourceMap::new().with_tab_width(args.tab_width);
+    let mut token_lists = Vec::new();
+    let mut lex_errors = Vec::new();
+
+    for (path, text) in paths.iter().zip(&texts) {
+        let first_line = source.add(&path.display().to_string(), text);
+        let mut lexer = Lexer::from_chars(text.chars())
+            .with_first_line(first_line)
+            .with_tab_width(args.tab_width);
+        let (tokens, errs) = lexer.lex_all();
+
+        token_lists.push(tokens);
+        lex_errors.extend(errs);
+    }
+
+    // Every lexing error in every file is reported at once
+    if !lex_errors.is_empty() {
+        let count = lex_errors.len();
+        for err in lex_errors {
             error(err.reason, &source, err.span);
-            exit(1);
         }
-    };
+        abort(count, EXIT_SYNTAX);
+    }
 
-    // Now, parse the tokens into a syntax tree
-    let mut parser = Parser::new(&tokens);
-    let ast = match parser.parse_file() {
-        Ok(ast) => ast,
-        Err(err) => {
+    timer.lap("lexing");
+
+    // Lines are numbered across every file in order, so the tokens of all files together are still ordered
+    if let Some(location) = &args.token_at {
+        let tokens = token_lists.concat();
+
+        match token_at(&tokens, location) {
+            Some(index) => {
+                let start = &tokens[index].span.as_ref().unwrap().start;
+                println!("{}:{} {}", start.line, start.column, tokens[index].kind);
+            }
+            None => println!("No token at or after {}:{}", location.line, location.column),
+        }
+    }
+
+    // Now, parse the tokens of every file into a syntax tree
+    let mut files = Vec::new();
+    let mut syntax_errors = 0;
+
+    for tokens in &token_lists {
+        // The syntax tree only keeps doc comments when it's printed, since no other phase reads them
+        let mut parser = match args.emit {
+            Some(Emit::Ast) => Parser::new(tokens).with_docs(),
+            _ => Parser::new(tokens),
+        };
+
+        let (file, errors) = parser.parse_file();
+        syntax_errors += errors.len();
+
+        for err in errors {
             error(err.reason, &source, err.span);
-            exit(1);
         }
-    };
+
+        files.push(file);
+    }
+
+    if syntax_errors > 0 {
+        abort(syntax_errors, EXIT_SYNTAX);
+    }
+
+    // Items with the same name in different files would silently replace each other once merged
+    let clashes = clashes(&files, &source);
+    if !clashes.is_empty() {
+        let count = clashes.len();
+        for (reason, span) in clashes {
+            error(reason, &source, Some(span));
+        }
+        abort(count, EXIT_SEMA);
+    }
+
+    let ast = File::merge(files);
+
+    timer.lap("parsing");
+
+    if let Some(Emit::Ast) = args.emit {
+        print!("{}", AstRenderer::render(&ast));
+    }
 
     // Next, let's perform semantic analysis!
     // First, we'll need to collect all exisiting function declarations.
@@ -72,27 +249,238 @@ fn main() {
     resolver.collect_tys();
     resolver.collect_functions();
 
+    timer.lap("resolution");
+
+    if args.dump_symbols {
+        print!("{}", resolver.dump());
+    }
+
     // Now we can run some simple semantic analysis
-    let mut sema = SemaEngine::new(&ast).register(Box::new(Basic::new()));
+    let mut sema = SemaEngine::new(&ast)
+        .register(Box::new(Basic::new()))
+        .register(Box::new(DeadCode::new()))
+        .register(Box::new(Escape::new()))
+        .register(Box::new(Recursion::new()));
 
-    if let Err(errs) = sema.run() {
+    let result = sema.run();
+    timer.lap("sema");
+
+    for warn in sema.warnings() {
+        warning(&warn.reason, &source, warn.span.clone());
+    }
+
+    if let Err(errs) = result {
         // Output every error that occured
+        let count = errs.len();
         for err in errs {
             error(err.reason, &source, err.span);
         }
-        exit(1);
+        abort(count, EXIT_SEMA);
     }
 
     // Also perform type checking
-    let typeck = TypeCk::new(resolver);
-    if let Err(err) = typeck.run(&ast) {
-        error(&err.reason, &source, err.span);
-        exit(1);
+    let typeck = TypeCk::new(&resolver);
+    let result = typeck.run(&ast);
+    timer.lap("type checking");
+
+    let types = match result {
+        Ok(types) => types,
+        Err(err) => {
+            diagnostic(&err, &source);
+            abort(1, EXIT_SEMA);
+        }
+    };
+
+    // The IR isn't used for anything else yet, so the program is only lowered to print it or to time the lowering
+    let emit_ir = matches!(args.emit, Some(Emit::Ir));
+    if emit_ir || args.time {
+        let mut engine = LoweringEngine::new(&ast, &types);
+        let ir = engine.lower();
+        timer.lap("lowering");
+
+        if emit_ir {
+            let _ = ir.render(&mut io::stdout());
+        }
+    }
+
+    if args.test {
+        let failed = run_tests(&ast, &source);
+        timer.lap("testing");
+
+        if args.time {
+            timer.report();
+        }
+
+        exit(if failed > 0 { EXIT_RUNTIME } else { 0 });
+    }
+
+    if args.run {
+        let result = Interpreter::new(&ast).run();
+        timer.lap("interpreting");
+
+        if args.time {
+            timer.report();
+        }
+
+        // The exit code belongs to the program, even when it matches one the compiler uses for its own failures
+        match result {
+            Ok(code) => exit(code),
+            Err(err) => {
+                error(err.reason, &source, err.span);
+                abort(1, EXIT_RUNTIME);
+            }
+        }
+    }
+
+    if args.time {
+        timer.report();
+    }
+}
+
+/// Parse a location given on the command line as `LINE:COLUMN`.
+fn parse_location(arg: &str) -> Result<Location, String> {
+    let (line, column) = arg
+        .split_once(':')
+        .ok_or_else(|| String::from("expected a location of the form LINE:COLUMN"))?;
+
+    let line = line.parse().map_err(|_| format!("invalid line '{line}'"))?;
+    let column = column
+        .parse()
+        .map_err(|_| format!("invalid column '{column}'"))?;
+
+    Ok(Location::new(line, column))
+}
+
+/// Run every function marked with `@test`, in declaration order, printing whether each one passed. A test fails if it
+/// runs into a runtime error (such as a failed `assert`). Returns how many tests failed.
+fn run_tests(ast: &File, source: &SourceMap) -> usize {
+    let tests = ast
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(item_fn) if item_fn.is_test() => Some(item_fn),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut failed = 0;
+
+    for test in &tests {
+        // Every test starts from a fresh interpreter, so tests can't affect each other
+        match Interpreter::new(ast).eval(&test.body) {
+            Ok(_) => println!("test {} ... ok", test.ident.repr),
+            Err(err) => {
+                println!("test {} ... FAILED", test.ident.repr);
+                error(err.reason, source, err.span);
+                failed += 1;
+            }
+        }
     }
 
-    // // Next, we'll lower the AST to IR and generate a human readable IR file
-    // let mut lower = LoweringEngine::new(&ast);
-    // let ir = lower.lower();
+    println!("\n{} passed, {} failed", tests.len() - failed, failed);
+    failed
+}
+
+/// Find the items declared in more than one file, returning an error message and span for every declaration after
+/// the first.
+fn clashes(files: &[File], source: &SourceMap) -> Vec<(String, Span)> {
+    let mut seen: HashMap<Name, &Ident> = HashMap::new();
+    let mut clashes = Vec::new();
+
+    for file in files {
+        for ident in file.items.iter().filter_map(Item::ident) {
+            match seen.get(&ident.repr) {
+                Some(first) => {
+                    let (first_path, _) = source.locate(first.span.start.line).unwrap_or_default();
+                    let (path, _) = source.locate(ident.span.start.line).unwrap_or_default();
+
+                    if first_path != path {
+                        clashes.push((
+                            format!(
+                                "'{}' is declared in both {first_path} and {path}",
+                                ident.repr
+                            ),
+                            ident.span.clone(),
+                        ));
+                    }
+                }
+
+                None => {
+                    seen.insert(ident.repr, ident);
+                }
+            }
+        }
+    }
+
+    clashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Add every file to a source map and parse it, numbering lines across the files like `main` does.
+    fn parse_files(texts: &[(&str, &str)]) -> (Vec<File>, SourceMap) {
+        let mut source = SourceMap::new();
+        let mut files = Vec::new();
 
-    // let _ = ir.human_readable("./out.ir");
+        for (path, text) in texts {
+            let first_line = source.add(path, text);
+            let tokens = match Lexer::from_chars(text.chars())
+                .with_first_line(first_line)
+                .lex()
+            {
+                Ok(tokens) => tokens,
+                Err(err) => panic!("{path} should lex, but: {}", err.reason),
+            };
+
+            match Parser::new(&tokens).parse_file() {
+                (file, errors) if errors.is_empty() => files.push(file),
+                (_, errors) => panic!("{path} should parse, but: {}", errors[0].reason),
+            }
+        }
+
+        (files, source)
+    }
+
+    #[test]
+    fn only_items_declared_in_different_files_clash() {
+        let (files, source) = parse_files(&[
+            (
+                "a.scoot",
+                "fn helper() -> i32 { 1 }\nfn main() -> i32 { 0 }",
+            ),
+            ("b.scoot", "struct S {}\nfn helper() -> i32 { 2 }"),
+        ]);
+
+        let found = clashes(&files, &source);
+        assert_eq!(found.len(), 1);
+
+        let (reason, span) = &found[0];
+        assert_eq!(reason, "'helper' is declared in both a.scoot and b.scoot");
+        assert_eq!((span.start.line, span.start.column), (4, 4));
+
+        // Duplicates within a single file are left for later checks to report
+        let (files, source) =
+            parse_files(&[("a.scoot", "fn f() -> i32 { 1 }\nfn f() -> i32 { 2 }")]);
+        assert!(clashes(&files, &source).is_empty());
+    }
+
+    #[test]
+    fn main_can_call_a_helper_declared_in_another_file() {
+        let (files, _) = parse_files(&[
+            ("main.scoot", "fn main() -> i32 { helper(2) }"),
+            ("helper.scoot", "fn helper(x: i32) -> i32 { x * 3 }"),
+        ]);
+        let ast = File::merge(files);
+
+        let mut resolver = Resolver::new(&ast);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        // The call only type checks if `helper` resolves to the function declared in the other file
+        if let Err(err) = TypeCk::new(&resolver).run(&ast) {
+            panic!("the merged program should type check, but: {}", err.reason);
+        }
+    }
 }
@@ -1,2 +1,99 @@
+#[allow(clippy::module_inception)]
 mod parser;
 pub use parser::*;
+
+use crate::ast::File;
+use crate::lexer::Lexer;
+
+/// Lex and parse a program in one go. Lexing errors are reported as parse errors. This never panics, no matter the
+/// input, which makes it a convenient entry point for fuzzing.
+#[allow(dead_code)]
+pub fn parse_str(src: &str) -> Result<File, Vec<ParseError>> {
+    let tokens = Lexer::from_chars(src.chars()).lex().map_err(|err| {
+        vec![ParseError {
+            reason: err.reason,
+            span: err.span,
+        }]
+    })?;
+
+    match Parser::new(&tokens).parse_file() {
+        (file, errors) if errors.is_empty() => Ok(file),
+        (_, errors) => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program that uses most of the syntax, so that cutting it short leaves all kinds of unfinished constructs.
+    const PROGRAM: &str = "
+        /// A point.
+        struct P { x: i32, y: bool }
+        struct Pair(i32, i32);
+        const N: i32 = 0x10 + 0b1;
+
+        impl P {
+            fn get(self) -> i32 { self.x }
+        }
+
+        @test
+        fn check() -> () { assert(!false); }
+
+        fn main() -> i32 {
+            let p = P { x: 1, y: true };
+            let t: (i32, bool) = (2, false);
+            let v = 'outer: { if p.y { break 'outer t.0; } match p.x { 1 => 2, _ => 3 } };
+            return -v * N % 3 >= 1;
+        }
+    ";
+
+    #[test]
+    fn malformed_programs_are_rejected() {
+        let cases = [
+            "fn",
+            "fn main",
+            "fn main(",
+            "fn main() ->",
+            "fn main() -> i32 {",
+            "fn main() -> i32 { 1 + }",
+            "fn main() -> i32 { let = 1; }",
+            "fn main() -> i32 { ((1) }",
+            "fn main() -> i32 { 1 } }",
+            "struct",
+            "struct S {",
+            "struct S { x: }",
+            "struct S(i32",
+            "impl",
+            "impl S { fn }",
+            "const N: i32 =",
+            "@",
+            "@test",
+            "fn main() -> i32 { 'a }",
+            "fn main() -> i32 { break; }",
+            "fn main() -> i32 { 0x }",
+            "fn main() -> i32 { 1 $ 2 }",
+            "\"",
+            "}",
+        ];
+
+        for src in cases {
+            assert!(parse_str(src).is_err(), "{src:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn every_truncation_of_a_program_is_handled() {
+        assert!(parse_str(PROGRAM).is_ok());
+
+        // Truncated programs are usually rejected, but the only real requirement is that they don't panic
+        let mut rejected = 0;
+        for (end, _) in PROGRAM.char_indices() {
+            if parse_str(&PROGRAM[..end]).is_err() {
+                rejected += 1;
+            }
+        }
+
+        assert!(rejected > 0);
+    }
+}
@@ -1,12 +1,15 @@
-use std::fmt::format;
+use std::rc::Rc;
 
 use crate::ast::{
-    ArgList, BinaryOp, Block, CallFn, Expr, ExprBin, ExprCall, ExprLit, ExprStruct, FieldNamed,
-    Fields, FieldsNamed, File, Ident, ImplItem, ImplItemFn, ImplParamList, Item, ItemFn, ItemImpl,
-    ItemStruct, LitNum, Local, NamedArg, NamedArgList, OpKind, Param, ParamList, Return, Stmt, Ty,
+    ArgList, Arm, Assign, Attr, BinaryOp, Block, BlockLabel, Break, CallFn, CmpOp, CmpOpKind, Expr,
+    ExprBin, ExprBlock, ExprCall, ExprCmp, ExprField, ExprIf, ExprLit, ExprMatch, ExprMethodCall,
+    ExprRef, ExprStruct, ExprTuple, ExprTupleIndex, ExprUnary, FieldNamed, Fields, FieldsNamed,
+    FieldsUnnamed, File, Ident, ImplItem, ImplItemFn, ImplParamList, Item, ItemConst, ItemFn,
+    ItemImpl, ItemStruct, LitBool, LitNum, Local, NamedArg, NamedArgList, OpKind, Param, ParamList,
+    Pat, Return, Stmt, Ty, TyArray, TyFn, TyRef, TyTuple, UnaryOp, UnaryOpKind, Visibility,
 };
 use crate::lexer::{Token, TokenKind};
-use crate::shared::Span;
+use crate::shared::{Name, Span};
 
 /// Represents an error that occured during parsing.
 pub struct ParseError {
@@ -20,6 +23,14 @@ pub struct ParseError {
 /// Represents the result of parsing.
 type ParseResult<T> = Result<T, ParseError>;
 
+/// How deeply expressions and types may be nested. Deeper nesting is almost certainly a mistake (or a fuzzer), and
+/// is rejected before the parser runs out of stack space.
+const MAX_DEPTH: usize = 64;
+
+/// How many errors the parser reports before giving up on the rest of the input, unless configured otherwise. A badly
+/// broken file would otherwise bury the first (and most useful) errors under the ones that follow from them.
+pub const MAX_ERRORS: usize = 20;
+
 pub struct Parser<'a> {
     /// The tokens of an entire file.
     input: &'a [Token],
@@ -29,51 +40,209 @@ pub struct Parser<'a> {
 
     /// The current span.
     starts: Vec<Span>,
+
+    /// How deeply the expression or type currently being parsed is nested.
+    depth: usize,
+
+    /// Whether struct expressions are currently ruled out. In the condition of an `if`, `x { ... }` is the variable
+    /// `x` followed by a block, not a struct expression.
+    no_structs: bool,
+
+    /// The errors the parser has recovered from so far.
+    errors: Vec<ParseError>,
+
+    /// How many errors are reported before the parser gives up.
+    max_errors: usize,
+
+    /// Whether doc comments are kept on the items they document, for documentation tooling. Otherwise, they're
+    /// skipped like any other comment.
+    docs: bool,
+
+    /// Stands in for the current token once every token has been consumed. It sits just past the last token, so
+    /// errors about a truncated file point at its end.
+    eof: Token,
 }
 
 impl<'a> Parser<'a> {
     /// Create a new parser.
     pub fn new(input: &'a [Token]) -> Self {
+        let eof = match input.last().and_then(|token| token.span.as_ref()) {
+            Some(span) => Span::single(span.end.line, span.end.column + 1),
+            None => Span::single(1, 1),
+        };
+
         Parser {
             input,
             index: 0,
             starts: vec![],
+            depth: 0,
+            no_structs: false,
+            errors: Vec::new(),
+            max_errors: MAX_ERRORS,
+            docs: false,
+            eof: Token::spanned(TokenKind::EOF, eof),
         }
     }
 
-    /// Parse an entire file.
-    pub fn parse_file(&mut self) -> ParseResult<File> {
+    /// Report at most `max_errors` errors (and at least one) before giving up on the rest of the input.
+    #[allow(dead_code)]
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors.max(1);
+        self
+    }
+
+    /// Keep the doc comments of items, fields and methods in the syntax tree.
+    pub fn with_docs(mut self) -> Self {
+        self.docs = true;
+        self
+    }
+
+    /// Parse an entire file. When an item can't be parsed, the error is recorded and the parser skips ahead to the
+    /// next item, so that every broken item is reported at once. Returns the items that could be parsed, along with
+    /// every error that was recorded.
+    pub fn parse_file(&mut self) -> (File, Vec<ParseError>) {
         self.start();
 
         let mut items: Vec<Item> = Vec::new();
 
-        while self.current_kind() != &TokenKind::EOF && self.current_kind() != &TokenKind::RBrace {
-            items.push(self.parse_item()?);
+        while self.current_kind() != &TokenKind::EOF {
+            let (index, starts) = (self.index, self.starts.len());
+
+            // Doc comments at the end of the file don't document anything
+            if self.input[self.index..]
+                .iter()
+                .all(|token| matches!(token.kind, TokenKind::DocComment(_)))
+            {
+                break;
+            }
+
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(err) => {
+                    let more = self.record(err);
+                    self.recover(index, starts);
+
+                    if !more {
+                        break;
+                    }
+                }
+            }
         }
 
-        Ok(File {
+        let file = File {
             items,
             span: self.end(),
-        })
+        };
+
+        (file, std::mem::take(&mut self.errors))
+    }
+
+    /// Get back on track after an error inside the item starting at token `index`, by skipping ahead to the start of
+    /// the next item (or the end of the file). Anything left over from the abandoned item is dropped as well.
+    fn recover(&mut self, index: usize, starts: usize) {
+        self.starts.truncate(starts);
+        self.depth = 0;
+        self.no_structs = false;
+
+        // Skip at least one token, so that an item which fails right away isn't parsed over and over
+        if self.index == index {
+            self.advance(1);
+        }
+
+        while !matches!(
+            self.current_kind(),
+            TokenKind::EOF
+                | TokenKind::KwFn
+                | TokenKind::KwStruct
+                | TokenKind::KwImpl
+                | TokenKind::KwConst
+                | TokenKind::KwPub
+                | TokenKind::At
+                | TokenKind::DocComment(_)
+        ) {
+            self.advance(1);
+        }
     }
 
-    /// Parse an item.
+    /// Parse an item. Only functions may have attributes, and only functions and structs may be public.
     fn parse_item(&mut self) -> ParseResult<Item> {
+        let docs = self.parse_docs();
+        let attrs = self.parse_attrs()?;
+        let vis = self.parse_vis();
         let kind = self.current_kind();
 
+        if let (Some(attr), false) = (attrs.first(), kind == &TokenKind::KwFn) {
+            return Err(ParseError {
+                reason: format!("Attributes can only be applied to functions, found {kind}"),
+                span: Some(attr.span.clone()),
+            });
+        }
+
+        if let (Visibility::Public(kw), TokenKind::KwImpl | TokenKind::KwConst) = (&vis, kind) {
+            return Err(ParseError {
+                reason: format!("'pub' can only be applied to functions and structs, found {kind}"),
+                span: kw.span.clone(),
+            });
+        }
+
         match kind {
-            TokenKind::KwFn => self.parse_item_fn(),
-            TokenKind::KwStruct => self.parse_item_struct(),
-            TokenKind::KwImpl => self.parse_item_impl(),
+            TokenKind::KwFn => self.parse_item_fn(docs, attrs, vis),
+            TokenKind::KwStruct => self.parse_item_struct(docs, vis),
+            TokenKind::KwImpl => self.parse_item_impl(docs),
+            TokenKind::KwConst => self.parse_item_const(docs),
             _ => Err(ParseError {
-                reason: format!("Expected 'fn' or 'mod', found {kind}"),
-                span: Some(self.end()),
+                reason: format!("Expected 'fn', 'struct', 'impl' or 'const', found {kind}"),
+                span: self.current().span.clone(),
             }),
         }
     }
 
+    /// Parse an optional `pub` keyword.
+    fn parse_vis(&mut self) -> Visibility {
+        match self.current_kind() {
+            TokenKind::KwPub => {
+                let kw = self.current().clone();
+                self.advance(1);
+                Visibility::Public(kw)
+            }
+            _ => Visibility::Private,
+        }
+    }
+
+    /// Collect the doc comments preceding an item. They're skipped (and left out) unless the parser keeps docs.
+    fn parse_docs(&mut self) -> Vec<String> {
+        let mut docs = Vec::new();
+
+        while let TokenKind::DocComment(text) = self.current_kind() {
+            if self.docs {
+                docs.push(text.clone());
+            }
+
+            self.advance(1);
+        }
+
+        docs
+    }
+
+    /// Parse the attributes preceding an item (`attr ::= "@" ident`).
+    fn parse_attrs(&mut self) -> ParseResult<Vec<Attr>> {
+        let mut attrs = Vec::new();
+
+        while self.current_kind() == &TokenKind::At {
+            self.start();
+
+            attrs.push(Attr {
+                at: self.expect(TokenKind::At)?,
+                ident: self.parse_ident()?,
+                span: self.end(),
+            });
+        }
+
+        Ok(attrs)
+    }
+
     /// Parse an impl block.
-    fn parse_item_impl(&mut self) -> ParseResult<Item> {
+    fn parse_item_impl(&mut self, docs: Vec<String>) -> ParseResult<Item> {
         self.start();
         let kw = self.expect(TokenKind::KwImpl)?;
 
@@ -87,6 +256,7 @@ impl<'a> Parser<'a> {
         }
 
         Ok(Item::Impl(ItemImpl {
+            docs,
             kw,
             ident,
             lb,
@@ -97,9 +267,11 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_impl_item(&mut self) -> ParseResult<ImplItem> {
+        let docs = self.parse_docs();
         let kind = self.current_kind();
+
         if kind == &TokenKind::KwFn {
-            self.parse_impl_item_fn()
+            self.parse_impl_item_fn(docs)
         } else {
             Err(ParseError {
                 reason: format!("Expected a function item, found {kind}"),
@@ -108,43 +280,105 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_impl_item_fn(&mut self) -> ParseResult<ImplItem> {
+    fn parse_impl_item_fn(&mut self, docs: Vec<String>) -> ParseResult<ImplItem> {
         // Start a new span
         self.start();
 
+        let kw = self.expect(TokenKind::KwFn)?;
+        let ident = self.parse_ident()?;
+
         Ok(ImplItem::Fn(ImplItemFn {
-            kw: self.expect(TokenKind::KwFn)?,
-            ident: self.parse_ident()?,
+            docs,
+            kw,
             lp: self.expect(TokenKind::LParen)?,
             params: self.parse_impl_param_list()?,
             rp: self.expect(TokenKind::RParen)?,
             arrow: self.expect(TokenKind::RArrow)?,
             ty: self.parse_ty()?,
-            body: self.parse_block()?,
+            body: self.parse_fn_body(&ident)?,
+            ident,
             span: self.end(),
         }))
     }
 
     /// Parse a struct declaration.
-    fn parse_item_struct(&mut self) -> ParseResult<Item> {
+    fn parse_item_struct(&mut self, docs: Vec<String>, vis: Visibility) -> ParseResult<Item> {
         self.start();
 
+        let kw = self.expect(TokenKind::KwStruct)?;
+        let ident = self.parse_ident()?;
+        let fields = self.parse_fields()?;
+
+        let semi = match &fields {
+            Fields::Unnamed(_) => Some(self.expect(TokenKind::Semicolon)?),
+            Fields::Named(_) => None,
+        };
+
         Ok(Item::Struct(ItemStruct {
-            kw: self.expect(TokenKind::KwStruct)?,
+            docs,
+            vis,
+            kw,
+            ident,
+            fields,
+            semi,
+            span: self.end(),
+        }))
+    }
+
+    /// Parse a constant declaration.
+    fn parse_item_const(&mut self, docs: Vec<String>) -> ParseResult<Item> {
+        self.start();
+
+        Ok(Item::Const(ItemConst {
+            docs,
+            kw: self.expect(TokenKind::KwConst)?,
             ident: self.parse_ident()?,
-            fields: self.parse_fields()?,
+            colon: self.expect(TokenKind::Colon)?,
+            ty: self.parse_ty()?,
+            eq: self.expect(TokenKind::Equal)?,
+            expr: self.parse_expr()?,
+            semi: self.expect(TokenKind::Semicolon)?,
             span: self.end(),
         }))
     }
 
     fn parse_fields(&mut self) -> ParseResult<Fields> {
-        if self.current_kind() == &TokenKind::LBrace {
-            self.parse_fields_named()
-        } else {
-            todo!()
+        match self.current_kind() {
+            TokenKind::LBrace => self.parse_fields_named(),
+            TokenKind::LParen => self.parse_fields_unnamed(),
+            kind => Err(ParseError {
+                reason: format!(
+                    "Expected '{{' or '(' to begin the fields of a struct, found {kind}"
+                ),
+                span: self.current().span.clone(),
+            }),
         }
     }
 
+    /// Parse a list of positional fields (`fields-unnamed ::= "(" { ty "," } ")"`).
+    fn parse_fields_unnamed(&mut self) -> ParseResult<Fields> {
+        self.start();
+
+        let lp = self.expect(TokenKind::LParen)?;
+
+        let mut fields = Vec::new();
+
+        while self.current_kind() != &TokenKind::RParen {
+            fields.push(self.parse_ty()?);
+
+            if self.current_kind() != &TokenKind::RParen {
+                self.expect(TokenKind::Comma)?;
+            }
+        }
+
+        Ok(Fields::Unnamed(FieldsUnnamed {
+            lp,
+            fields,
+            rp: self.expect(TokenKind::RParen)?,
+            span: self.end(),
+        }))
+    }
+
     fn parse_fields_named(&mut self) -> ParseResult<Fields> {
         self.start();
 
@@ -169,8 +403,12 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_field_named(&mut self) -> ParseResult<FieldNamed> {
+        let docs = self.parse_docs();
+
         self.start();
         Ok(FieldNamed {
+            docs,
+            vis: self.parse_vis(),
             ident: self.parse_ident()?,
             colon: self.expect(TokenKind::Colon)?,
             ty: self.parse_ty()?,
@@ -179,23 +417,46 @@ impl<'a> Parser<'a> {
     }
 
     /// Parse a function declaration.
-    fn parse_item_fn(&mut self) -> ParseResult<Item> {
+    fn parse_item_fn(
+        &mut self,
+        docs: Vec<String>,
+        attrs: Vec<Attr>,
+        vis: Visibility,
+    ) -> ParseResult<Item> {
         // Start a new span
         self.start();
 
+        let kw = self.expect(TokenKind::KwFn)?;
+        let ident = self.parse_ident()?;
+
         Ok(Item::Fn(ItemFn {
-            kw: self.expect(TokenKind::KwFn)?,
-            ident: self.parse_ident()?,
+            docs,
+            attrs,
+            vis,
+            kw,
             lp: self.expect(TokenKind::LParen)?,
             params: self.parse_param_list()?,
             rp: self.expect(TokenKind::RParen)?,
             arrow: self.expect(TokenKind::RArrow)?,
             ty: self.parse_ty()?,
-            body: self.parse_block()?,
+            body: self.parse_fn_body(&ident)?,
+            ident,
             span: self.end(),
         }))
     }
 
+    /// Parse the body of the function named `ident`. Declarations without a body aren't supported.
+    fn parse_fn_body(&mut self, ident: &Ident) -> ParseResult<Block> {
+        if self.current_kind() == &TokenKind::Semicolon {
+            return Err(ParseError {
+                reason: format!("Function '{}' has no body", ident.repr),
+                span: self.current().span.clone(),
+            });
+        }
+
+        self.parse_block()
+    }
+
     /// Parse a list of function parameters.
     fn parse_param_list(&mut self) -> ParseResult<ParamList> {
         self.start();
@@ -269,7 +530,7 @@ impl<'a> Parser<'a> {
                 self.advance(1);
                 Ok(Ident {
                     repr: raw,
-                    span: current.span.unwrap(),
+                    span: span_of(&current),
                 })
             }
             _ => Err(ParseError {
@@ -286,129 +547,560 @@ impl<'a> Parser<'a> {
         // Get the left curly brace
         let lc = self.expect(TokenKind::LBrace)?;
 
-        // Collect the statements
+        // Collect the statements, followed by an optional tail expression without a semicolon
         let mut stmts = Vec::new();
-        while self.current_kind() != &TokenKind::RBrace {
-            stmts.push(self.parse_stmt()?);
-            self.expect(TokenKind::Semicolon)?;
+        let mut tail = None;
+
+        loop {
+            // Only items and their members keep their docs, so doc comments on statements are skipped
+            self.parse_docs();
+
+            if self.current_kind() == &TokenKind::RBrace {
+                break;
+            }
+
+            // Struct expressions are allowed again inside braces, even in a condition
+            match self.structs(true, Self::parse_stmt)? {
+                // An expression without a semicolon is the tail expression, which must be the last thing in the block
+                // Block-like expressions (such as `if`) can be used as statements without a semicolon
+                Stmt::Expr(expr @ (Expr::If(_) | Expr::Block(_) | Expr::Match(_)))
+                    if !matches!(
+                        self.current_kind(),
+                        TokenKind::Semicolon | TokenKind::RBrace
+                    ) =>
+                {
+                    stmts.push(Stmt::Expr(expr));
+                }
+
+                Stmt::Expr(expr) if self.current_kind() != &TokenKind::Semicolon => {
+                    // Anything other than the closing brace means a semicolon is missing, so rather than complaining
+                    // about the next token, point at the spot right after the expression where it belongs
+                    if self.current_kind() != &TokenKind::RBrace {
+                        let end = &expr.span().end;
+
+                        return Err(ParseError {
+                            reason: format!(
+                                "Expected ';' after this expression but found {}",
+                                self.current_kind()
+                            ),
+                            span: Some(Span::single(end.line, end.column + 1)),
+                        });
+                    }
+
+                    tail = Some(Box::new(expr));
+                    break;
+                }
+
+                stmt => {
+                    stmts.push(stmt);
+                    self.expect(TokenKind::Semicolon)?;
+                }
+            }
         }
 
         Ok(Block {
             lc,
             stmts,
+            tail,
             rc: self.expect(TokenKind::RBrace)?,
             span: self.end(),
         })
     }
 
-    /// Parse a statement.
+    /// Parse a statement, without its semicolon. Anything that isn't a `let`, `return` or `break` statement or an
+    /// assignment is an expression statement (`foo();`).
     fn parse_stmt(&mut self) -> ParseResult<Stmt> {
+        match self.current_kind() {
+            TokenKind::KwLet => Ok(Stmt::Local(self.parse_local()?)),
+            // Expression statements can start with an identifier too, so only the `=` after it marks an assignment
+            TokenKind::Ident(_) if self.peek(1) == &TokenKind::Equal => {
+                Ok(Stmt::Assign(self.parse_assign()?))
+            }
+            TokenKind::KwRet => Ok(Stmt::Return(self.parse_return()?)),
+            TokenKind::KwBreak => Ok(Stmt::Break(self.parse_break()?)),
+            _ => Ok(Stmt::Expr(self.parse_expr()?)),
+        }
+    }
+
+    /// Parse an assignment (`assign ::= ident "=" expr`).
+    fn parse_assign(&mut self) -> ParseResult<Assign> {
+        self.start();
+
+        Ok(Assign {
+            ident: self.parse_ident()?,
+            eq: self.expect(TokenKind::Equal)?,
+            expr: self.parse_expr()?,
+            span: self.end(),
+        })
+    }
+
+    /// Parse a return statement (`return [ expr ]`).
+    fn parse_return(&mut self) -> ParseResult<Return> {
+        self.start();
+
+        Ok(Return {
+            kw: self.expect(TokenKind::KwRet)?,
+            expr: match self.current_kind() {
+                TokenKind::Semicolon => None,
+                _ => Some(self.parse_expr()?),
+            },
+            span: self.end(),
+        })
+    }
+
+    /// Parse a break statement (`break label [ expr ]`).
+    fn parse_break(&mut self) -> ParseResult<Break> {
+        self.start();
+
+        Ok(Break {
+            kw: self.expect(TokenKind::KwBreak)?,
+            label: self.parse_label()?,
+            expr: match self.current_kind() {
+                TokenKind::Semicolon => None,
+                _ => Some(self.parse_expr()?),
+            },
+            span: self.end(),
+        })
+    }
+
+    /// Parse a block label (`'outer`).
+    fn parse_label(&mut self) -> ParseResult<BlockLabel> {
         let current = self.current().clone();
 
         match current.kind {
-            TokenKind::KwLet => Ok(Stmt::Local(self.parse_local()?)),
-            TokenKind::KwRet => Ok(Stmt::Return(self.parse_return()?)),
+            TokenKind::Label(name) => {
+                self.advance(1);
+                Ok(BlockLabel {
+                    name,
+                    span: span_of(&current),
+                })
+            }
             _ => Err(ParseError {
-                reason: format!("Unknown statement beginning with {}", current.kind),
+                reason: format!("Expected a label, found {}", current.kind),
                 span: current.span,
             }),
         }
     }
 
-    /// Parse a return statement.
-    fn parse_return(&mut self) -> ParseResult<Return> {
+    /// Parse a block expression, which may be labeled (`expr-block ::= [ label ":" ] block`).
+    fn parse_expr_block(&mut self) -> ParseResult<Expr> {
         self.start();
 
-        Ok(Return {
-            kw: self.expect(TokenKind::KwRet)?,
-            expr: self.parse_expr()?,
+        let (label, colon) = match self.current_kind() {
+            TokenKind::Label(_) => (
+                Some(self.parse_label()?),
+                Some(self.expect(TokenKind::Colon)?),
+            ),
+            _ => (None, None),
+        };
+
+        Ok(Expr::Block(ExprBlock {
+            label,
+            colon,
+            block: self.parse_block()?,
+            span: self.end(),
+        }))
+    }
+
+    /// Parse a conditional expression (`expr-if ::= "if" expr block [ "else" ( expr-if | block ) ]`).
+    fn parse_expr_if(&mut self) -> ParseResult<Expr> {
+        self.start();
+
+        let kw = self.expect(TokenKind::KwIf)?;
+
+        let cond = Box::new(self.structs(false, Self::parse_expr)?);
+        let then = self.parse_block()?;
+
+        let (else_kw, els) = match self.current_kind() {
+            TokenKind::KwElse => {
+                let else_kw = self.expect(TokenKind::KwElse)?;
+                let els = match self.current_kind() {
+                    TokenKind::KwIf => self.nested(Self::parse_expr_if)?,
+                    _ => self.parse_else_block()?,
+                };
+
+                (Some(else_kw), Some(Box::new(els)))
+            }
+            _ => (None, None),
+        };
+
+        Ok(Expr::If(ExprIf {
+            kw,
+            cond,
+            then,
+            else_kw,
+            els,
+            span: self.end(),
+        }))
+    }
+
+    /// Parse the block of an `else` branch, which can't be labeled.
+    fn parse_else_block(&mut self) -> ParseResult<Expr> {
+        self.start();
+
+        Ok(Expr::Block(ExprBlock {
+            label: None,
+            colon: None,
+            block: self.parse_block()?,
+            span: self.end(),
+        }))
+    }
+
+    /// Parse a `match` expression (`expr-match ::= "match" expr "{" { arm "," } [ arm ] "}"`).
+    fn parse_expr_match(&mut self) -> ParseResult<Expr> {
+        self.start();
+
+        let kw = self.expect(TokenKind::KwMatch)?;
+        let scrutinee = Box::new(self.structs(false, Self::parse_expr)?);
+        let lb = self.expect(TokenKind::LBrace)?;
+
+        let mut arms = Vec::new();
+
+        while self.current_kind() != &TokenKind::RBrace {
+            let arm = self.structs(true, Self::parse_arm)?;
+
+            // Like statements, arms whose body is block-like don't need a comma
+            if self.current_kind() != &TokenKind::RBrace
+                && !matches!(arm.body, Expr::If(_) | Expr::Block(_) | Expr::Match(_))
+            {
+                self.expect(TokenKind::Comma)?;
+            } else if self.current_kind() == &TokenKind::Comma {
+                self.advance(1);
+            }
+
+            arms.push(arm);
+        }
+
+        Ok(Expr::Match(ExprMatch {
+            kw,
+            scrutinee,
+            lb,
+            arms,
+            rb: self.expect(TokenKind::RBrace)?,
+            span: self.end(),
+        }))
+    }
+
+    /// Parse a single arm of a `match` expression (`arm ::= pat "=>" expr`).
+    fn parse_arm(&mut self) -> ParseResult<Arm> {
+        self.start();
+
+        Ok(Arm {
+            pat: self.parse_pat()?,
+            arrow: self.expect(TokenKind::FatArrow)?,
+            body: self.parse_expr()?,
             span: self.end(),
         })
     }
 
-    /// Parse a local `let` binding.
+    /// Parse a pattern, which is either an integer literal or the wildcard `_`.
+    fn parse_pat(&mut self) -> ParseResult<Pat> {
+        let current = self.current().clone();
+
+        match current.kind {
+            TokenKind::LitNum(value, raw) => {
+                self.advance(1);
+
+                Ok(Pat::Lit(in_range(LitNum {
+                    value,
+                    raw,
+                    span: span_of(&current),
+                })?))
+            }
+
+            TokenKind::Ident(name) if name == "_" => {
+                self.advance(1);
+                Ok(Pat::Wild(span_of(&current)))
+            }
+
+            _ => Err(ParseError {
+                reason: format!(
+                    "Expected a literal number or '_' pattern but found {}",
+                    current.kind
+                ),
+                span: current.span,
+            }),
+        }
+    }
+
+    /// Parse a local `let` binding (`local ::= "let" ident [ ":" ty ] "=" expr`).
     fn parse_local(&mut self) -> ParseResult<Local> {
         self.start();
 
+        let kw = self.expect(TokenKind::KwLet)?;
+        let ident = self.parse_ident()?;
+
+        let (colon, ty) = match self.current_kind() {
+            TokenKind::Colon => (Some(self.expect(TokenKind::Colon)?), Some(self.parse_ty()?)),
+            _ => (None, None),
+        };
+
         Ok(Local {
-            kw: self.expect(TokenKind::KwLet)?,
-            ident: self.parse_ident()?,
-            colon: self.expect(TokenKind::Colon)?,
-            ty: self.parse_ty()?,
+            kw,
+            ident,
+            colon,
+            ty,
             eq: self.expect(TokenKind::Equal)?,
             expr: self.parse_expr()?,
             span: self.end(),
         })
     }
 
-    /// Parse an expression (`expr ::= term { "+" term }`).
+    /// Parse an expression (`expr ::= unary { infix-op unary }`). How tightly each infix operator binds comes from
+    /// [`infix_op`], so comparisons bind looser than arithmetic and `a + 1 == b` compares `a + 1` with `b`.
     fn parse_expr(&mut self) -> ParseResult<Expr> {
-        let mut expr = self.parse_term()?;
+        self.parse_expr_bp(0)
+    }
 
-        while self.current_kind() == &TokenKind::Plus {
-            let op = self.expect(TokenKind::Plus)?;
-            let op = BinaryOp {
-                kind: OpKind::Add,
-                span: op.span.clone().unwrap(),
-            };
+    /// Parse an expression whose infix operators all bind at least as tightly as `min_bp`. Every operator associates
+    /// to the left, so `a - b - c` subtracts `c` from `a - b`.
+    fn parse_expr_bp(&mut self, min_bp: u8) -> ParseResult<Expr> {
+        let mut expr = self.nested(Self::parse_unary)?;
 
-            let rhs = self.parse_term()?;
-            let start = expr.span().clone().start;
-            let end = rhs.span().clone().end;
+        while let Some((infix, bp)) = infix_op(self.current_kind()) {
+            if bp < min_bp {
+                break;
+            }
 
-            expr = Expr::Binary(ExprBin {
-                lhs: Box::new(expr),
-                op,
-                rhs: Box::new(rhs),
-                span: Span::new(start, end),
-            })
+            let op_span = span_of(self.current());
+            self.advance(1);
+
+            // Only operators that bind tighter than this one end up on its right hand side
+            let rhs = self.parse_expr_bp(bp + 1)?;
+            let span = Span::new(expr.span().start.clone(), rhs.span().end.clone());
+            let (lhs, rhs) = (Box::new(expr), Box::new(rhs));
+
+            expr = match infix {
+                Infix::Bin(kind) => Expr::Binary(ExprBin {
+                    lhs,
+                    op: BinaryOp {
+                        kind,
+                        span: op_span,
+                    },
+                    rhs,
+                    span,
+                }),
+                Infix::Cmp(kind) => Expr::Cmp(ExprCmp {
+                    lhs,
+                    op: CmpOp {
+                        kind,
+                        span: op_span,
+                    },
+                    rhs,
+                    span,
+                }),
+            };
         }
 
         Ok(expr)
     }
 
-    /// Parse a term (`term ::= factor { "*" factor }`).
-    fn parse_term(&mut self) -> ParseResult<Expr> {
-        let mut expr = self.parse_factor()?;
+    /// Parse a unary expression (`unary ::= ( "&" | "-" | "!" ) unary | factor`). Postfix operators bind tighter than
+    /// prefix ones, so `&a.b` is a reference to the field `b` of `a`. Prefix operators nest, so `- -5` negates twice.
+    fn parse_unary(&mut self) -> ParseResult<Expr> {
+        match self.current_kind() {
+            TokenKind::Ampersand => self.parse_ref(),
+            TokenKind::Minus => self.parse_negate(),
+            TokenKind::Bang => self.parse_not(),
+            _ => self.parse_factor(),
+        }
+    }
 
-        while self.current_kind() == &TokenKind::Star {
-            let op = self.expect(TokenKind::Star)?;
-            let op = BinaryOp {
-                kind: OpKind::Multiply,
-                span: op.span.clone().unwrap(),
-            };
+    /// Parse a reference (`"&" unary`).
+    fn parse_ref(&mut self) -> ParseResult<Expr> {
+        let amp = self.expect(TokenKind::Ampersand)?;
+        let expr = self.nested(Self::parse_unary)?;
+        let span = Span::new(span_of(&amp).start, expr.span().end.clone());
 
-            let rhs = self.parse_factor()?;
-            let start = expr.span().clone().start;
-            let end = rhs.span().clone().end;
+        Ok(Expr::Ref(ExprRef {
+            amp,
+            expr: Box::new(expr),
+            span,
+        }))
+    }
 
-            expr = Expr::Binary(ExprBin {
-                lhs: Box::new(expr),
-                op,
-                rhs: Box::new(rhs),
-                span: Span::new(start, end),
-            })
+    /// Parse a negation (`"-" unary`).
+    fn parse_negate(&mut self) -> ParseResult<Expr> {
+        let op = self.expect(TokenKind::Minus)?;
+        let op = UnaryOp {
+            kind: UnaryOpKind::Negate,
+            span: span_of(&op),
+        };
+
+        // `i32::MIN` can only be written by negating its magnitude, which is out of range on its own
+        if let TokenKind::LitNum(i32::MIN, raw) = self.current_kind() {
+            let raw = Name::intern(&format!("-{}", raw.as_str()));
+            let span = Span::new(op.span.start, span_of(self.current()).end);
+            self.advance(1);
+
+            return Ok(Expr::Lit(ExprLit::Num(LitNum {
+                value: i32::MIN,
+                raw,
+                span,
+            })));
         }
 
-        Ok(expr)
+        self.parse_operand(op)
+    }
+
+    /// Parse a logical negation (`"!" unary`).
+    fn parse_not(&mut self) -> ParseResult<Expr> {
+        let op = self.expect(TokenKind::Bang)?;
+        let op = UnaryOp {
+            kind: UnaryOpKind::Not,
+            span: span_of(&op),
+        };
+
+        self.parse_operand(op)
     }
 
-    /// Parse a factor (`factor ::= lit-num | ident | call-fn | "(" expr ")"`).
+    /// Parse the operand of a prefix operator that has already been consumed.
+    fn parse_operand(&mut self, op: UnaryOp) -> ParseResult<Expr> {
+        let expr = self.nested(Self::parse_unary)?;
+        let span = Span::new(op.span.start.clone(), expr.span().end.clone());
+
+        Ok(Expr::Unary(ExprUnary {
+            op,
+            expr: Box::new(expr),
+            span,
+        }))
+    }
+
+    /// Parse a factor (`factor ::= primary { "." ( lit-num | ident [ "(" arg-list ")" ] ) }`). Field accesses,
+    /// tuple indexing and method calls chain left to right, so `a.b().c` is the field `c` of the result of calling
+    /// `b` on `a`.
     fn parse_factor(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.parse_primary()?;
+
+        while self.current_kind() == &TokenKind::Dot {
+            let dot = self.expect(TokenKind::Dot)?;
+            let start = expr.span().start.clone();
+
+            if let TokenKind::LitNum(value, raw) = self.current_kind() {
+                let index = in_range(LitNum {
+                    value: *value,
+                    raw: *raw,
+                    span: span_of(self.current()),
+                })?;
+
+                self.advance(1);
+                let end = index.span.end.clone();
+
+                expr = Expr::TupleIndex(ExprTupleIndex {
+                    expr: Box::new(expr),
+                    dot,
+                    index,
+                    span: Span::new(start, end),
+                });
+
+                continue;
+            }
+
+            let ident = self.parse_ident()?;
+
+            if self.current_kind() == &TokenKind::LParen {
+                let lp = self.expect(TokenKind::LParen)?;
+                let args = self.parse_arg_list()?;
+                let rp = self.expect(TokenKind::RParen)?;
+                let end = span_of(&rp).end;
+
+                expr = Expr::MethodCall(ExprMethodCall {
+                    receiver: Box::new(expr),
+                    dot,
+                    ident,
+                    lp,
+                    args,
+                    rp,
+                    span: Span::new(start, end),
+                })
+            } else {
+                let end = ident.span.end.clone();
+
+                expr = Expr::Field(ExprField {
+                    expr: Box::new(expr),
+                    dot,
+                    ident,
+                    span: Span::new(start, end),
+                })
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse a primary expression (`primary ::= lit-num | lit-bool | ident | call-fn | expr-struct | expr-block | expr-if | "(" [ expr-list ] ")"`). A
+    /// single expression in parentheses is just that expression, unless it's followed by a comma (`(1,)`).
+    fn parse_primary(&mut self) -> ParseResult<Expr> {
         self.start();
         let current = self.current().clone();
 
         match current.kind {
-            TokenKind::LitNum(value) => {
+            TokenKind::LitNum(value, raw) => {
                 self.advance(1);
 
-                Ok(Expr::Lit(ExprLit::Num(LitNum {
+                Ok(Expr::Lit(ExprLit::Num(in_range(LitNum {
                     value,
+                    raw,
+                    span: self.end(),
+                })?)))
+            }
+
+            TokenKind::KwTrue | TokenKind::KwFalse => {
+                self.advance(1);
+
+                Ok(Expr::Lit(ExprLit::Bool(LitBool {
+                    value: current.kind == TokenKind::KwTrue,
                     span: self.end(),
                 })))
             }
 
-            TokenKind::Ident(repr) => {
+            TokenKind::LParen => {
+                let lp = self.expect(TokenKind::LParen)?;
+                let mut elems = Vec::new();
+                let mut trailing = false;
+
+                while self.current_kind() != &TokenKind::RParen {
+                    elems.push(self.structs(true, Self::parse_expr)?);
+                    trailing = false;
+
+                    if self.current_kind() != &TokenKind::RParen {
+                        self.expect(TokenKind::Comma)?;
+                        trailing = true;
+                    }
+                }
+
+                let rp = self.expect(TokenKind::RParen)?;
+
+                if elems.len() == 1 && !trailing {
+                    self.end();
+                    return Ok(elems.pop().unwrap());
+                }
+
+                Ok(Expr::Tuple(ExprTuple {
+                    lp,
+                    elems,
+                    rp,
+                    span: self.end(),
+                }))
+            }
+
+            // Blocks take up much more stack space than other expressions, so they count as an extra level
+            TokenKind::Label(_) | TokenKind::LBrace => {
+                self.end();
+                self.nested(Self::parse_expr_block)
+            }
+
+            TokenKind::KwIf => {
+                self.end();
+                self.nested(Self::parse_expr_if)
+            }
+
+            TokenKind::KwMatch => {
+                self.end();
+                self.nested(Self::parse_expr_match)
+            }
+
+            TokenKind::Ident(_) => {
                 let ident = self.parse_ident()?;
 
                 match self.current_kind() {
@@ -420,7 +1112,7 @@ impl<'a> Parser<'a> {
                         span: self.end(),
                     }))),
 
-                    TokenKind::LBrace => Ok(Expr::Struct(ExprStruct {
+                    TokenKind::LBrace if !self.no_structs => Ok(Expr::Struct(ExprStruct {
                         ident,
                         lb: self.expect(TokenKind::LBrace)?,
                         args: self.parse_named_arg_list()?,
@@ -428,10 +1120,24 @@ impl<'a> Parser<'a> {
                         span: self.end(),
                     })),
 
-                    _ => Ok(Expr::Ident(ident)),
+                    _ => {
+                        self.end();
+                        Ok(Expr::Ident(ident))
+                    }
                 }
             }
 
+            // Inside a method, the receiver is just a local named `self`
+            TokenKind::KwSelf => {
+                self.advance(1);
+                self.end();
+
+                Ok(Expr::Ident(Ident {
+                    repr: Name::intern("self"),
+                    span: span_of(&current),
+                }))
+            }
+
             _ => {
                 self.advance(1);
                 Err(ParseError {
@@ -450,7 +1156,7 @@ impl<'a> Parser<'a> {
         let mut args = Vec::new();
 
         while self.current_kind() != &TokenKind::RParen {
-            args.push(self.parse_expr()?);
+            args.push(self.structs(true, Self::parse_expr)?);
 
             if self.current_kind() != &TokenKind::RParen {
                 self.expect(TokenKind::Comma)?;
@@ -492,66 +1198,193 @@ impl<'a> Parser<'a> {
         })
     }
 
-    /// Parse a type.
+    /// Parse a type (`ty ::= ident | "&" ty | "(" [ ty-list ] ")" | "[" ty [ ";" expr ] "]" | "fn" "(" [ ty-list ] ")"
+    /// "->" ty`).
+    /// A single type in parentheses is just that type, unless it's followed by a comma (`(i32,)`).
     fn parse_ty(&mut self) -> ParseResult<Ty> {
         self.start();
 
-        let current = self.current();
-        match current.kind {
+        match self.current_kind() {
             TokenKind::LParen => {
-                // Special case - the unit type '()'
+                let lp = self.expect(TokenKind::LParen)?;
+                let (elems, trailing) = self.parse_ty_list()?;
+                let rp = self.expect(TokenKind::RParen)?;
+
+                if elems.len() == 1 && !trailing {
+                    self.end();
+                    return Ok(elems.into_iter().next().unwrap());
+                }
+
+                Ok(Ty::Tuple(TyTuple {
+                    lp,
+                    elems,
+                    rp,
+                    span: self.end(),
+                }))
+            }
+
+            TokenKind::LBracket => {
+                let lb = self.expect(TokenKind::LBracket)?;
+                let elem = Box::new(self.nested(Self::parse_ty)?);
+
+                let len = if self.current_kind() == &TokenKind::Semicolon {
+                    self.expect(TokenKind::Semicolon)?;
+                    Some(Rc::new(self.parse_expr()?))
+                } else {
+                    None
+                };
+
+                Ok(Ty::Array(TyArray {
+                    lb,
+                    elem,
+                    len,
+                    rb: self.expect(TokenKind::RBracket)?,
+                    span: self.end(),
+                }))
+            }
+
+            TokenKind::Ampersand => Ok(Ty::Ref(TyRef {
+                amp: self.expect(TokenKind::Ampersand)?,
+                ty: Box::new(self.nested(Self::parse_ty)?),
+                span: self.end(),
+            })),
+
+            TokenKind::KwFn => {
+                let kw = self.expect(TokenKind::KwFn)?;
+
                 self.expect(TokenKind::LParen)?;
+                let (params, _) = self.parse_ty_list()?;
                 self.expect(TokenKind::RParen)?;
+                self.expect(TokenKind::RArrow)?;
 
-                let span: Span = self.end();
-                Ok(Ty {
-                    ident: Ident {
-                        repr: "()".to_owned(),
-                        span: span.clone(),
-                    },
-                    span: span,
-                })
+                Ok(Ty::Fn(TyFn {
+                    kw,
+                    params,
+                    ret: Box::new(self.nested(Self::parse_ty)?),
+                    span: self.end(),
+                }))
             }
 
-            _ => Ok(Ty {
-                ident: self.parse_ident()?,
-                span: self.end(),
-            }),
+            _ => {
+                self.end();
+                Ok(Ty::Named(self.parse_ident()?))
+            }
+        }
+    }
+
+    /// Parse a comma separated list of types up to (but not including) a right parenthesis. Also returns whether
+    /// the list ended with a trailing comma.
+    fn parse_ty_list(&mut self) -> ParseResult<(Vec<Ty>, bool)> {
+        let mut tys = Vec::new();
+        let mut trailing = false;
+
+        while self.current_kind() != &TokenKind::RParen {
+            tys.push(self.nested(Self::parse_ty)?);
+            trailing = false;
+
+            if self.current_kind() != &TokenKind::RParen {
+                self.expect(TokenKind::Comma)?;
+                trailing = true;
+            }
+        }
+
+        Ok((tys, trailing))
+    }
+
+    /// Record an error that the parser can recover from. Returns `false` once the maximum number of errors has been
+    /// reached, in which case a final error saying so is recorded as well, and parsing should stop.
+    fn record(&mut self, err: ParseError) -> bool {
+        self.errors.push(err);
+
+        if self.errors.len() < self.max_errors {
+            return true;
+        }
+
+        self.errors.push(ParseError {
+            reason: format!(
+                "Too many errors, so the parser stopped after the first {}",
+                self.max_errors
+            ),
+            span: None,
+        });
+
+        false
+    }
+
+    /// Parse something nested inside the expression or type currently being parsed, as long as the nesting isn't
+    /// too deep.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError {
+                reason: format!(
+                    "Expressions and types can't be nested more than {MAX_DEPTH} levels deep"
+                ),
+                span: self.current().span.clone(),
+            });
         }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Parse something with struct expressions either allowed or ruled out, restoring the previous setting
+    /// afterwards.
+    fn structs<T>(
+        &mut self,
+        allowed: bool,
+        parse: fn(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let no_structs = std::mem::replace(&mut self.no_structs, !allowed);
+        let result = parse(self);
+        self.no_structs = no_structs;
+
+        result
     }
 
     /// Start a span at the current location.
     fn start(&mut self) {
-        let span = self.input[self.index].clone().span.unwrap();
+        let span = span_of(self.current());
         self.starts.push(span);
     }
 
-    /// End a span at the current location.
+    /// End a span at the current location. If no token has been consumed yet (for example, when the very first
+    /// token is erroneous), there's nothing to span, so a zero-width span at `1:1` is returned instead. The same goes
+    /// for a span started at the end of the file, which covers just the end of the file.
     fn end(&mut self) -> Span {
-        let from = self.starts.last().cloned().unwrap();
-        let to = self.input[self.index - 1].clone().span.unwrap();
+        let from = self.starts.pop();
+        let to = match self.index.checked_sub(1) {
+            Some(index) => self.input.get(index).and_then(|token| token.span.clone()),
+            None => None,
+        };
 
-        self.starts.pop();
-        Span::new(from.start, to.end)
+        match (from, to) {
+            (Some(from), Some(to)) if to.end >= from.start => Span::new(from.start, to.end),
+            (Some(from), Some(_)) => from,
+            _ => Span::single(1, 1),
+        }
     }
 
     /// Get the kind of the current token.
     fn current_kind(&self) -> &TokenKind {
-        &self.input[self.index].kind
+        &self.current().kind
     }
 
-    /// Get the current token.
+    /// Get the current token, which is `EOF` once every token has been consumed.
     fn current(&self) -> &Token {
-        &self.input[self.index]
+        self.input.get(self.index).unwrap_or(&self.eof)
+    }
+
+    /// Get the kind of the token `n` tokens after the current one, which is `EOF` past the last token.
+    fn peek(&self, n: usize) -> &TokenKind {
+        &self.input.get(self.index + n).unwrap_or(&self.eof).kind
     }
 
-    /// Advance the token `n` times.
+    /// Advance the token `n` times, stopping just past the last token.
     fn advance(&mut self, n: usize) {
-        if self.index + n >= self.input.len() {
-            self.index = self.input.len() - 1;
-        } else {
-            self.index += n;
-        }
+        self.index = (self.index + n).min(self.input.len());
     }
 
     /// Return the current token if its kind matches `kind`, or an error otherwise.
@@ -568,3 +1401,377 @@ impl<'a> Parser<'a> {
         })
     }
 }
+
+/// Get the span of a token. Every token produced by the lexer has one, but a zero-width span at `1:1` is returned
+/// for any that doesn't, rather than panicking.
+fn span_of(token: &Token) -> Span {
+    token.span.clone().unwrap_or(Span::single(1, 1))
+}
+
+/// Rejects the magnitude of `i32::MIN` (which the lexer produces as `i32::MIN` itself) anywhere but right after a minus
+/// sign, where `parse_negate` picks it up.
+fn in_range(lit: LitNum) -> ParseResult<LitNum> {
+    if lit.value != i32::MIN {
+        return Ok(lit);
+    }
+
+    Err(ParseError {
+        reason: format!(
+            "Integer literal {} is too large for an 'i32', which ranges from {} to {}",
+            lit.raw.as_str(),
+            i32::MIN,
+            i32::MAX
+        ),
+        span: Some(lit.span),
+    })
+}
+
+/// An infix operator, by the kind of node it produces.
+enum Infix {
+    Bin(OpKind),
+    Cmp(CmpOpKind),
+}
+
+/// The infix operator a token stands for, along with its binding power. Operators with a higher binding power bind
+/// tighter, and adding an operator only takes a new entry here.
+fn infix_op(kind: &TokenKind) -> Option<(Infix, u8)> {
+    let op = match kind {
+        TokenKind::EqualEqual => (Infix::Cmp(CmpOpKind::Equal), 1),
+        TokenKind::BangEqual => (Infix::Cmp(CmpOpKind::NotEqual), 1),
+        TokenKind::Less => (Infix::Cmp(CmpOpKind::Less), 1),
+        TokenKind::LessEqual => (Infix::Cmp(CmpOpKind::LessEqual), 1),
+        TokenKind::Greater => (Infix::Cmp(CmpOpKind::Greater), 1),
+        TokenKind::GreaterEqual => (Infix::Cmp(CmpOpKind::GreaterEqual), 1),
+        TokenKind::Plus => (Infix::Bin(OpKind::Add), 2),
+        TokenKind::Minus => (Infix::Bin(OpKind::Subtract), 2),
+        TokenKind::Star => (Infix::Bin(OpKind::Multiply), 3),
+        TokenKind::Slash => (Infix::Bin(OpKind::Divide), 3),
+        TokenKind::Percent => (Infix::Bin(OpKind::Remainder), 3),
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    /// Parse a program, returning the syntax tree along with every error.
+    fn parse_all(src: &str) -> (File, Vec<ParseError>) {
+        let tokens = match Lexer::from_chars(src.chars()).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("the program should lex, but: {}", err.reason),
+        };
+
+        Parser::new(&tokens).parse_file()
+    }
+
+    /// Parse a program that doesn't contain any syntax errors.
+    fn parse(src: &str) -> File {
+        let (file, errors) = parse_all(src);
+        if let Some(err) = errors.first() {
+            panic!("the program should parse, but: {}", err.reason);
+        }
+
+        file
+    }
+
+    /// Parse a program that should be rejected, returning the reason of the first error.
+    fn reason(src: &str) -> String {
+        match parse_all(src).1.into_iter().next() {
+            Some(err) => err.reason,
+            None => panic!("the program should be rejected"),
+        }
+    }
+
+    /// Parse a program that doesn't contain any syntax errors, keeping its doc comments.
+    fn parse_with_docs(src: &str) -> File {
+        let tokens = match Lexer::from_chars(src.chars()).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("the program should lex, but: {}", err.reason),
+        };
+
+        match Parser::new(&tokens).with_docs().parse_file() {
+            (file, errors) if errors.is_empty() => file,
+            (_, errors) => panic!("the program should parse, but: {}", errors[0].reason),
+        }
+    }
+
+    /// Parse an expression, as the tail of a function body.
+    fn expr(src: &str) -> Expr {
+        let file = parse(&format!("fn main() -> i32 {{ {src} }}"));
+
+        match file.items.into_iter().next() {
+            Some(Item::Fn(item_fn)) => *item_fn.body.tail.expect("the body should have a tail"),
+            _ => panic!("the program should be a single function"),
+        }
+    }
+
+    /// Render an expression with every operator application in parentheses, so that precedence is visible.
+    fn grouped(expr: &Expr) -> String {
+        match expr {
+            Expr::Binary(expr_bin) => {
+                format!(
+                    "({} {} {})",
+                    grouped(&expr_bin.lhs),
+                    expr_bin.op.kind,
+                    grouped(&expr_bin.rhs)
+                )
+            }
+            Expr::Cmp(expr_cmp) => {
+                format!(
+                    "({} {} {})",
+                    grouped(&expr_cmp.lhs),
+                    expr_cmp.op.kind,
+                    grouped(&expr_cmp.rhs)
+                )
+            }
+            Expr::Unary(expr_unary) => {
+                format!("({}{})", expr_unary.op.kind, grouped(&expr_unary.expr))
+            }
+            expr => expr.to_string(),
+        }
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(
+            grouped(&expr("1 + 2 * 3 - 4 == 5")),
+            "(((1 + (2 * 3)) - 4) == 5)"
+        );
+        assert_eq!(grouped(&expr("8 / 4 / 2 * 3")), "(((8 / 4) / 2) * 3)");
+        assert_eq!(
+            grouped(&expr("-1 * 2 != 3 - -4")),
+            "(((-1) * 2) != (3 - (-4)))"
+        );
+    }
+
+    #[test]
+    fn doubled_unary_operators_nest() {
+        assert_eq!(grouped(&expr("- - 5")), "(-(-5))");
+        assert_eq!(grouped(&expr("--5")), "(-(-5))");
+        assert_eq!(grouped(&expr("!!true")), "(!(!true))");
+        assert_eq!(grouped(&expr("!a == b")), "((!a) == b)");
+    }
+
+    #[test]
+    fn subtraction_binds_looser_than_multiplication() {
+        assert_eq!(grouped(&expr("10 - 2 * 3")), "(10 - (2 * 3))");
+        assert_eq!(grouped(&expr("10 / 2 - 3")), "((10 / 2) - 3)");
+        assert_eq!(grouped(&expr("1 + 7 % 2 * 3")), "(1 + ((7 % 2) * 3))");
+    }
+
+    #[test]
+    fn orderings_bind_like_equality() {
+        assert_eq!(grouped(&expr("a < b + 1")), "(a < (b + 1))");
+        assert_eq!(grouped(&expr("a * 2 >= b")), "((a * 2) >= b)");
+
+        for op in ["<", "<=", ">", ">="] {
+            let Expr::Cmp(expr_cmp) = expr(&format!("1 {op} 2")) else {
+                panic!("'{op}' should produce a comparison");
+            };
+
+            assert_eq!(expr_cmp.op.kind.to_string(), op);
+        }
+    }
+
+    #[test]
+    fn ifs_may_have_else_branches_and_chain() {
+        let Expr::If(plain) = expr("if a { 1 }") else {
+            panic!("'if' should produce an if expression");
+        };
+        assert!(plain.else_kw.is_none() && plain.els.is_none());
+
+        let Expr::If(with_else) = expr("if a { 1 } else { 2 }") else {
+            panic!("'if/else' should produce an if expression");
+        };
+        assert!(matches!(with_else.els.as_deref(), Some(Expr::Block(_))));
+
+        let Expr::If(chain) = expr("if a { 1 } else if b { 2 } else { 3 }") else {
+            panic!("'if/else if/else' should produce an if expression");
+        };
+        let Some(Expr::If(inner)) = chain.els.as_deref() else {
+            panic!("'else if' should nest another if expression");
+        };
+        assert!(matches!(&*inner.cond, Expr::Ident(ident) if ident.repr == "b"));
+        assert!(matches!(inner.els.as_deref(), Some(Expr::Block(_))));
+    }
+
+    #[test]
+    fn field_accesses_nest_left_to_right() {
+        let Expr::Field(outer) = expr("a.b.c") else {
+            panic!("'a.b.c' should be a field access");
+        };
+        let Expr::Field(inner) = &*outer.expr else {
+            panic!("'a.b' should be a field access");
+        };
+
+        assert_eq!(outer.ident.repr, "c");
+        assert_eq!(inner.ident.repr, "b");
+        assert!(matches!(&*inner.expr, Expr::Ident(ident) if ident.repr == "a"));
+    }
+
+    #[test]
+    fn a_field_followed_by_arguments_is_a_method_call() {
+        let Expr::MethodCall(call) = expr("p.dist(q)") else {
+            panic!("'p.dist(q)' should be a method call");
+        };
+
+        assert_eq!(call.ident.repr, "dist");
+        assert!(matches!(&*call.receiver, Expr::Ident(ident) if ident.repr == "p"));
+        assert!(matches!(&call.args.args[..], [Expr::Ident(ident)] if ident.repr == "q"));
+
+        // The receiver can be any expression, such as a field access
+        let Expr::MethodCall(call) = expr("a.b.len()") else {
+            panic!("'a.b.len()' should be a method call");
+        };
+
+        assert!(matches!(&*call.receiver, Expr::Field(field) if field.ident.repr == "b"));
+        assert!(call.args.args.is_empty());
+    }
+
+    /// The columns an expression spans. Expressions parsed by `expr` start at column 20.
+    fn columns(expr: &Expr) -> (usize, usize) {
+        let span = expr.span();
+        (span.start.column, span.end.column)
+    }
+
+    #[test]
+    fn parentheses_make_unit_groups_and_tuples() {
+        // `()` is the unit value, which is the empty tuple
+        let unit = expr("()");
+        assert!(matches!(&unit, Expr::Tuple(tuple) if tuple.elems.is_empty()));
+        assert_eq!(columns(&unit), (20, 21));
+
+        // `(1)` is just `1`
+        let group = expr("(1)");
+        assert!(matches!(&group, Expr::Lit(ExprLit::Num(lit)) if lit.value == 1));
+        assert_eq!(columns(&group), (21, 21));
+
+        // A trailing comma makes a tuple with one element
+        let single = expr("(1,)");
+        assert!(matches!(&single, Expr::Tuple(tuple) if tuple.elems.len() == 1));
+        assert_eq!(columns(&single), (20, 23));
+
+        let pair = expr("(1, 2)");
+        assert!(matches!(&pair, Expr::Tuple(tuple) if tuple.elems.len() == 2));
+        assert_eq!(columns(&pair), (20, 25));
+    }
+
+    #[test]
+    fn every_broken_item_is_reported() {
+        let (file, errors) = parse_all(
+            "
+            fn first() -> i32 { 1 + }
+            fn second( -> i32 { 2 }
+            fn main() -> i32 { 0 }
+            ",
+        );
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(file.items.len(), 1);
+        assert!(matches!(&file.items[0], Item::Fn(item_fn) if item_fn.ident.repr == "main"));
+    }
+
+    #[test]
+    fn doc_comments_are_attached_in_order() {
+        let file = parse_with_docs("/// Adds one.\n/// Never overflows.\nfn main() -> i32 { 0 }");
+
+        match &file.items[0] {
+            Item::Fn(item_fn) => assert_eq!(item_fn.docs, ["Adds one.", "Never overflows."]),
+            _ => panic!("the item should be a function"),
+        }
+    }
+
+    #[test]
+    fn doc_comments_are_skipped_unless_docs_are_kept() {
+        let file = parse("/// Adds one.\nfn main() -> i32 { 0 }");
+
+        match &file.items[0] {
+            Item::Fn(item_fn) => assert!(item_fn.docs.is_empty()),
+            _ => panic!("the item should be a function"),
+        }
+    }
+
+    #[test]
+    fn doc_comments_are_accepted_inside_items_and_at_the_end() {
+        let file = parse_with_docs(
+            "
+            struct Point {
+                /// The horizontal position.
+                x: i32,
+            }
+
+            impl Point {
+                /// Moves nowhere.
+                fn stay(self) -> () {}
+            }
+
+            fn main() -> i32 {
+                /// Not kept.
+                let a = 1;
+                /// Not kept either.
+                a
+            }
+
+            /// Dangling.
+            ",
+        );
+
+        assert_eq!(file.items.len(), 3);
+
+        match &file.items[0] {
+            Item::Struct(ItemStruct {
+                fields: Fields::Named(named),
+                ..
+            }) => assert_eq!(named.fields[0].docs, ["The horizontal position."]),
+            _ => panic!("the item should be a struct with named fields"),
+        }
+
+        match &file.items[1] {
+            Item::Impl(item_impl) => match &item_impl.items[0] {
+                ImplItem::Fn(method) => assert_eq!(method.docs, ["Moves nowhere."]),
+            },
+            _ => panic!("the item should be an impl block"),
+        }
+    }
+
+    #[test]
+    fn the_smallest_i32_can_be_written_as_a_negative_literal() {
+        let min = expr("-2147483648");
+        assert!(matches!(&min, Expr::Lit(ExprLit::Num(lit)) if lit.value == i32::MIN));
+        assert_eq!(columns(&min), (20, 30));
+
+        let (_, errors) = parse_all("fn main() -> i32 { 2147483648 }");
+        assert_eq!(
+            errors[0].reason,
+            "Integer literal 2147483648 is too large for an 'i32', which ranges from -2147483648 to 2147483647"
+        );
+    }
+
+    #[test]
+    fn a_function_without_a_body_is_rejected() {
+        assert_eq!(
+            reason("fn foo() -> i32;\nfn main() -> i32 { 0 }"),
+            "Function 'foo' has no body"
+        );
+    }
+
+    #[test]
+    fn keywords_are_not_identifiers() {
+        assert_eq!(
+            reason("fn main() -> i32 { let match = 1; 0 }"),
+            "Expected an identifier, found 'match'"
+        );
+    }
+
+    #[test]
+    fn a_stray_token_between_items_is_reported() {
+        assert_eq!(
+            reason("+\nfn main() -> i32 { 0 }"),
+            "Expected 'fn', 'struct', 'impl' or 'const', found '+'"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::ast::Item;
+use crate::interp::{Interpreter, Value};
+use crate::lexer::{Lexer, TokenKind};
+use crate::parser::Parser;
+use crate::resolution::{Resolver, Type};
+use crate::sema::basic::Basic;
+use crate::sema::escape::Escape;
+use crate::sema::recursion::Recursion;
+use crate::sema::typeck::TypeCk;
+use crate::sema::SemaEngine;
+use crate::shared::{Name, SourceMap};
+use crate::utilities::{diagnostic_to, error_to, warning_to};
+
+/// The path diagnostics give for the input being evaluated. Everything else the REPL compiles along with it is
+/// labelled `<session>`.
+const INPUT: &str = "<repl>";
+
+/// The kinds of input accepted by the REPL.
+enum Entry {
+    /// A function (possibly with attributes), struct, `impl` block or constant, which is kept for every later line.
+    Item,
+
+    /// A `let` binding or an assignment of the given local, whose new value is kept for every later line.
+    Local(Name),
+
+    /// An expression, which is evaluated once and printed.
+    Expr,
+}
+
+/// An interactive session. Items are kept as source code, and every line is compiled together with all of them as
+/// the body of `main`. Locals are kept as values instead, which `main` starts out with, so every line is evaluated
+/// exactly once. A line is only kept if the program still compiles and runs with it, so errors never leave the
+/// session in a broken state.
+pub struct Repl {
+    /// The items entered so far.
+    items: Vec<String>,
+
+    /// The name, type and current value of every local bound so far.
+    locals: Vec<(Name, Type, Value)>,
+}
+
+impl Repl {
+    /// Create a session with no items or locals.
+    pub fn new() -> Self {
+        Repl {
+            items: Vec::new(),
+            locals: Vec::new(),
+        }
+    }
+
+    /// Read lines from standard input until it ends. Input with unbalanced braces continues on the next line, so
+    /// items can span several lines.
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let interactive = stdin.is_terminal();
+        let mut input = String::new();
+
+        loop {
+            if interactive {
+                print!("{}", if input.is_empty() { "> " } else { "... " });
+                io::stdout().flush()?;
+            }
+
+            if stdin.lock().read_line(&mut input)? == 0 {
+                return Ok(());
+            }
+
+            let depth = input.matches('{').count() as isize - input.matches('}').count() as isize;
+            if depth > 0 {
+                continue;
+            }
+
+            self.eval_line(input.trim(), &mut io::stdout(), &mut io::stderr())?;
+            input.clear();
+        }
+    }
+
+    /// Evaluate one complete piece of input. Items and locals are remembered, the values of expressions are written
+    /// to `out`, and diagnostics are written to `err`.
+    pub fn eval_line(
+        &mut self,
+        input: &str,
+        out: &mut dyn Write,
+        err: &mut dyn Write,
+    ) -> io::Result<()> {
+        match Self::classify(input) {
+            None => {}
+
+            Some(Entry::Item) => {
+                let result = self.eval(input, true, None, err);
+
+                if result.is_some() {
+                    self.items.push(input.to_owned());
+                }
+            }
+
+            Some(Entry::Local(name)) => {
+                let stmt = match input.ends_with(';') {
+                    true => input.to_owned(),
+                    false => format!("{input};"),
+                };
+
+                // The local is the value of `main`, which is how its new value is found
+                if let Some((value, ty)) = self.eval(&stmt, false, Some(name.as_str()), err) {
+                    self.locals.retain(|(local, _, _)| *local != name);
+                    self.locals.push((name, ty, value));
+                }
+            }
+
+            Some(Entry::Expr) => {
+                let expr = input.trim_end_matches(';');
+
+                match self.eval(expr, false, None, err) {
+                    Some((_, ty)) if ty == Type::Primitive(String::from("()")) => {}
+                    Some((value, _)) => writeln!(out, "{value}")?,
+                    None => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decide what kind of input this is from its first tokens. Input that can't be lexed is treated as an
+    /// expression, so that the error is reported when it's compiled.
+    fn classify(input: &str) -> Option<Entry> {
+        let mut lexer = Lexer::from_chars(input.chars());
+
+        match lexer.next() {
+            Ok(token) => match token.kind {
+                TokenKind::EOF => None,
+                TokenKind::KwFn
+                | TokenKind::KwStruct
+                | TokenKind::KwImpl
+                | TokenKind::KwConst
+                | TokenKind::At
+                | TokenKind::KwPub
+                | TokenKind::DocComment(_) => Some(Entry::Item),
+                TokenKind::KwLet => match lexer.next().map(|token| token.kind) {
+                    Ok(TokenKind::Ident(name)) => Some(Entry::Local(name)),
+
+                    // Without a name there's nothing to keep, so the statement is only evaluated
+                    _ => Some(Entry::Expr),
+                },
+                // Assignments change a local, so they're kept just like the `let` that declared it
+                TokenKind::Ident(name)
+                    if lexer
+                        .next()
+                        .is_ok_and(|token| token.kind == TokenKind::Equal) =>
+                {
+                    Some(Entry::Local(name))
+                }
+                _ => Some(Entry::Expr),
+            },
+            Err(_) => Some(Entry::Expr),
+        }
+    }
+
+    /// Compile the items together with the input, then run `main` with the locals bound so far. The input is either
+    /// another item (if `item` is set) or the body of `main`, followed by the optional `tail` expression. Returns the
+    /// value and type of `main`, or `None` (after reporting the problem to `err`) if anything failed.
+    fn eval(
+        &self,
+        input: &str,
+        item: bool,
+        tail: Option<&str>,
+        err: &mut dyn Write,
+    ) -> Option<(Value, Type)> {
+        let mut pieces: Vec<(&str, &str)> = Vec::new();
+
+        for item in &self.items {
+            pieces.push(("<session>", item));
+        }
+        if item {
+            pieces.push((INPUT, input));
+        }
+
+        pieces.push(("<session>", "fn main() -> () {"));
+        if !item {
+            pieces.push((INPUT, input));
+        }
+        if let Some(tail) = tail {
+            pieces.push(("<session>", tail));
+        }
+        pieces.push(("<session>", "}"));
+
+        // Every piece is a file of its own, so diagnostics point at the input as it was entered
+        let mut source = SourceMap::new();
+        for (path, text) in &pieces {
+            source.add(path, text);
+        }
+
+        let text = pieces
+            .iter()
+            .map(|(_, text)| *text)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let tokens = match Lexer::from_chars(text.chars()).lex() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                error_to(err, e.reason, &source, e.span);
+                return None;
+            }
+        };
+
+        let (mut file, errors) = Parser::new(&tokens).parse_file();
+        if !errors.is_empty() {
+            for e in errors {
+                error_to(err, e.reason, &source, e.span);
+            }
+
+            return None;
+        }
+
+        // Items that are never called are expected while a program is being built up, so only the warnings about
+        // the input itself are reported, and unused items aren't looked for at all
+        let mut sema = SemaEngine::new(&file)
+            .register(Box::new(Basic::new()))
+            .register(Box::new(Escape::new()))
+            .register(Box::new(Recursion::new()));
+
+        let result = sema.run();
+
+        for warn in sema.warnings() {
+            let line = warn.span.as_ref().map(|span| span.start.line);
+
+            if line
+                .and_then(|line| source.locate(line))
+                .is_some_and(|(path, _)| path == INPUT)
+            {
+                warning_to(err, &warn.reason, &source, warn.span.clone());
+            }
+        }
+
+        if let Err(errs) = result {
+            for e in errs {
+                error_to(err, e.reason, &source, e.span);
+            }
+            return None;
+        }
+
+        // `main` is checked and run on its own, since its type depends on the tail expression
+        let Some(Item::Fn(main)) = file.items.pop() else {
+            return None;
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        let locals: Vec<_> = self
+            .locals
+            .iter()
+            .map(|(name, ty, _)| (*name, ty.clone()))
+            .collect();
+
+        let ty = match TypeCk::new(&resolver).infer(&file, &main.body, &locals) {
+            Ok(ty) => ty,
+            Err(e) => {
+                let _ = diagnostic_to(err, &e, &source);
+                return None;
+            }
+        };
+
+        let locals = self
+            .locals
+            .iter()
+            .map(|(name, _, value)| (name.as_str(), value.clone()))
+            .collect();
+
+        match Interpreter::new(&file).eval_with(&main.body, locals) {
+            Ok(value) => Some((value, ty)),
+            Err(e) => {
+                error_to(err, e.reason, &source, e.span);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed every line to the session, returning what it wrote to its output and to its diagnostics.
+    fn feed(repl: &mut Repl, lines: &[&str]) -> (String, String) {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        for line in lines {
+            repl.eval_line(line, &mut out, &mut err).unwrap();
+        }
+
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn functions_can_be_called_on_later_lines() {
+        let (out, err) = feed(
+            &mut Repl::new(),
+            &["fn double(x: i32) -> i32 { x * 2 }", "double(21)"],
+        );
+
+        assert_eq!(out, "42\n");
+        assert_eq!(err, "");
+    }
+
+    #[test]
+    fn locals_are_kept_as_values() {
+        let mut repl = Repl::new();
+        let (out, _) = feed(
+            &mut repl,
+            &["let a = 5;", "a = a + 1;", "let b = (a, 1)", "b.0"],
+        );
+
+        assert_eq!(out, "6\n");
+
+        // Assigning replaces the value, rather than keeping the statement around to run again
+        assert_eq!(repl.locals.len(), 2);
+        assert_eq!(repl.locals[0].2, Value::Int(6));
+    }
+
+    #[test]
+    fn lines_that_fail_are_not_kept() {
+        let mut repl = Repl::new();
+        let (out, _) = feed(
+            &mut repl,
+            &["let a = 1;", "a = nope;", "let c = 1 / 0;", "a"],
+        );
+
+        assert_eq!(out, "1\n");
+        assert_eq!(repl.locals.len(), 1);
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_input() {
+        let (_, err) = feed(
+            &mut Repl::new(),
+            &["fn one() -> i32 { 1 }", "let a = 1;", "a + nope"],
+        );
+
+        assert!(err.contains("<repl>:1:a + nope"));
+    }
+
+    #[test]
+    fn functions_that_always_call_themselves_are_reported() {
+        let (_, err) = feed(&mut Repl::new(), &["fn f(x: i32) -> i32 { f(x) }"]);
+
+        assert!(err.contains("Function 'f' always calls itself"));
+    }
+}
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 
-use crate::ast::{visitor::Visit, File, Ident, ItemFn};
-use crate::ast::{Fields, ItemStruct};
-use crate::ir::table::SymbolTable;
+use crate::ast::{fold::Fold, visitor::Visit, Expr, File, Ident, ItemFn};
+use crate::ast::{Fields, ImplItem, ItemConst, ItemImpl, ItemStruct, Param, Ty};
+use crate::ir::fold::{eval_consts, ConstFolder};
+use crate::shared::{Name, Span, SymbolTable, PRELUDE};
+use crate::utilities::edit_distance;
 
 #[derive(PartialEq)]
 pub enum CollectMode {
@@ -15,6 +17,9 @@ pub enum CollectMode {
 /// Represents a resolved function.
 #[derive(Debug, Clone)]
 pub struct Function {
+    /// The name and resolved type of every parameter of this function, in order.
+    pub params: Vec<(Name, Type)>,
+
     /// The resolved type returned by this function.
     pub return_type: Type,
 }
@@ -24,27 +29,58 @@ pub struct Function {
 pub enum Type {
     Primitive(String),
     Struct(TyStruct),
+
+    /// A tuple with at least one element. The empty tuple is the primitive unit type `()`.
+    Tuple(Vec<Type>),
+
+    /// An array of elements of the given type, along with its length (if one was given).
+    Array(Box<Type>, Option<usize>),
+    Fn(Vec<Type>, Box<Type>),
+    Ref(Box<Type>),
 }
 
 #[derive(Debug, Clone)]
 pub struct TyStruct {
     path: String,
-    pub fields: HashMap<String, String>,
+    pub fields: HashMap<String, TyField>,
+
+    /// Whether the fields are positional (`struct Pair(i32, i32);`), in which case they're named by their index.
+    tuple: bool,
 }
 
-impl PartialEq for Type {
-    fn eq(&self, other: &Self) -> bool {
-        let left = match self {
-            Self::Primitive(repr) => repr,
-            Self::Struct(strct) => &strct.path,
-        };
+impl TyStruct {
+    /// The name of the struct.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
 
-        let right = match other {
-            Self::Primitive(repr) => repr,
-            Self::Struct(strct) => &strct.path,
-        };
+    /// Whether this is a tuple struct, which is built by calling it like a function (`Pair(1, 2)`).
+    pub fn is_tuple(&self) -> bool {
+        self.tuple
+    }
+}
+
+/// Represents a field of a resolved struct.
+#[derive(Debug, Clone)]
+pub struct TyField {
+    /// The field's type, as written in the source.
+    pub ty: Ty,
+
+    /// The span of the field's identifier, or of its type for a positional field.
+    pub ident_span: Span,
+}
 
-        left == right
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Primitive(left), Self::Primitive(right)) => left == right,
+            (Self::Struct(left), Self::Struct(right)) => left.path == right.path,
+            (Self::Tuple(left), Self::Tuple(right)) => left == right,
+            (Self::Array(le, ll), Self::Array(re, rl)) => le == re && ll == rl,
+            (Self::Fn(lp, lr), Self::Fn(rp, rr)) => lp == rp && lr == rr,
+            (Self::Ref(left), Self::Ref(right)) => left == right,
+            _ => false,
+        }
     }
 }
 
@@ -53,18 +89,40 @@ impl Display for Type {
         match self {
             Self::Primitive(repr) => write!(f, "{}", repr),
             Self::Struct(strct) => write!(f, "{}", strct.path),
+            Self::Tuple(elems) if elems.len() == 1 => write!(f, "({},)", elems[0]),
+            Self::Tuple(elems) => write!(f, "({})", join(elems)),
+            Self::Array(elem, Some(len)) => write!(f, "[{}; {}]", elem, len),
+            Self::Array(elem, None) => write!(f, "[{}]", elem),
+            Self::Fn(params, ret) => write!(f, "fn({}) -> {}", join(params), ret),
+            Self::Ref(ty) => write!(f, "&{}", ty),
         }
     }
 }
 
+/// Join a list of types with commas.
+fn join(tys: &[Type]) -> String {
+    tys.iter()
+        .map(|ty| ty.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Raw strings should resolve to one of the following kinds of symbols.
 #[derive(Debug, Clone)]
 pub enum Symbol {
     Function(Function),
+    Const(Const),
     Local(Local),
     Type(Type),
 }
 
+/// Represents a resolved constant.
+#[derive(Debug, Clone)]
+pub struct Const {
+    /// The resolved type of this constant.
+    pub ty: Type,
+}
+
 /// Represents a resolved local.
 #[derive(Debug, Clone)]
 pub struct Local {
@@ -80,6 +138,12 @@ pub struct Resolver<'a> {
     /// The global symbol table.
     pub table: SymbolTable<'a, Symbol>,
 
+    /// The methods of every implemented type, keyed by the type's name and then the method's name.
+    methods: HashMap<&'a str, HashMap<&'a str, Function>>,
+
+    /// The values of every constant that could be computed at compile time.
+    pub consts: HashMap<Name, i32>,
+
     /// Which construct is being collected.
     mode: CollectMode,
 }
@@ -92,39 +156,193 @@ impl<'a> Resolver<'a> {
         let mut table = SymbolTable::new();
         table.insert("()", Symbol::Type(Type::Primitive("()".to_owned())));
         table.insert("i32", Symbol::Type(Type::Primitive("i32".to_owned())));
+        table.insert("bool", Symbol::Type(Type::Primitive("bool".to_owned())));
+
+        // Followed by the functions in the prelude
+        for builtin in PRELUDE {
+            let function = Function {
+                params: builtin
+                    .params
+                    .iter()
+                    .map(|(name, ty)| (Name::intern(name), Type::Primitive((*ty).to_owned())))
+                    .collect(),
+                return_type: Type::Primitive(builtin.ret.to_owned()),
+            };
+
+            table.insert(builtin.name, Symbol::Function(function));
+        }
 
         Resolver {
             file: ast,
             table,
+            methods: HashMap::new(),
+            consts: eval_consts(ast),
             mode: CollectMode::Unset,
         }
     }
 
     pub fn collect_tys(&mut self) {
         self.mode = CollectMode::Types;
-        self.visit_file(&self.file);
+        self.visit_file(self.file);
     }
 
     /// Collect all the functions in the program. This is run during the first name resolution pass.
     pub fn collect_functions(&mut self) {
         self.mode = CollectMode::Functions;
-        self.visit_file(&self.file)
+        self.visit_file(self.file)
     }
 
     /// Resolve an identifier to the type it represents.
     pub fn resolve_ty(&self, ident: &str) -> Option<Type> {
-        self.table.find(&ident).and_then(|symbol| match symbol {
+        self.table.find(ident).and_then(|symbol| match symbol {
             Symbol::Type(ty) => Some(ty),
             _ => None,
         })
     }
 
-    /// Resolve an identifier to the local it represents.
-    pub fn resolve_local(&self, ident: &Ident) -> Option<Type> {
+    /// Resolve a type as written in the source, which is only possible if every named type inside it exists.
+    pub fn resolve(&self, ty: &Ty) -> Option<Type> {
+        match ty {
+            Ty::Named(ident) => self.resolve_ty(&ident.repr),
+
+            Ty::Tuple(ty_tuple) if ty_tuple.elems.is_empty() => self.resolve_ty("()"),
+            Ty::Tuple(ty_tuple) => Some(Type::Tuple(self.resolve_all(&ty_tuple.elems)?)),
+
+            Ty::Array(ty_array) => {
+                let len = match &ty_array.len {
+                    Some(len) => Some(self.eval_len(len)?),
+                    None => None,
+                };
+
+                Some(Type::Array(Box::new(self.resolve(&ty_array.elem)?), len))
+            }
+
+            Ty::Fn(ty_fn) => Some(Type::Fn(
+                self.resolve_all(&ty_fn.params)?,
+                Box::new(self.resolve(&ty_fn.ret)?),
+            )),
+
+            Ty::Ref(ty_ref) => Some(Type::Ref(Box::new(self.resolve(&ty_ref.ty)?))),
+        }
+    }
+
+    /// Compute the length of an array type, which must be a constant expression that isn't negative.
+    pub fn eval_len(&self, len: &Expr) -> Option<usize> {
+        match ConstFolder::new(&self.consts).fold_expr(len) {
+            Ok(Some(value)) => usize::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Resolve a list of types, as long as all of them can be resolved.
+    fn resolve_all(&self, tys: &[Ty]) -> Option<Vec<Type>> {
+        tys.iter().map(|ty| self.resolve(ty)).collect()
+    }
+
+    /// Find the known type whose name is closest to `ident`, as long as it's close enough to be a likely typo.
+    pub fn suggest_ty(&self, ident: &str) -> Option<String> {
+        // Allow roughly one edit for every three characters
+        let threshold = (ident.chars().count() / 3).max(1);
+
+        self.table
+            .iter_all()
+            .filter(|(_, symbol)| matches!(symbol, Symbol::Type(_)))
+            .map(|(name, _)| (edit_distance(ident, name), name))
+            .filter(|(distance, _)| *distance <= threshold)
+            .min()
+            .map(|(_, name)| name.to_owned())
+    }
+
+    /// Render every symbol in the global table, types first, then constants and functions, each sorted by name. Used by
+    /// `--dump-symbols` to debug the resolver.
+    pub fn dump(&self) -> String {
+        let mut symbols = self.table.iter_all().collect::<Vec<_>>();
+        symbols.sort_by_key(|(name, symbol)| {
+            let kind = match symbol {
+                Symbol::Type(_) => 0,
+                Symbol::Const(_) => 1,
+                Symbol::Function(_) => 2,
+                Symbol::Local(_) => 3,
+            };
+
+            (kind, *name)
+        });
+
+        let mut result = String::new();
+        for (name, symbol) in symbols {
+            match symbol {
+                Symbol::Type(Type::Struct(strct)) => {
+                    result += &format!("type {name} (struct)\n");
+
+                    let mut fields = strct.fields.iter().collect::<Vec<_>>();
+                    fields.sort_by_key(|(_, field)| field.ident_span.start.clone());
+
+                    for (ident, field) in fields {
+                        result += &format!("    {ident}: {}\n", field.ty);
+                    }
+                }
+
+                Symbol::Type(_) => result += &format!("type {name} (primitive)\n"),
+
+                Symbol::Function(function) => {
+                    let params = function
+                        .params
+                        .iter()
+                        .map(|(name, ty)| format!("{name}: {ty}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    result += &format!("fn {name}({params}) -> {}\n", function.return_type);
+                }
+
+                Symbol::Const(constant) => match self.consts.get(&Name::intern(name)) {
+                    Some(value) => result += &format!("const {name}: {} = {value}\n", constant.ty),
+                    None => result += &format!("const {name}: {}\n", constant.ty),
+                },
+
+                Symbol::Local(local) => result += &format!("let {name}: {}\n", local.ty),
+            }
+        }
+
+        result
+    }
+
+    /// Resolve a method called on a value of type `ty`.
+    pub fn resolve_method(&self, ty: &Type, ident: &Ident) -> Option<Function> {
+        match ty {
+            Type::Struct(strct) => self
+                .methods
+                .get(strct.path.as_str())
+                .and_then(|methods| methods.get(ident.repr.as_str()))
+                .cloned(),
+
+            _ => None,
+        }
+    }
+
+    /// Resolve the signature of a function, treating unknown types as `()`. Unknown types are reported later
+    /// on, during type checking.
+    fn resolve_signature(&self, params: &[Param], ty: &Ty) -> Function {
+        let resolve = |ty: &Ty| {
+            self.resolve(ty)
+                .unwrap_or(Type::Primitive(String::from("()")))
+        };
+
+        Function {
+            params: params
+                .iter()
+                .map(|param| (param.ident.repr, resolve(&param.ty)))
+                .collect(),
+            return_type: resolve(ty),
+        }
+    }
+
+    /// Resolve an identifier to the type of the constant it represents.
+    pub fn resolve_const(&self, ident: &Ident) -> Option<Type> {
         self.table
             .find(&ident.repr)
             .and_then(|symbol| match symbol {
-                Symbol::Local(local) => Some(local.ty),
+                Symbol::Const(constant) => Some(constant.ty),
                 _ => None,
             })
     }
@@ -147,16 +365,45 @@ impl<'a> Visit<'a> for Resolver<'a> {
         }
 
         let name = &item_fn.ident.repr;
-
-        let symbol = Symbol::Function(Function {
-            return_type: self
-                .resolve_ty(&item_fn.ty.ident.repr)
-                .unwrap_or(Type::Primitive(String::from("()"))),
-        });
+        let symbol = Symbol::Function(self.resolve_signature(&item_fn.params.params, &item_fn.ty));
 
         self.table.insert(name, symbol)
     }
 
+    fn visit_item_impl(&mut self, item_impl: &'a ItemImpl) {
+        if self.mode != CollectMode::Functions {
+            return;
+        }
+
+        for item in &item_impl.items {
+            match item {
+                ImplItem::Fn(impl_item_fn) => {
+                    let method =
+                        self.resolve_signature(&impl_item_fn.params.params, &impl_item_fn.ty);
+
+                    self.methods
+                        .entry(item_impl.ident.repr.as_str())
+                        .or_default()
+                        .insert(impl_item_fn.ident.repr.as_str(), method);
+                }
+            }
+        }
+    }
+
+    fn visit_item_const(&mut self, item_const: &'a ItemConst) {
+        if self.mode != CollectMode::Functions {
+            return;
+        }
+
+        // Unknown types are reported later on, during type checking
+        let ty = self
+            .resolve(&item_const.ty)
+            .unwrap_or(Type::Primitive(String::from("()")));
+
+        self.table
+            .insert(&item_const.ident.repr, Symbol::Const(Const { ty }))
+    }
+
     fn visit_item_struct(&mut self, item_struct: &'a crate::ast::ItemStruct) {
         if self.mode != CollectMode::Types {
             return;
@@ -165,26 +412,106 @@ impl<'a> Visit<'a> for Resolver<'a> {
         let name = &item_struct.ident.repr;
 
         let symbol = Symbol::Type(Type::Struct(TyStruct {
-            path: name.clone(),
+            path: name.to_string(),
             fields: item_struct_fields(item_struct),
+            tuple: matches!(item_struct.fields, Fields::Unnamed(_)),
         }));
 
         self.table.insert(name, symbol)
     }
 }
 
-fn item_struct_fields(item_struct: &ItemStruct) -> HashMap<String, String> {
+fn item_struct_fields(item_struct: &ItemStruct) -> HashMap<String, TyField> {
     let mut result = HashMap::new();
 
     match &item_struct.fields {
         Fields::Named(named_fields) => {
             for field in &named_fields.fields {
-                result.insert(field.ident.repr.clone(), field.ty.ident.repr.clone());
+                let ty_field = TyField {
+                    ty: field.ty.clone(),
+                    ident_span: field.ident.span.clone(),
+                };
+
+                result.insert(field.ident.repr.to_string(), ty_field);
             }
         }
 
-        _ => {}
+        // Positional fields are named by their index, just like the elements of a tuple
+        Fields::Unnamed(unnamed_fields) => {
+            for (index, ty) in unnamed_fields.fields.iter().enumerate() {
+                let ty_field = TyField {
+                    ty: ty.clone(),
+                    ident_span: ty.span().clone(),
+                };
+
+                result.insert(index.to_string(), ty_field);
+            }
+        }
     }
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    #[test]
+    fn the_dump_lists_types_then_constants_then_functions() {
+        let file = match parse_str(
+            "
+            struct P { y: i32, x: bool }
+            const N: i32 = 2 * 3;
+            fn add(a: i32, b: i32) -> i32 { a + b }
+            fn main() -> i32 { 0 }
+            ",
+        ) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        let dump = resolver.dump();
+        let lines = dump.lines().collect::<Vec<_>>();
+        let position = |line: &str| match lines.iter().position(|l| *l == line) {
+            Some(position) => position,
+            None => panic!("the dump should contain {line:?}:\n{dump}"),
+        };
+
+        // Fields are listed in declaration order, right after their struct
+        let strct = position("type P (struct)");
+        assert_eq!(lines[strct + 1..strct + 3], ["    y: i32", "    x: bool"]);
+
+        // Constants are shown with their value, and builtins are listed with the program's own functions
+        assert!(position("type i32 (primitive)") < position("const N: i32 = 6"));
+        assert!(position("const N: i32 = 6") < position("fn add(a: i32, b: i32) -> i32"));
+        assert!(position("fn add(a: i32, b: i32) -> i32") < position("fn main() -> i32"));
+        position("fn print(value: i32) -> ()");
+    }
+
+    #[test]
+    fn tuple_structs_parse_and_resolve_with_fields_named_by_index() {
+        let file = match parse_str("struct Pair(i32, i32);\nfn main() -> i32 { 0 }") {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+
+        let Some(Type::Struct(strct)) = resolver.resolve_ty("Pair") else {
+            panic!("'Pair' should resolve to a struct");
+        };
+
+        assert!(strct.is_tuple());
+        assert_eq!(strct.fields.len(), 2);
+        for index in ["0", "1"] {
+            let field = &strct.fields[index];
+            assert_eq!(resolver.resolve(&field.ty), resolver.resolve_ty("i32"));
+        }
+    }
+}
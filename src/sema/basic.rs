@@ -1,25 +1,44 @@
 use crate::ast::visitor::{self, Visit};
 use crate::ast::{File, Ident};
-use crate::shared::Span;
+use crate::shared::{Builtin, Span};
 
 use super::{Analysis, SemaError, SemaResult};
 
 /// Performs several basic checks, including...
 /// - Does a `main` function exist?
+/// - Is there only one `main` function?
 /// - Does the `main` function have no parameters?
+/// - Does any function share its name with a function in the prelude?
+/// - Is every attribute known, and does every `@test` function take no parameters?
 pub struct Basic {
     /// The main function identifier.
     main: Option<Ident>,
 
+    /// The identifier of the second `main` function, if there is one.
+    duplicate: Option<Ident>,
+
     /// How many parameters does the main function have?
     params: usize,
+
+    /// The identifier of the first function named after a function in the prelude, if there is one.
+    shadowed: Option<Ident>,
+
+    /// The first attribute that doesn't exist, if there is one.
+    unknown_attr: Option<Ident>,
+
+    /// The first test function with parameters, along with how many it has.
+    test_params: Option<(Ident, usize)>,
 }
 
 impl Basic {
     pub fn new() -> Self {
         Basic {
             main: None,
+            duplicate: None,
             params: 0,
+            shadowed: None,
+            unknown_attr: None,
+            test_params: None,
         }
     }
 }
@@ -28,10 +47,46 @@ impl Analysis for Basic {
     fn run(&mut self, file: &File) -> SemaResult<()> {
         self.visit_file(file);
 
+        if let Some(ident) = &self.shadowed {
+            return Err(SemaError {
+                reason: format!(
+                    "Function '{}' has the same name as a function in the prelude",
+                    ident.repr
+                ),
+                span: Some(ident.span.clone()),
+            });
+        }
+
+        if let Some(ident) = &self.unknown_attr {
+            return Err(SemaError {
+                reason: format!("Unknown attribute '@{}'", ident.repr),
+                span: Some(ident.span.clone()),
+            });
+        }
+
+        if let Some((ident, params)) = &self.test_params {
+            return Err(SemaError {
+                reason: format!(
+                    "Test function '{}' takes no arguments, but {} {} provided",
+                    ident.repr,
+                    params,
+                    if *params == 1 { "was" } else { "were" }
+                ),
+                span: Some(ident.span.clone()),
+            });
+        }
+
+        if let Some(ident) = &self.duplicate {
+            return Err(SemaError {
+                reason: String::from("Multiple definitions of the main function"),
+                span: Some(ident.span.clone()),
+            });
+        }
+
         match &self.main {
             Some(ident) if self.params == 1 => {
                 return Err(SemaError {
-                    reason: format!("Main function takes no arguments, but 1 was provided"),
+                    reason: String::from("Main function takes no arguments, but 1 was provided"),
                     span: Some(ident.span.clone()),
                 });
             }
@@ -48,7 +103,7 @@ impl Analysis for Basic {
 
             None => {
                 return Err(SemaError {
-                    reason: format!("Could not find the main function"),
+                    reason: String::from("Could not find the main function"),
                     span: Some(Span::single(file.span.start.line, file.span.start.column)),
                 });
             }
@@ -62,7 +117,30 @@ impl Analysis for Basic {
 
 impl Visit<'_> for Basic {
     fn visit_item_fn(&mut self, item_fn: &'_ crate::ast::ItemFn) {
+        if self.shadowed.is_none() && Builtin::find(&item_fn.ident.repr).is_some() {
+            self.shadowed = Some(item_fn.ident.clone());
+        }
+
+        for attr in &item_fn.attrs {
+            if self.unknown_attr.is_none() && attr.ident.repr != "test" {
+                self.unknown_attr = Some(attr.ident.clone());
+            }
+        }
+
+        if self.test_params.is_none() && item_fn.is_test() && item_fn.params.len() > 0 {
+            self.test_params = Some((item_fn.ident.clone(), item_fn.params.len()));
+        }
+
         if item_fn.ident.repr == "main" {
+            if self.main.is_some() {
+                // Only the first duplicate is reported
+                if self.duplicate.is_none() {
+                    self.duplicate = Some(item_fn.ident.clone());
+                }
+
+                return;
+            }
+
             self.main = Some(item_fn.ident.clone());
             self.params = item_fn.params.len();
         } else {
@@ -70,3 +148,27 @@ impl Visit<'_> for Basic {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    #[test]
+    fn a_second_main_is_reported_at_its_name() {
+        let file = match parse_str("fn main() -> i32 { 0 }\nfn main() -> i32 { 1 }") {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let err = match Basic::new().run(&file) {
+            Ok(()) => panic!("the program should be rejected"),
+            Err(err) => err,
+        };
+
+        assert_eq!(err.reason, "Multiple definitions of the main function");
+
+        let span = err.span.expect("the error should have a span");
+        assert_eq!((span.start.line, span.start.column), (2, 4));
+    }
+}
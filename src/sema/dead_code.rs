@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::visitor::{self, Visit};
+use crate::ast::{CallFn, ExprMethodCall, File, Ident, ImplItemFn, ItemFn};
+use crate::shared::Name;
+
+use super::{Analysis, SemaError, SemaResult};
+
+/// Warns about functions that can never be called. Starting from `main`, every `@test` function and every `pub`
+/// function (which can be called from outside the program), every function reachable through the call graph is
+/// marked as used, and every other top-level function is reported. Calls made inside methods count once the method
+/// is reachable, and since the receiver's type isn't known here, calling a method reaches every method of that name.
+pub struct DeadCode {
+    /// The warnings found during the last run.
+    warnings: Vec<SemaError>,
+}
+
+impl DeadCode {
+    pub fn new() -> Self {
+        DeadCode {
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Analysis for DeadCode {
+    fn run(&mut self, file: &File) -> SemaResult<()> {
+        let mut graph = CallGraph::new();
+        graph.visit_file(file);
+
+        let reachable = graph.reachable();
+
+        for ident in &graph.functions {
+            if !reachable.contains(ident.repr.as_str()) {
+                self.warnings.push(SemaError {
+                    reason: format!("Function '{}' is never called", ident.repr),
+                    span: Some(ident.span.clone()),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn warnings(&mut self) -> Vec<SemaError> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// Records which functions are called by every function and method. Every method is recorded under its name
+/// prefixed by a `.`, so that methods and functions can share a name.
+struct CallGraph<'a> {
+    /// The identifiers of every top-level function, in declaration order.
+    functions: Vec<&'a Ident>,
+
+    /// Map from each function (or method) to the names of the functions and methods it calls.
+    calls: HashMap<&'a str, Vec<&'a str>>,
+
+    /// The function or method whose body is currently being visited.
+    current: Option<&'a str>,
+
+    /// The names of the functions that are called from outside the program, which are those marked with `@test`
+    /// (run directly) and those declared `pub`.
+    roots: Vec<&'a str>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn new() -> Self {
+        CallGraph {
+            functions: Vec::new(),
+            calls: HashMap::new(),
+            current: None,
+            roots: Vec::new(),
+        }
+    }
+
+    /// Return the names of all functions and methods reachable from `main` or another root.
+    fn reachable(&self) -> HashSet<&'a str> {
+        let mut reachable = HashSet::new();
+        let mut stack = vec!["main"];
+        stack.extend(self.roots.iter().copied());
+
+        while let Some(name) = stack.pop() {
+            if reachable.insert(name) {
+                if let Some(callees) = self.calls.get(name) {
+                    stack.extend(callees.iter().copied());
+                }
+            }
+        }
+
+        reachable
+    }
+}
+
+impl<'a> Visit<'a> for CallGraph<'a> {
+    fn visit_item_fn(&mut self, item_fn: &'a ItemFn) {
+        self.functions.push(&item_fn.ident);
+
+        if item_fn.is_test() || item_fn.vis.is_public() {
+            self.roots.push(&item_fn.ident.repr);
+        }
+        self.current = Some(&item_fn.ident.repr);
+
+        visitor::visit_item_fn(self, item_fn);
+
+        self.current = None;
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'a ImplItemFn) {
+        self.current = Some(method(&impl_item_fn.ident));
+
+        visitor::visit_impl_item_fn(self, impl_item_fn);
+
+        self.current = None;
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &'a ExprMethodCall) {
+        if let Some(current) = self.current {
+            self.calls
+                .entry(current)
+                .or_default()
+                .push(method(&expr_method_call.ident));
+        }
+
+        visitor::visit_expr_method_call(self, expr_method_call);
+    }
+
+    fn visit_call_fn(&mut self, call_fn: &'a CallFn) {
+        if let Some(current) = self.current {
+            self.calls
+                .entry(current)
+                .or_default()
+                .push(&call_fn.ident.repr);
+        }
+
+        visitor::visit_call_fn(self, call_fn);
+    }
+}
+
+/// The name a method is recorded under in the call graph.
+fn method(ident: &Ident) -> &'static str {
+    Name::intern(&format!(".{}", ident.repr)).as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    /// The names of the functions reported as never called in a program, which must parse.
+    fn uncalled(src: &str) -> Vec<String> {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut dead_code = DeadCode::new();
+        assert!(dead_code.run(&file).is_ok());
+
+        dead_code
+            .warnings()
+            .into_iter()
+            .map(|warning| warning.reason)
+            .collect()
+    }
+
+    #[test]
+    fn only_uncalled_functions_are_reported() {
+        let src = "
+            fn used() -> i32 { 1 }
+            fn unused() -> i32 { 2 }
+            fn main() -> i32 { used() }
+        ";
+
+        assert_eq!(uncalled(src), ["Function 'unused' is never called"]);
+    }
+
+    #[test]
+    fn calls_inside_methods_count() {
+        let src = "
+            struct P { x: i32 }
+            fn helper() -> i32 { 1 }
+            fn other() -> i32 { 2 }
+            impl P {
+                fn get(self) -> i32 { helper() }
+                fn never(self) -> i32 { other() }
+            }
+            fn main() -> i32 { let p = P { x: 1 }; p.get() }
+        ";
+
+        // `never` isn't called, so neither is `other`
+        assert_eq!(uncalled(src), ["Function 'other' is never called"]);
+    }
+
+    #[test]
+    fn public_functions_are_used() {
+        let src = "
+            pub fn api() -> i32 { helper() }
+            fn helper() -> i32 { 1 }
+            fn main() -> i32 { 0 }
+        ";
+
+        assert!(uncalled(src).is_empty());
+    }
+}
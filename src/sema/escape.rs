@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use crate::ast::visitor::Visit;
+use crate::ast::{Block, Expr, File, Ident, ImplItemFn, ItemFn, Param, Stmt};
+use crate::shared::Name;
+
+use super::{Analysis, SemaError, SemaResult};
+
+/// Rejects functions that return a reference to one of their own locals or parameters (such as `return &x;`),
+/// since the reference would outlive the value it refers to. Parameters are passed by value, so they're dropped
+/// when the function returns just like locals are. For now, only references taken directly in the returned
+/// expression are caught, including references to a field or element of a local (`&x.field`).
+pub struct Escape {
+    /// The first error found during the last run.
+    error: Option<SemaError>,
+}
+
+impl Escape {
+    pub fn new() -> Self {
+        Escape { error: None }
+    }
+
+    /// Check every value returned from a function body, either by a `return` statement or the tail expression.
+    fn check_fn(&mut self, params: &[Param], body: &Block) {
+        let params = params
+            .iter()
+            .map(|param| param.ident.repr)
+            .collect::<HashSet<Name>>();
+        let mut locals = HashSet::new();
+
+        for stmt in &body.stmts {
+            match stmt {
+                Stmt::Local(local) => {
+                    locals.insert(local.ident.repr);
+                }
+
+                Stmt::Return(ret) => {
+                    if let Some(expr) = &ret.expr {
+                        self.check_returned(expr, &params, &locals);
+                    }
+                }
+
+                Stmt::Assign(_) | Stmt::Expr(_) | Stmt::Break(_) => {}
+            }
+        }
+
+        if let Some(tail) = &body.tail {
+            self.check_returned(tail, &params, &locals);
+        }
+    }
+
+    /// Report a returned expression that borrows from a local or parameter.
+    fn check_returned(&mut self, expr: &Expr, params: &HashSet<Name>, locals: &HashSet<Name>) {
+        let Expr::Ref(expr_ref) = expr else {
+            return;
+        };
+
+        let Some(root) = borrowed_root(&expr_ref.expr) else {
+            return;
+        };
+
+        let kind = if locals.contains(&root.repr) {
+            "local variable"
+        } else if params.contains(&root.repr) {
+            "parameter"
+        } else {
+            return;
+        };
+
+        if self.error.is_none() {
+            self.error = Some(SemaError {
+                reason: format!(
+                    "Cannot return a reference to {kind} '{}', which is dropped when the function returns",
+                    root.repr
+                ),
+                span: Some(expr.span().clone()),
+            });
+        }
+    }
+}
+
+/// Find the variable a place expression (such as `x`, `x.field` or `x.0`) is part of, if there is one.
+fn borrowed_root(expr: &Expr) -> Option<&Ident> {
+    match expr {
+        Expr::Ident(ident) => Some(ident),
+        Expr::Field(expr_field) => borrowed_root(&expr_field.expr),
+        Expr::TupleIndex(expr_tuple_index) => borrowed_root(&expr_tuple_index.expr),
+        _ => None,
+    }
+}
+
+impl Analysis for Escape {
+    fn run(&mut self, file: &File) -> SemaResult<()> {
+        self.visit_file(file);
+
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Visit<'_> for Escape {
+    fn visit_item_fn(&mut self, item_fn: &ItemFn) {
+        self.check_fn(&item_fn.params.params, &item_fn.body);
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &ImplItemFn) {
+        self.check_fn(&impl_item_fn.params.params, &impl_item_fn.body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+    use crate::resolution::Resolver;
+    use crate::sema::typeck::TypeCk;
+
+    /// Run escape analysis on an entire program, which must parse, returning the reason of the error (if any).
+    fn escape(src: &str) -> Option<String> {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        Escape::new().run(&file).err().map(|err| err.reason)
+    }
+
+    #[test]
+    fn returning_a_reference_to_a_local_is_rejected() {
+        let expected = "Cannot return a reference to local variable 'x', which is dropped when the function returns";
+
+        assert_eq!(
+            escape("fn f() -> &i32 { let x: i32 = 1; return &x; }").as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            escape("fn f() -> &i32 { let x: i32 = 1; &x }").as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn returning_a_reference_to_a_parameter_is_rejected() {
+        assert_eq!(
+            escape("fn f(x: i32) -> &i32 { &x }").as_deref(),
+            Some("Cannot return a reference to parameter 'x', which is dropped when the function returns")
+        );
+    }
+
+    #[test]
+    fn returning_a_reference_passed_as_a_parameter_is_allowed() {
+        let src = "fn f(x: &i32) -> &i32 { x }\nfn main() -> i32 { let a = 1; let r = f(&a); 0 }";
+        assert_eq!(escape(src), None);
+
+        // The parameter can be used in the body, so the program as a whole checks out
+        let file = parse_str(src).ok().unwrap();
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        assert!(TypeCk::new(&resolver).run(&file).is_ok());
+    }
+}
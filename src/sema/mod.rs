@@ -1,4 +1,7 @@
 pub mod basic;
+pub mod dead_code;
+pub mod escape;
+pub mod recursion;
 pub mod typeck;
 
 use crate::{ast::File, shared::Span};
@@ -18,12 +21,18 @@ type SemaResult<T> = Result<T, SemaError>;
 /// Must be implemented for any semantic analysis.
 pub trait Analysis {
     fn run(&mut self, ast: &File) -> SemaResult<()>;
+
+    /// Return the warnings found by the last run. Warnings don't prevent compilation.
+    fn warnings(&mut self) -> Vec<SemaError> {
+        Vec::new()
+    }
 }
 
 /// Contains all semantic analysis to be run on the AST.
 pub struct SemaEngine<'a> {
     ast: &'a File,
     analyses: Vec<Box<dyn Analysis>>,
+    warnings: Vec<SemaError>,
 }
 
 impl<'a> SemaEngine<'a> {
@@ -32,6 +41,7 @@ impl<'a> SemaEngine<'a> {
         SemaEngine {
             ast,
             analyses: vec![],
+            warnings: vec![],
         }
     }
 
@@ -46,12 +56,14 @@ impl<'a> SemaEngine<'a> {
         let mut errors = Vec::new();
 
         for analysis in &mut self.analyses {
-            match analysis.run(&self.ast) {
+            match analysis.run(self.ast) {
                 Ok(_) => {}
                 Err(err) => {
                     errors.push(err);
                 }
             }
+
+            self.warnings.append(&mut analysis.warnings());
         }
 
         if errors.is_empty() {
@@ -60,4 +72,9 @@ impl<'a> SemaEngine<'a> {
             Err(errors)
         }
     }
+
+    /// Return every warning produced by the analyses that have run.
+    pub fn warnings(&self) -> &[SemaError] {
+        &self.warnings
+    }
 }
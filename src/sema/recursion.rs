@@ -0,0 +1,189 @@
+use crate::ast::visitor::{self, Visit};
+use crate::ast::{CallFn, Expr, ExprBlock, ExprIf, ExprMatch, File, ItemFn, Return, Stmt};
+use crate::shared::{Name, Span};
+
+use super::{Analysis, SemaError, SemaResult};
+
+/// Warns about functions that always call themselves, and so can never return (such as `fn f() -> i32 { return
+/// f(); }`). The statements of the body are followed in order until one of them might return (even from inside a
+/// branch, which is how a base case usually looks), and the first call to the function itself along the way is
+/// reported. Calls inside block expressions are ignored, since a `break` could skip
+/// them, and so are calls in the branches of a conditional, so only the clearest cases are caught.
+pub struct Recursion {
+    /// The warnings found during the last run.
+    warnings: Vec<SemaError>,
+}
+
+impl Recursion {
+    pub fn new() -> Self {
+        Recursion {
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Analysis for Recursion {
+    fn run(&mut self, file: &File) -> SemaResult<()> {
+        self.visit_file(file);
+        Ok(())
+    }
+
+    fn warnings(&mut self) -> Vec<SemaError> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl Visit<'_> for Recursion {
+    fn visit_item_fn(&mut self, item_fn: &'_ ItemFn) {
+        let name = item_fn.ident.repr;
+
+        for stmt in &item_fn.body.stmts {
+            let expr = match stmt {
+                Stmt::Local(local) => &local.expr,
+                Stmt::Assign(assign) => &assign.expr,
+                Stmt::Expr(expr) => expr,
+                Stmt::Return(ret) => match &ret.expr {
+                    Some(expr) => expr,
+                    None => return,
+                },
+
+                // A `break` outside of a labeled block is reported during type checking
+                Stmt::Break(_) => return,
+            };
+
+            if let Some(span) = self_call(name, expr) {
+                return self.report(item_fn, span);
+            }
+
+            // Nothing after a `return` runs, and a `return` nested in a branch may skip the rest of the body
+            if may_return(stmt) {
+                return;
+            }
+        }
+
+        if let Some(span) = item_fn
+            .body
+            .tail
+            .as_ref()
+            .and_then(|tail| self_call(name, tail))
+        {
+            self.report(item_fn, span);
+        }
+    }
+}
+
+impl Recursion {
+    fn report(&mut self, item_fn: &ItemFn, span: Span) {
+        self.warnings.push(SemaError {
+            reason: format!(
+                "Function '{}' always calls itself, so it can never return",
+                item_fn.ident.repr
+            ),
+            span: Some(span),
+        });
+    }
+}
+
+/// Find the first call to the function `name` that's certain to run when `expr` is evaluated.
+fn self_call(name: Name, expr: &Expr) -> Option<Span> {
+    let mut finder = SelfCall { name, found: None };
+    finder.visit_expr(expr);
+    finder.found
+}
+
+/// Whether running `stmt` might return from the function, because it's a `return` statement or contains one.
+fn may_return(stmt: &Stmt) -> bool {
+    let mut finder = ReturnFinder { found: false };
+    finder.visit_stmt(stmt);
+    finder.found
+}
+
+/// Searches a statement for `return` statements, including those nested in blocks and branches.
+struct ReturnFinder {
+    /// Whether a `return` statement was found.
+    found: bool,
+}
+
+impl Visit<'_> for ReturnFinder {
+    fn visit_ret(&mut self, _: &'_ Return) {
+        self.found = true;
+    }
+}
+
+/// Searches an expression for a call to a particular function.
+struct SelfCall {
+    /// The name of the function.
+    name: Name,
+
+    /// The span of the first call found.
+    found: Option<Span>,
+}
+
+impl Visit<'_> for SelfCall {
+    fn visit_call_fn(&mut self, call_fn: &'_ CallFn) {
+        // Arguments are evaluated before the call itself
+        visitor::visit_call_fn(self, call_fn);
+
+        if self.found.is_none() && call_fn.ident.repr == self.name {
+            self.found = Some(call_fn.span.clone());
+        }
+    }
+
+    fn visit_expr_block(&mut self, _: &'_ ExprBlock) {}
+
+    /// Only the condition is always evaluated.
+    fn visit_expr_if(&mut self, expr_if: &'_ ExprIf) {
+        self.visit_expr(&expr_if.cond);
+    }
+
+    /// Only the scrutinee is always evaluated.
+    fn visit_expr_match(&mut self, expr_match: &'_ ExprMatch) {
+        self.visit_expr(&expr_match.scrutinee);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    /// The warnings reported about a program, which must parse.
+    fn warnings(src: &str) -> Vec<String> {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut recursion = Recursion::new();
+        assert!(recursion.run(&file).is_ok());
+
+        recursion
+            .warnings()
+            .into_iter()
+            .map(|warning| warning.reason)
+            .collect()
+    }
+
+    #[test]
+    fn an_unconditional_self_call_is_reported() {
+        assert_eq!(
+            warnings("fn f() -> i32 { return f(); }\nfn main() -> i32 { 0 }"),
+            ["Function 'f' always calls itself, so it can never return"]
+        );
+        assert_eq!(
+            warnings("fn g(n: i32) -> i32 { let m = n + 1; g(m) * 2 }\nfn main() -> i32 { 0 }"),
+            ["Function 'g' always calls itself, so it can never return"]
+        );
+    }
+
+    #[test]
+    fn recursion_with_a_base_case_is_not_reported() {
+        let src = "
+            fn fact(n: i32) -> i32 { if n == 0 { 1 } else { n * fact(n - 1) } }
+            fn count(n: i32) -> i32 { if n == 0 { return 0; } count(n - 1) + 1 }
+            fn main() -> i32 { fact(3) + count(3) }
+        ";
+
+        assert!(warnings(src).is_empty());
+    }
+}
@@ -1,98 +1,594 @@
 use crate::{
-    ast::{visitor::Visit, Block, Expr, ExprBin, ExprCall, ExprLit, ExprStruct, File, Ident, Stmt},
-    resolution::{Local, Resolver, Symbol, Type},
-    shared::Span,
+    ast::{
+        fold::Fold,
+        visitor::{self, Visit},
+        ArgList, Block, Expr, ExprBin, ExprBlock, ExprCall, ExprCmp, ExprField, ExprIf, ExprLit,
+        ExprMatch, ExprMethodCall, ExprRef, ExprStruct, ExprTuple, ExprTupleIndex, ExprUnary, File,
+        Ident, Item, ItemConst, Param, Pat, Stmt, Ty, UnaryOpKind,
+    },
+    ir::fold::ConstFolder,
+    resolution::{Function, Local, Resolver, Symbol, TyField, TyStruct, Type},
+    shared::{Diagnostic, Name, Span, SymbolTable},
 };
+use std::collections::HashMap;
 
-pub struct TypeCkError {
-    /// The cause of this error.
-    pub reason: String,
+pub type TypeCkResult<T> = Result<T, Diagnostic>;
 
-    /// The (optional) span of this error.
-    pub span: Option<Span>,
+/// The types computed while type checking a program, so that later stages (such as lowering) can look up the type of
+/// an expression instead of working it out again.
+pub struct Types<'a> {
+    /// Resolves the types of struct fields. It borrows the program, so the program's expressions can't move while
+    /// the types are around.
+    resolver: &'a Resolver<'a>,
+
+    /// The type of every expression, keyed by its address. The program can't move, so every address belongs to
+    /// exactly one expression.
+    exprs: HashMap<*const Expr, Type>,
 }
 
-pub type TypeCkResult<T> = Result<T, TypeCkError>;
+impl Types<'_> {
+    /// The type of an expression, if it was type checked.
+    pub fn of(&self, expr: &Expr) -> Option<&Type> {
+        self.exprs.get(&(expr as *const Expr))
+    }
+
+    /// The type of a field of a struct, if the struct has such a field.
+    pub fn field(&self, strct: &TyStruct, ident: &str) -> Option<Type> {
+        strct
+            .fields
+            .get(ident)
+            .and_then(|field| self.resolver.resolve(&field.ty))
+    }
+}
 
 pub struct TypeCk<'a> {
-    resolver: Resolver<'a>,
+    /// Resolves the items of the program. It's only ever read, so the same resolver can be used to type check the
+    /// program any number of times.
+    resolver: &'a Resolver<'a>,
+
+    /// The locals in scope, which start out empty every time the program is type checked.
+    locals: SymbolTable<'a, Symbol>,
+
     result: TypeCkResult<()>,
+
+    /// The labels of the blocks enclosing the current expression, innermost last, along with the type and span of
+    /// every value that exits the block so far.
+    labels: Vec<(Name, Vec<(Type, Span)>)>,
+
+    /// The struct implemented by the enclosing `impl` block, which is the type of `self` inside its methods.
+    receiver: Option<Type>,
+
+    /// The type of every expression checked so far.
+    types: HashMap<*const Expr, Type>,
+
+    /// The declared return type of the enclosing function, which every `return` statement inside it must match, and
+    /// whether that function is `main`.
+    ret: Option<(Type, bool)>,
 }
 
 impl<'a> TypeCk<'a> {
-    pub fn new(resolver: Resolver<'a>) -> Self {
+    pub fn new(resolver: &'a Resolver<'a>) -> Self {
         TypeCk {
             resolver,
+            locals: SymbolTable::new(),
             result: Ok(()),
+            labels: Vec::new(),
+            receiver: None,
+            types: HashMap::new(),
+            ret: None,
         }
     }
 
-    pub fn run(mut self, file: &'a File) -> TypeCkResult<()> {
+    /// Type check the program, returning the type of every expression in it.
+    pub fn run(mut self, file: &'a File) -> TypeCkResult<Types<'a>> {
         self.visit_file(file);
-        self.result
+
+        self.result.map(|_| Types {
+            resolver: self.resolver,
+            exprs: self.types,
+        })
+    }
+
+    /// Type check the program, then type check `block` as the body of a function whose parameters are `locals`, and
+    /// return its type. The block can use every item in the program, but isn't part of it.
+    pub fn infer(
+        mut self,
+        file: &'a File,
+        block: &'a Block,
+        locals: &[(Name, Type)],
+    ) -> TypeCkResult<Type> {
+        self.visit_file(file);
+        std::mem::replace(&mut self.result, Ok(()))?;
+
+        for (name, ty) in locals {
+            let ty = ty.clone();
+            self.locals
+                .insert(name.as_str(), Symbol::Local(Local { ty }));
+        }
+
+        self.typeck_block(block)
     }
 }
 
 impl<'a> Visit<'a> for TypeCk<'a> {
+    /// Stops at the first item that doesn't type check, since the errors after it often follow from it.
+    fn visit_item(&mut self, item: &'a Item) {
+        if self.result.is_ok() {
+            visitor::visit_item(self, item);
+        }
+    }
+
+    /// Checks that the type of the function body matches the declared return type. The `main` function is
+    /// special - if it is declared to return `i32` but its body falls through without returning a value, the
+    /// exit code is zero, exactly like a `main` that returns `()`.
     fn visit_item_fn(&mut self, item_fn: &'a crate::ast::ItemFn) {
-        // Does the type of the body match the expected return type?
-        match self.resolver.resolve_ty(&item_fn.ty.ident.repr) {
-            Some(expected) => {
-                match self.typeck_block(&item_fn.body) {
-                    Err(err) => self.result = Err(err),
-                    Ok(actual) => {
-                        if expected != actual {
-                            self.result = Err(TypeCkError { reason: format!("Function must return type '{}' but type '{}' is returned instead", expected, actual), span: Some(item_fn.ty.span.clone()) })
-                        }
-                    }
+        let main = item_fn.ident.repr == "main";
+
+        if let Err(err) = self.typeck_fn(
+            None,
+            &item_fn.params.params,
+            &item_fn.ty,
+            &item_fn.body,
+            main,
+        ) {
+            self.result = Err(err);
+        }
+    }
+
+    /// Checks that a constant is an `i32` whose value can be computed at compile time.
+    fn visit_item_const(&mut self, item_const: &'a ItemConst) {
+        if let Err(err) = self.typeck_item_const(item_const) {
+            self.result = Err(err);
+        }
+    }
+
+    fn visit_item_struct(&mut self, item_struct: &'a crate::ast::ItemStruct) {
+        // Do the types of all the fields exist?
+        if let Some(Type::Struct(strct)) = self.resolver.resolve_ty(&item_struct.ident.repr) {
+            let mut fields = strct.fields.values().collect::<Vec<_>>();
+            fields.sort_by_key(|field| field.ty.span().start.clone());
+
+            for field in fields {
+                if let Err(err) = self.typeck_field(field) {
+                    self.result = Err(err);
+                    return;
                 }
             }
+        }
+    }
+
+    /// Checks that the type being implemented is a declared struct before checking the functions inside.
+    fn visit_item_impl(&mut self, item_impl: &'a crate::ast::ItemImpl) {
+        let ident = &item_impl.ident;
+
+        match self.resolver.resolve_ty(&ident.repr) {
+            Some(ty @ Type::Struct(_)) => {
+                self.receiver = Some(ty);
+                visitor::visit_item_impl(self, item_impl);
+                self.receiver = None;
+            }
+
+            // Only structs and primitives can be named
+            Some(ty) => {
+                self.result = Err(Diagnostic::error(format!(
+                    "Cannot implement the primitive type '{}'",
+                    ty
+                ))
+                .with_span(ident.span.clone())
+                .with_note(String::from(
+                    "Only structs declared in this program can be implemented",
+                )))
+            }
 
             None => {
-                self.result = Err(TypeCkError {
-                    reason: format!("Unknown type '{}'", item_fn.ty.ident.repr),
-                    span: Some(item_fn.ty.span.clone()),
-                })
+                self.result = Err(self.suggest_ty(
+                    Diagnostic::error(format!("Cannot implement unknown type '{}'", ident.repr))
+                        .with_span(ident.span.clone()),
+                    &ident.repr,
+                ))
             }
         }
     }
 
+    /// Checks a method like any other function, except that `self` is bound to the implemented struct (if the
+    /// method takes it), so sibling methods can be called on it.
     fn visit_impl_item_fn(&mut self, item_fn: &'a crate::ast::ImplItemFn) {
-        // Does the type of the body match the expected return type?
-        match self.resolver.resolve_ty(&item_fn.ty.ident.repr) {
-            Some(expected) => {
-                match self.typeck_block(&item_fn.body) {
-                    Err(err) => self.result = Err(err),
-                    Ok(actual) => {
-                        if expected != actual {
-                            self.result = Err(TypeCkError { reason: format!("Function must return type '{}' but type '{}' is returned instead", expected, actual), span: Some(item_fn.ty.span.clone()) })
+        let receiver = match &item_fn.params.receiver {
+            Some(_) => self.receiver.clone(),
+            None => None,
+        };
+
+        if let Err(err) = self.typeck_fn(
+            receiver,
+            &item_fn.params.params,
+            &item_fn.ty,
+            &item_fn.body,
+            false,
+        ) {
+            self.result = Err(err);
+        }
+    }
+}
+
+/// Whether a function declared to return `expected` may return a value of type `actual`. The `main` function is
+/// special - if it is declared to return `i32`, it may also return `()`, which results in an exit code of zero.
+fn returns(expected: &Type, actual: &Type, main: bool) -> bool {
+    let unit = Type::Primitive(String::from("()"));
+    let i32 = Type::Primitive(String::from("i32"));
+
+    expected == actual || (main && *expected == i32 && *actual == unit)
+}
+
+/// Reports a value whose type doesn't match the declared return type of the function it's returned from. The error
+/// points at where the value comes from, which is a `return` statement, the tail expression, or the return type if
+/// the body runs off its end.
+fn return_mismatch(expected: &Type, actual: &Type, span: Span) -> Diagnostic {
+    Diagnostic::error(format!(
+        "Function must return type '{}' but type '{}' is returned instead",
+        expected, actual
+    ))
+    .with_span(span)
+}
+
+/// Explains that integers aren't implicitly converted to booleans, if that's what `diagnostic` is about.
+fn no_truthiness(diagnostic: Diagnostic, expected: &Type, actual: &Type) -> Diagnostic {
+    match (expected, actual) {
+        (Type::Primitive(expected), Type::Primitive(actual))
+            if expected == "bool" && actual == "i32" =>
+        {
+            diagnostic.with_note(String::from(
+                "Integers aren't treated as booleans, so compare the value instead (for example, 'x != 0')",
+            ))
+        }
+        _ => diagnostic,
+    }
+}
+
+/// Whether a block always exits early, because it ends with a `return` or `break` statement instead of a value.
+fn exits_early(block: &Block) -> bool {
+    block.tail.is_none() && matches!(block.stmts.last(), Some(Stmt::Return(_) | Stmt::Break(_)))
+}
+
+/// Whether the branch of a conditional always exits early. An `else if` chain (or a `match`) only does if every
+/// branch does.
+fn expr_exits_early(expr: &Expr) -> bool {
+    match expr {
+        Expr::Block(expr_block) => exits_early(&expr_block.block),
+        Expr::If(expr_if) => {
+            exits_early(&expr_if.then) && expr_if.els.as_deref().is_some_and(expr_exits_early)
+        }
+        Expr::Match(expr_match) => {
+            !expr_match.arms.is_empty()
+                && expr_match
+                    .arms
+                    .iter()
+                    .all(|arm| expr_exits_early(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+/// The span of the value a block produces, which is its tail expression if there is one and its closing brace
+/// otherwise.
+fn block_value_span(block: &Block) -> Span {
+    match &block.tail {
+        Some(tail) => tail.span().clone(),
+        None => block.rc.span.clone().unwrap_or(block.span.clone()),
+    }
+}
+
+/// Checks that a call to the function (or method) `ident` with the signature `sig` passes the right number of
+/// arguments, and that every argument has the type of its parameter. Mismatches name the parameter by position and
+/// name, since the argument itself may not make it obvious which one it is.
+fn check_args(
+    ident: &Ident,
+    sig: &Function,
+    args: &ArgList,
+    tys: &[Type],
+    span: &Span,
+) -> TypeCkResult<()> {
+    if tys.len() != sig.params.len() {
+        return Err(Diagnostic::error(format!(
+            "'{}' takes {} argument{} but {} {} supplied",
+            ident.repr,
+            sig.params.len(),
+            if sig.params.len() == 1 { "" } else { "s" },
+            tys.len(),
+            if tys.len() == 1 { "was" } else { "were" }
+        ))
+        .with_span(span.clone()));
+    }
+
+    for (index, ((name, expected), actual)) in sig.params.iter().zip(tys).enumerate() {
+        if expected != actual {
+            return Err(Diagnostic::error(format!(
+                "Argument {} ('{}') of '{}' expects type '{}' but found '{}'",
+                index + 1,
+                name,
+                ident.repr,
+                expected,
+                actual
+            ))
+            .with_span(args.args[index].span().clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the type of every expression.
+impl<'a> Fold<'a> for TypeCk<'a> {
+    type Output = TypeCkResult<Type>;
+
+    fn fold_expr_bin(&mut self, expr_bin: &'a ExprBin) -> Self::Output {
+        self.typeck_expr_bin(expr_bin)
+    }
+
+    fn fold_expr_call(&mut self, expr_call: &'a ExprCall) -> Self::Output {
+        self.typeck_expr_call(expr_call)
+    }
+
+    fn fold_expr_lit(&mut self, expr_lit: &'a ExprLit) -> Self::Output {
+        self.typeck_expr_lit(expr_lit)
+    }
+
+    fn fold_expr_struct(&mut self, expr_struct: &'a ExprStruct) -> Self::Output {
+        self.typeck_expr_struct(expr_struct)
+    }
+
+    fn fold_ident(&mut self, ident: &'a Ident) -> Self::Output {
+        self.typeck_ident(ident)
+    }
+
+    fn fold_expr_field(&mut self, expr_field: &'a ExprField) -> Self::Output {
+        self.typeck_expr_field(expr_field)
+    }
+
+    fn fold_expr_method_call(&mut self, expr_method_call: &'a ExprMethodCall) -> Self::Output {
+        self.typeck_expr_method_call(expr_method_call)
+    }
+
+    fn fold_expr_tuple(&mut self, expr_tuple: &'a ExprTuple) -> Self::Output {
+        self.typeck_expr_tuple(expr_tuple)
+    }
+
+    fn fold_expr_tuple_index(&mut self, expr_tuple_index: &'a ExprTupleIndex) -> Self::Output {
+        self.typeck_expr_tuple_index(expr_tuple_index)
+    }
+
+    fn fold_expr_block(&mut self, expr_block: &'a ExprBlock) -> Self::Output {
+        self.typeck_expr_block(expr_block)
+    }
+
+    fn fold_expr_ref(&mut self, expr_ref: &'a ExprRef) -> Self::Output {
+        Ok(Type::Ref(Box::new(self.typeck_expr(&expr_ref.expr)?)))
+    }
+
+    fn fold_expr_unary(&mut self, expr_unary: &'a ExprUnary) -> Self::Output {
+        self.typeck_expr_unary(expr_unary)
+    }
+
+    fn fold_expr_if(&mut self, expr_if: &'a ExprIf) -> Self::Output {
+        self.typeck_expr_if(expr_if)
+    }
+
+    fn fold_expr_cmp(&mut self, expr_cmp: &'a ExprCmp) -> Self::Output {
+        self.typeck_expr_cmp(expr_cmp)
+    }
+
+    fn fold_expr_match(&mut self, expr_match: &'a ExprMatch) -> Self::Output {
+        self.typeck_expr_match(expr_match)
+    }
+}
+
+impl<'a> TypeCk<'a> {
+    /// Adds a suggestion naming the closest known type (if there is one) to an "unknown type" diagnostic.
+    fn suggest_ty(&self, diagnostic: Diagnostic, repr: &str) -> Diagnostic {
+        match self.resolver.suggest_ty(repr) {
+            Some(name) => diagnostic.with_suggestion(format!("did you mean '{name}'?")),
+            None => diagnostic,
+        }
+    }
+
+    /// Resolve an identifier to the type of the local it represents.
+    fn resolve_local(&self, ident: &Ident) -> Option<Type> {
+        self.locals
+            .find(&ident.repr)
+            .and_then(|symbol| match symbol {
+                Symbol::Local(local) => Some(local.ty),
+                _ => None,
+            })
+    }
+
+    /// Checks that the type of a function body matches the declared return type. The parameters (and `self`, given
+    /// the type of the receiver) are locals of the body, which aren't visible to any other function.
+    fn typeck_fn(
+        &mut self,
+        receiver: Option<Type>,
+        params: &'a [Param],
+        ty: &Ty,
+        body: &'a Block,
+        main: bool,
+    ) -> TypeCkResult<()> {
+        self.locals.up();
+
+        if let Some(ty) = receiver {
+            self.locals.insert("self", Symbol::Local(Local { ty }));
+        }
+
+        let result = self
+            .typeck_params(params)
+            .and_then(|_| self.typeck_ty(ty))
+            .and_then(|expected| {
+                self.ret = Some((expected.clone(), main));
+                let actual = self.typeck_block(body);
+                self.ret = None;
+
+                Ok((expected, actual?))
+            });
+
+        self.locals.down();
+        let (expected, actual) = result?;
+
+        // A body that ends with a `return` statement was already checked there
+        if exits_early(body) || returns(&expected, &actual, main) {
+            return Ok(());
+        }
+
+        let span = match &body.tail {
+            Some(tail) => tail.span().clone(),
+            None => ty.span().clone(),
+        };
+
+        Err(return_mismatch(&expected, &actual, span))
+    }
+
+    /// Binds every parameter as a local of its declared type, which must exist.
+    fn typeck_params(&mut self, params: &'a [Param]) -> TypeCkResult<()> {
+        for param in params {
+            self.typeck_binding(&param.ident)?;
+
+            let ty = self.typeck_ty(&param.ty)?;
+            self.locals
+                .insert(&param.ident.repr, Symbol::Local(Local { ty }));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a local or parameter doesn't shadow a constant, since uses of constants are replaced by their
+    /// values.
+    fn typeck_binding(&self, ident: &Ident) -> TypeCkResult<()> {
+        match self.resolver.resolve_const(ident) {
+            Some(_) => Err(Diagnostic::error(format!(
+                "Cannot declare a variable named '{}', since a constant with that name exists",
+                ident.repr
+            ))
+            .with_span(ident.span.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn typeck_item_const(&mut self, item_const: &'a ItemConst) -> TypeCkResult<()> {
+        let i32 = Type::Primitive(String::from("i32"));
+
+        let expected = self.typeck_ty(&item_const.ty)?;
+        if expected != i32 {
+            return Err(Diagnostic::error(format!(
+                "Constant '{}' has type '{}', but only 'i32' constants are supported",
+                item_const.ident.repr, expected
+            ))
+            .with_span(item_const.ty.span().clone()));
+        }
+
+        let actual = self.typeck_expr(&item_const.expr)?;
+        if actual != expected {
+            return Err(Diagnostic::error(format!(
+                "The expression assigned to constant '{}' must have type '{}' but it actually has type '{}'",
+                item_const.ident.repr, expected, actual
+            ))
+            .with_span(item_const.expr.span().clone()));
+        }
+
+        match ConstFolder::new(&self.resolver.consts).fold_expr(&item_const.expr)? {
+            Some(_) => Ok(()),
+            None => Err(Diagnostic::error(format!(
+                "The value of constant '{}' can't be computed at compile time",
+                item_const.ident.repr
+            ))
+            .with_span(item_const.expr.span().clone())
+            .with_note(String::from(
+                "Constants may only use literals, arithmetic, and other constants that don't depend on them",
+            ))),
+        }
+    }
+
+    /// Resolves a type as written in the source, reporting the first unknown type inside it.
+    fn typeck_ty(&self, ty: &Ty) -> TypeCkResult<Type> {
+        match ty {
+            Ty::Named(ident) => {
+                return match self.resolver.resolve_ty(&ident.repr) {
+                    Some(ty) => Ok(ty),
+                    None => Err(self.suggest_ty(
+                        Diagnostic::error(format!("Unknown type '{}'", ident.repr))
+                            .with_span(ident.span.clone()),
+                        &ident.repr,
+                    )),
+                }
+            }
+
+            Ty::Tuple(ty_tuple) => {
+                for elem in &ty_tuple.elems {
+                    self.typeck_ty(elem)?;
+                }
+            }
+
+            Ty::Array(ty_array) => {
+                self.typeck_ty(&ty_array.elem)?;
+
+                if let Some(len) = &ty_array.len {
+                    match ConstFolder::new(&self.resolver.consts).fold_expr(len)? {
+                        Some(value) if value < 0 => {
+                            return Err(Diagnostic::error(format!(
+                                "The length of an array can't be negative, but it's {value}"
+                            ))
+                            .with_span(len.span().clone()))
+                        }
+
+                        Some(_) => {}
+
+                        None => {
+                            return Err(Diagnostic::error(String::from(
+                                "The length of an array must be computable at compile time",
+                            ))
+                            .with_span(len.span().clone())
+                            .with_note(String::from(
+                                "Array lengths may only use literals, arithmetic, and constants",
+                            )))
                         }
                     }
                 }
             }
 
-            None => {
-                self.result = Err(TypeCkError {
-                    reason: format!("Unknown type '{}'", item_fn.ty.ident.repr),
-                    span: Some(item_fn.ty.span.clone()),
-                })
+            Ty::Fn(ty_fn) => {
+                for param in &ty_fn.params {
+                    self.typeck_ty(param)?;
+                }
+
+                self.typeck_ty(&ty_fn.ret)?;
+            }
+
+            Ty::Ref(ty_ref) => {
+                self.typeck_ty(&ty_ref.ty)?;
             }
         }
+
+        // Every named type inside the type exists and every length is valid, so it must resolve
+        Ok(self.resolver.resolve(ty).unwrap())
     }
-}
 
-impl<'a> TypeCk<'a> {
+    /// Resolves the declared type of a struct field, reporting unknown types at the field's type.
+    fn typeck_field(&self, field: &TyField) -> TypeCkResult<Type> {
+        self.typeck_ty(&field.ty)
+    }
+
+    /// Computes the type of a block, which is the type of its tail expression. A block without one has type `()`,
+    /// unless it ends with a `return` statement, in which case it has the type of the returned value.
     fn typeck_block(&mut self, block: &'a Block) -> TypeCkResult<Type> {
         let mut result: Type = Type::Primitive(String::from("()"));
 
-        for (index, stmt) in block.stmts.iter().enumerate() {
-            // Throw away the result of typechecking every statement except the last one
-            let _ = self.typeck_stmt(stmt)?;
+        for stmt in &block.stmts {
+            let ty = self.typeck_stmt(stmt)?;
 
-            if index == block.stmts.len() - 1 {
-                // This is the return statement, and must be the type of the block
-                result = self.typeck_stmt(stmt)?;
-            }
+            result = match stmt {
+                Stmt::Return(_) => ty,
+                _ => Type::Primitive(String::from("()")),
+            };
+        }
+
+        if let Some(tail) = &block.tail {
+            // The tail expression is the value of the block
+            result = self.typeck_expr(tail)?;
         }
 
         Ok(result)
@@ -101,86 +597,472 @@ impl<'a> TypeCk<'a> {
     fn typeck_stmt(&mut self, stmt: &'a Stmt) -> TypeCkResult<Type> {
         match stmt {
             Stmt::Local(local) => {
+                self.typeck_binding(&local.ident)?;
+
                 // Type check the expression
                 let actual = self.typeck_expr(&local.expr)?;
-                let expected = self.resolver.resolve_ty(&local.ty.ident.repr);
-
-                match expected {
-                    Some(expected) => {
-                        if expected == actual {
-                            // This statement checks out
-                            self.resolver.table.insert(
-                                &local.ident.repr,
-                                Symbol::Local(Local { ty: actual.clone() }),
-                            );
-                            Ok(actual)
-                        } else {
-                            // The expected type doesn't match the actual type
-                            Err(TypeCkError {
-                                reason: format!("The expression assigned to variable '{}' must have type '{}' but it actually has type '{}'", local.ident.repr, expected, actual),
-                                span: Some(local.expr.span().clone())
-                            })
-                        }
-                    }
+                // The type assigned to this local variable must exist, and without one, the variable simply takes
+                // the type of the expression
+                let expected = match &local.ty {
+                    Some(ty) => self.typeck_ty(ty)?,
+                    None => actual.clone(),
+                };
+
+                if expected == actual {
+                    // This statement checks out
+                    self.locals.insert(
+                        &local.ident.repr,
+                        Symbol::Local(Local { ty: actual.clone() }),
+                    );
+                    Ok(actual)
+                } else {
+                    // The expected type doesn't match the actual type
+                    let diagnostic = Diagnostic::error(format!(
+                        "The expression assigned to variable '{}' must have type '{}' but it actually has type '{}'",
+                        local.ident.repr, expected, actual
+                    ))
+                    .with_span(local.expr.span().clone());
+
+                    Err(no_truthiness(diagnostic, &expected, &actual))
+                }
+            }
+
+            Stmt::Assign(assign) => {
+                let ident = &assign.ident;
 
+                // Only variables can be assigned to, and the new value must have the type they were declared with
+                let expected = match self.resolve_local(ident) {
+                    Some(ty) => ty,
                     None => {
-                        // The type assigned to this local variable doesn't exist
-                        Err(TypeCkError {
-                            reason: format!("The type '{}' doesn't exist", local.ty.ident.repr),
-                            span: Some(local.ty.ident.span.clone()),
-                        })
+                        let reason = match self.resolver.resolve_const(ident) {
+                            Some(_) => format!("Cannot assign to the constant '{}'", ident.repr),
+                            None => format!("Cannot find '{}' in this scope", ident.repr),
+                        };
+
+                        return Err(Diagnostic::error(reason).with_span(ident.span.clone()));
                     }
+                };
+
+                let actual = self.typeck_expr(&assign.expr)?;
+                if expected != actual {
+                    return Err(no_truthiness(
+                        Diagnostic::error(format!(
+                            "Cannot assign a value of type '{}' to variable '{}', which has type '{}'",
+                            actual, ident.repr, expected
+                        ))
+                        .with_span(assign.expr.span().clone()),
+                        &expected,
+                        &actual,
+                    ));
                 }
+
+                Ok(Type::Primitive(String::from("()")))
             }
 
             Stmt::Return(ret) => {
                 // Type check the returned expression
-                self.typeck_expr(&ret.expr)
+                let actual = match &ret.expr {
+                    Some(expr) => self.typeck_expr(expr)?,
+                    None => Type::Primitive(String::from("()")),
+                };
+
+                // Wherever it appears, the value must match the return type of the enclosing function
+                if let Some((expected, main)) = &self.ret {
+                    if !returns(expected, &actual, *main) {
+                        return Err(return_mismatch(expected, &actual, ret.span.clone()));
+                    }
+                }
+
+                Ok(actual)
+            }
+
+            Stmt::Break(brk) => {
+                let (ty, span) = match &brk.expr {
+                    Some(expr) => (self.typeck_expr(expr)?, expr.span().clone()),
+                    None => (Type::Primitive(String::from("()")), brk.span.clone()),
+                };
+
+                let label = &brk.label;
+                match self
+                    .labels
+                    .iter_mut()
+                    .rev()
+                    .find(|(name, _)| *name == label.name)
+                {
+                    Some((_, exits)) => exits.push((ty, span)),
+                    None => {
+                        return Err(Diagnostic::error(format!(
+                            "Cannot find label '{} in an enclosing block",
+                            label.name
+                        ))
+                        .with_span(label.span.clone()))
+                    }
+                }
+
+                Ok(Type::Primitive(String::from("()")))
+            }
+
+            // The value of an expression statement is thrown away, but the expression still has to make sense
+            Stmt::Expr(expr) => {
+                self.typeck_expr(expr)?;
+                Ok(Type::Primitive(String::from("()")))
             }
+        }
+    }
+
+    /// Computes the type of a block expression, whose locals are only visible inside it. Every value that exits the
+    /// block must have the same type, whether it's passed to `break` or produced by the tail expression. A block that
+    /// runs off its end without a tail expression produces `()`.
+    fn typeck_expr_block(&mut self, expr_block: &'a ExprBlock) -> TypeCkResult<Type> {
+        let block = &expr_block.block;
+
+        // Only labeled blocks can be exited with `break`
+        if let Some(label) = &expr_block.label {
+            self.labels.push((label.name, Vec::new()));
+        }
 
-            _ => todo!(),
+        self.locals.up();
+        let result = self.typeck_block(block);
+        self.locals.down();
+
+        let mut exits = match &expr_block.label {
+            Some(_) => self.labels.pop().unwrap().1,
+            None => Vec::new(),
+        };
+
+        let ty = result?;
+        match (&block.tail, block.stmts.last()) {
+            (Some(tail), _) => exits.push((ty, tail.span().clone())),
+            (None, Some(Stmt::Return(_) | Stmt::Break(_))) => {}
+            (None, _) => exits.push((ty, block.rc.span.clone().unwrap_or(block.span.clone()))),
         }
+
+        let Some((expected, _)) = exits.first().cloned() else {
+            return Ok(Type::Primitive(String::from("()")));
+        };
+
+        for (actual, span) in exits {
+            if actual != expected {
+                // An unlabeled block only has its tail, so any mismatch comes from a `break`
+                let label = expr_block.label.as_ref().unwrap();
+
+                return Err(Diagnostic::error(format!(
+                    "Block '{} must produce a value of type '{}' but this value has type '{}'",
+                    label.name, expected, actual
+                ))
+                .with_span(span));
+            }
+        }
+
+        Ok(expected)
     }
 
-    fn typeck_expr(&mut self, expr: &'a Expr) -> TypeCkResult<Type> {
-        match expr {
-            Expr::Binary(expr_bin) => self.typeck_expr_bin(expr_bin),
-            Expr::Call(expr_call) => self.typeck_expr_call(expr_call),
-            Expr::Ident(ident) => self.typeck_ident(ident),
-            Expr::Lit(expr_lit) => self.typeck_expr_lit(expr_lit),
-            Expr::Struct(expr_struct) => self.typeck_expr_struct(expr_struct),
+    /// Computes the type of a conditional, whose condition must be a `bool`. Both branches must have the same type,
+    /// unless one of them always exits early (with `return` or `break`), in which case the conditional has the type
+    /// of the other. Without an `else` branch, the `then` block must produce `()`.
+    fn typeck_expr_if(&mut self, expr_if: &'a ExprIf) -> TypeCkResult<Type> {
+        let unit = Type::Primitive(String::from("()"));
+        let bool = Type::Primitive(String::from("bool"));
+
+        let cond = self.typeck_expr(&expr_if.cond)?;
+        if cond != bool {
+            return Err(no_truthiness(
+                Diagnostic::error(format!(
+                    "The condition of an 'if' must have type 'bool' but it has type '{}'",
+                    cond
+                ))
+                .with_span(expr_if.cond.span().clone()),
+                &bool,
+                &cond,
+            ));
+        }
+
+        self.locals.up();
+        let then = self.typeck_block(&expr_if.then);
+        self.locals.down();
+
+        let then = then?;
+        let then_exits = exits_early(&expr_if.then);
+
+        let Some(els) = &expr_if.els else {
+            if then_exits || then == unit {
+                return Ok(unit);
+            }
+
+            return Err(Diagnostic::error(format!(
+                "An 'if' without an 'else' must produce '()' but this block produces '{}'",
+                then
+            ))
+            .with_span(block_value_span(&expr_if.then)));
+        };
+
+        let other = self.typeck_expr(els)?;
+
+        match (then_exits, expr_exits_early(els)) {
+            (_, true) => Ok(then),
+            (true, false) => Ok(other),
+            (false, false) if then == other => Ok(then),
+            (false, false) => Err(Diagnostic::error(format!(
+                "Both branches of an 'if' must have the same type, but the first has type '{}' and this one has type '{}'",
+                then, other
+            ))
+            .with_span(match &**els {
+                Expr::Block(expr_block) => block_value_span(&expr_block.block),
+                els => els.span().clone(),
+            })),
         }
     }
 
+    /// Computes the type of a `match`, whose scrutinee must be an `i32` since the only patterns are integer literals.
+    /// Literals can't cover every value, so there must be a wildcard arm. Like the branches of a conditional, every
+    /// arm must have the same type, apart from those that always exit early.
+    fn typeck_expr_match(&mut self, expr_match: &'a ExprMatch) -> TypeCkResult<Type> {
+        let i32 = Type::Primitive(String::from("i32"));
+        let unit = Type::Primitive(String::from("()"));
+
+        let scrutinee = self.typeck_expr(&expr_match.scrutinee)?;
+        if scrutinee != i32 {
+            return Err(Diagnostic::error(format!(
+                "Only values of type 'i32' can be matched, but this one has type '{}'",
+                scrutinee
+            ))
+            .with_span(expr_match.scrutinee.span().clone()));
+        }
+
+        if !expr_match
+            .arms
+            .iter()
+            .any(|arm| matches!(arm.pat, Pat::Wild(_)))
+        {
+            return Err(Diagnostic::error(String::from(
+                "This 'match' doesn't cover every value of type 'i32'",
+            ))
+            .with_span(expr_match.scrutinee.span().clone())
+            .with_note(String::from(
+                "Add a '_' arm to handle the values that no other arm matches",
+            )));
+        }
+
+        let mut first = None;
+
+        for arm in &expr_match.arms {
+            let ty = self.typeck_expr(&arm.body)?;
+
+            if expr_exits_early(&arm.body) {
+                continue;
+            }
+
+            match &first {
+                None => first = Some(ty),
+                Some(first) if *first == ty => {}
+                Some(first) => {
+                    return Err(Diagnostic::error(format!(
+                        "Every arm of a 'match' must have the same type, but the first has type '{}' and this one has type '{}'",
+                        first, ty
+                    ))
+                    .with_span(match &arm.body {
+                        Expr::Block(expr_block) => block_value_span(&expr_block.block),
+                        body => body.span().clone(),
+                    }))
+                }
+            }
+        }
+
+        // If every arm exits early, the `match` never produces a value
+        Ok(first.unwrap_or(unit))
+    }
+
+    fn typeck_expr(&mut self, expr: &'a Expr) -> TypeCkResult<Type> {
+        let ty = self.fold_expr(expr)?;
+        self.types.insert(expr, ty.clone());
+
+        Ok(ty)
+    }
+
     fn typeck_expr_lit(&mut self, expr_lit: &'a ExprLit) -> TypeCkResult<Type> {
         match expr_lit {
             ExprLit::Num(_) => Ok(Type::Primitive(String::from("i32"))), // Right now, all literal numbers are `i32` values
+            ExprLit::Bool(_) => Ok(Type::Primitive(String::from("bool"))),
         }
     }
 
     fn typeck_ident(&mut self, ident: &'a Ident) -> TypeCkResult<Type> {
-        match self.resolver.resolve_local(ident) {
+        match self
+            .resolve_local(ident)
+            .or_else(|| self.resolver.resolve_const(ident))
+        {
             Some(ty) => Ok(ty),
-            None => Err(TypeCkError {
-                reason: format!("Cannot find '{}' in this scope", ident.repr),
-                span: Some(ident.span.clone()),
-            }),
+            None => Err(
+                Diagnostic::error(format!("Cannot find '{}' in this scope", ident.repr))
+                    .with_span(ident.span.clone()),
+            ),
         }
     }
 
     fn typeck_expr_call(&mut self, expr_call: &'a ExprCall) -> TypeCkResult<Type> {
         match expr_call {
             ExprCall::Fn(call) => {
-                // First, we need to collect the function signature
+                // Problems inside the arguments (such as unknown identifiers) are more specific than anything about
+                // the call itself, so they're reported first
+                let args = self.typeck_args(&call.args)?;
+
+                // Now we need to collect the function signature
                 match self.resolver.resolve_fn(&call.ident) {
-                    Some(sig) => Ok(sig.return_type),
+                    Some(sig) => {
+                        check_args(&call.ident, &sig, &call.args, &args, &call.span)?;
+                        Ok(sig.return_type)
+                    }
+
+                    None => match self.resolver.resolve_ty(&call.ident.repr) {
+                        // A tuple struct is built by calling it, as if it were a function taking its fields in order
+                        Some(Type::Struct(strct)) if strct.is_tuple() => {
+                            let sig = self.constructor(&strct)?;
+                            check_args(&call.ident, &sig, &call.args, &args, &call.span)?;
+                            Ok(sig.return_type)
+                        }
+
+                        Some(Type::Struct(_)) => Err(Diagnostic::error(format!(
+                            "Struct '{}' has named fields, so it can't be called like a function",
+                            call.ident.repr
+                        ))
+                        .with_span(call.ident.span.clone())
+                        .with_note(format!(
+                            "Build it by naming its fields instead (for example, '{} {{ ... }}')",
+                            call.ident.repr
+                        ))),
+
+                        _ => Err(Diagnostic::error(format!(
+                            "Undefined function '{}'",
+                            call.ident.repr
+                        ))
+                        .with_span(call.ident.span.clone())),
+                    },
+                }
+            }
+        }
+    }
+
+    /// The signature of the function that builds a tuple struct, which takes every field in order and returns the
+    /// struct. Each parameter is named by the index of its field.
+    fn constructor(&self, strct: &TyStruct) -> TypeCkResult<Function> {
+        let mut params = Vec::new();
+
+        for index in 0..strct.fields.len() {
+            let name = index.to_string();
+            let ty = self.typeck_field(&strct.fields[&name])?;
+
+            params.push((Name::intern(&name), ty));
+        }
+
+        Ok(Function {
+            params,
+            return_type: Type::Struct(strct.clone()),
+        })
+    }
+
+    /// Compute the type of every argument in a list.
+    fn typeck_args(&mut self, args: &'a ArgList) -> TypeCkResult<Vec<Type>> {
+        args.args.iter().map(|arg| self.typeck_expr(arg)).collect()
+    }
+
+    fn typeck_expr_field(&mut self, expr_field: &'a ExprField) -> TypeCkResult<Type> {
+        let ty = self.typeck_expr(&expr_field.expr)?;
+        let ident = &expr_field.ident;
+
+        match &ty {
+            Type::Struct(strct) => match strct.fields.get(ident.repr.as_str()) {
+                Some(field) => self.typeck_field(field),
+                None => Err(Diagnostic::error(format!(
+                    "No field '{}' on type '{}'",
+                    ident.repr, ty
+                ))
+                .with_span(ident.span.clone())),
+            },
+
+            _ => Err(Diagnostic::error(format!("Type '{}' has no fields", ty))
+                .with_span(expr_field.expr.span().clone())),
+        }
+    }
+
+    fn typeck_expr_method_call(
+        &mut self,
+        expr_method_call: &'a ExprMethodCall,
+    ) -> TypeCkResult<Type> {
+        let ty = self.typeck_expr(&expr_method_call.receiver)?;
+        let ident = &expr_method_call.ident;
+
+        let args = self.typeck_args(&expr_method_call.args)?;
+
+        match self.resolver.resolve_method(&ty, ident) {
+            Some(method) => {
+                check_args(
+                    ident,
+                    &method,
+                    &expr_method_call.args,
+                    &args,
+                    &expr_method_call.span,
+                )?;
+                Ok(method.return_type)
+            }
+            None => Err(Diagnostic::error(format!(
+                "No method named '{}' found for type '{}'",
+                ident.repr, ty
+            ))
+            .with_span(ident.span.clone())),
+        }
+    }
+
+    fn typeck_expr_tuple(&mut self, expr_tuple: &'a ExprTuple) -> TypeCkResult<Type> {
+        if expr_tuple.elems.is_empty() {
+            // The empty tuple is the unit value
+            return Ok(Type::Primitive(String::from("()")));
+        }
+
+        let mut elems = Vec::new();
+        for elem in &expr_tuple.elems {
+            elems.push(self.typeck_expr(elem)?);
+        }
+
+        Ok(Type::Tuple(elems))
+    }
 
-                    None => Err(TypeCkError {
-                        reason: format!("Undefined function '{}'", call.ident.repr),
-                        span: Some(call.ident.span.clone()),
-                    }),
+    fn typeck_expr_tuple_index(
+        &mut self,
+        expr_tuple_index: &'a ExprTupleIndex,
+    ) -> TypeCkResult<Type> {
+        let ty = self.typeck_expr(&expr_tuple_index.expr)?;
+        let index = &expr_tuple_index.index;
+
+        match &ty {
+            Type::Tuple(elems) => match elems.get(index.value as usize) {
+                Some(elem) => Ok(elem.clone()),
+                None => Err(Diagnostic::error(format!(
+                    "Tuple index {} is out of range for type '{}'",
+                    index.value, ty
+                ))
+                .with_span(index.span.clone())
+                .with_note(format!(
+                    "The tuple has {} element(s), so the largest index is {}",
+                    elems.len(),
+                    elems.len() - 1
+                ))),
+            },
+
+            // The fields of a tuple struct are accessed by index, just like the elements of a tuple
+            Type::Struct(strct) if strct.is_tuple() => {
+                match strct.fields.get(&index.value.to_string()) {
+                    Some(field) => self.typeck_field(field),
+                    None => Err(Diagnostic::error(format!(
+                        "Tuple index {} is out of range for type '{}'",
+                        index.value, ty
+                    ))
+                    .with_span(index.span.clone())
+                    .with_note(format!("The struct has {} field(s)", strct.fields.len()))),
                 }
             }
+
+            _ => Err(Diagnostic::error(format!("Type '{}' is not a tuple", ty))
+                .with_span(expr_tuple_index.expr.span().clone())),
         }
     }
 
@@ -189,67 +1071,481 @@ impl<'a> TypeCk<'a> {
         let lhs = self.typeck_expr(&expr_bin.lhs)?;
         let rhs = self.typeck_expr(&expr_bin.rhs)?;
 
+        if lhs == rhs && lhs != Type::Primitive(String::from("i32")) {
+            // Arithmetic only works on integers, even though values of other types can be compared
+            return Err(Diagnostic::error(format!(
+                "Cannot apply binary operator '{}' to values of type '{}'",
+                expr_bin.op.kind, lhs
+            ))
+            .with_span(expr_bin.span.clone()));
+        }
+
         if lhs == rhs {
-            // We're good!
+            // Constant arithmetic that overflows is rejected rather than silently wrapping
+            ConstFolder::new(&self.resolver.consts).fold_expr_bin(expr_bin)?;
+
             Ok(lhs)
         } else {
             // The type of the lhs doesn't match the rhs
-            Err(TypeCkError {
-                reason: format!("Left hand side of binary expression has type '{}' but the right hand side has type '{}'", lhs, rhs),
-                span: Some(expr_bin.rhs.span().clone())
-            })
+            Err(Diagnostic::error(format!(
+                "Left hand side of binary expression has type '{}' but the right hand side has type '{}'",
+                lhs, rhs
+            ))
+            .with_span(expr_bin.rhs.span().clone()))
+        }
+    }
+
+    /// Computes the type of a comparison, which is always `bool`. Both sides must have the same type, and values
+    /// of that type must be comparable.
+    fn typeck_expr_cmp(&mut self, expr_cmp: &'a ExprCmp) -> TypeCkResult<Type> {
+        let lhs = self.typeck_expr(&expr_cmp.lhs)?;
+        let rhs = self.typeck_expr(&expr_cmp.rhs)?;
+
+        if lhs != rhs {
+            return Err(Diagnostic::error(format!(
+                "Cannot compare a value of type '{}' with a value of type '{}'",
+                lhs, rhs
+            ))
+            .with_span(expr_cmp.rhs.span().clone()));
+        }
+
+        let i32 = Type::Primitive(String::from("i32"));
+        if expr_cmp.op.kind.is_ordering() && lhs != i32 {
+            return Err(Diagnostic::error(format!(
+                "Values of type '{}' can't be ordered with '{}'",
+                lhs, expr_cmp.op.kind
+            ))
+            .with_span(expr_cmp.span.clone())
+            .with_note(String::from("Only integers can be ordered")));
+        }
+
+        if !self.comparable(&lhs, &mut Vec::new()) {
+            return Err(Diagnostic::error(format!(
+                "Values of type '{}' can't be compared with '{}'",
+                lhs, expr_cmp.op.kind
+            ))
+            .with_span(expr_cmp.span.clone())
+            .with_note(String::from(
+                "Only primitives, references to them, and tuples and structs made up of them can be compared",
+            )));
+        }
+
+        Ok(Type::Primitive(String::from("bool")))
+    }
+
+    /// Whether values of a type can be compared for equality. Structs are compared field by field, so every field
+    /// must be comparable too. Structs already being checked (listed in `seen`) are assumed to be comparable, so that
+    /// a struct that contains itself doesn't send the check around in circles.
+    fn comparable(&self, ty: &Type, seen: &mut Vec<String>) -> bool {
+        match ty {
+            Type::Primitive(_) => true,
+            Type::Tuple(elems) => elems.iter().all(|elem| self.comparable(elem, seen)),
+            Type::Ref(ty) => self.comparable(ty, seen),
+            Type::Struct(strct) => {
+                let name = ty.to_string();
+                if seen.contains(&name) {
+                    return true;
+                }
+
+                seen.push(name);
+                strct.fields.values().all(|field| {
+                    self.resolver
+                        .resolve(&field.ty)
+                        .is_some_and(|ty| self.comparable(&ty, seen))
+                })
+            }
+            Type::Array(..) | Type::Fn(..) => false,
         }
     }
 
+    fn typeck_expr_unary(&mut self, expr_unary: &'a ExprUnary) -> TypeCkResult<Type> {
+        let ty = self.typeck_expr(&expr_unary.expr)?;
+
+        // Negation works on integers and `!` works on booleans, and both produce a value of their operand's type
+        let expected = match expr_unary.op.kind {
+            UnaryOpKind::Negate => Type::Primitive(String::from("i32")),
+            UnaryOpKind::Not => Type::Primitive(String::from("bool")),
+        };
+
+        if ty != expected {
+            let diagnostic = Diagnostic::error(format!(
+                "Cannot apply unary operator '{}' to a value of type '{}'",
+                expr_unary.op.kind, ty
+            ))
+            .with_span(expr_unary.expr.span().clone());
+
+            return Err(no_truthiness(diagnostic, &expected, &ty));
+        }
+
+        // Negating `i32::MIN` overflows, so it's rejected just like overflowing binary arithmetic
+        ConstFolder::new(&self.resolver.consts).fold_expr_unary(expr_unary)?;
+
+        Ok(ty)
+    }
+
     fn typeck_expr_struct(&mut self, expr_struct: &'a ExprStruct) -> TypeCkResult<Type> {
         // Make sure that every field is provided an expression with the correct type
         match self.resolver.resolve_ty(&expr_struct.ident.repr) {
             Some(ty) => {
                 match &ty {
-                    Type::Primitive(repr) => Err(TypeCkError {
-                        reason: format!("The type '{}' is not a struct", repr),
-                        span: Some(expr_struct.ident.span.clone()),
-                    }),
-
                     Type::Struct(strct) => {
                         for arg in &expr_struct.args.args {
                             // Does this argument exist in this struct?
-                            if !strct.fields.contains_key(&arg.ident.repr) {
-                                return Err(TypeCkError {
-                                    reason: format!(
-                                        "Struct '{}' has no field '{}'",
-                                        expr_struct.ident.repr, arg.ident.repr
-                                    ),
-                                    span: Some(expr_struct.ident.span.clone()),
-                                });
+                            if !strct.fields.contains_key(arg.ident.repr.as_str()) {
+                                return Err(Diagnostic::error(format!(
+                                    "Struct '{}' has no field '{}'",
+                                    expr_struct.ident.repr, arg.ident.repr
+                                ))
+                                .with_span(expr_struct.ident.span.clone()));
                             } else {
                                 // Does the provided expression have the correct type?
                                 let e = &arg.expr;
 
-                                let expected_ty = self
-                                    .resolver
-                                    .resolve_ty(&strct.fields[&arg.ident.repr])
-                                    .unwrap();
-                                let actual_ty = self.typeck_expr(&e)?;
+                                let field = &strct.fields[arg.ident.repr.as_str()];
+                                let expected_ty = self.typeck_field(field)?;
+                                let actual_ty = self.typeck_expr(e)?;
 
                                 if expected_ty != actual_ty {
-                                    return Err(TypeCkError {
-                                        reason: format!("Field '{}' of '{}' must have type '{}', but an expression of type '{}' was provided", arg.ident.repr, expr_struct.ident.repr, expected_ty, actual_ty),
-                                        span: Some(expr_struct.ident.span.clone()),
-                                    });
+                                    return Err(Diagnostic::error(format!(
+                                        "Field '{}' of '{}' must have type '{}', but an expression of type '{}' was provided",
+                                        arg.ident.repr, expr_struct.ident.repr, expected_ty, actual_ty
+                                    ))
+                                    .with_span(e.span().clone())
+                                    .with_note(format!(
+                                        "Field '{}' is declared with type '{}' on line {}",
+                                        arg.ident.repr,
+                                        field.ty,
+                                        field.ty.span().start.line
+                                    )));
                                 }
                             }
                         }
 
                         Ok(ty)
                     }
+
+                    _ => Err(
+                        Diagnostic::error(format!("The type '{}' is not a struct", ty))
+                            .with_span(expr_struct.ident.span.clone()),
+                    ),
                 }
             }
 
-            None => Err(TypeCkError {
-                reason: format!("The type '{}' doesn't exist", expr_struct.ident.repr),
-                span: Some(expr_struct.ident.span.clone()),
-            }),
+            None => Err(self.suggest_ty(
+                Diagnostic::error(format!(
+                    "The type '{}' doesn't exist",
+                    expr_struct.ident.repr
+                ))
+                .with_span(expr_struct.ident.span.clone()),
+                &expr_struct.ident.repr,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    /// Type check an entire program, which must parse.
+    fn typeck(src: &str) -> TypeCkResult<()> {
+        let file = match parse_str(src) {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+
+        TypeCk::new(&resolver).run(&file).map(|_| ())
+    }
+
+    /// Type check a program that should be rejected, returning the reason.
+    fn reason(src: &str) -> String {
+        match typeck(src) {
+            Ok(()) => panic!("the program should be rejected"),
+            Err(err) => err.reason,
+        }
+    }
+
+    /// Type check a program that should be rejected, returning where the error starts and ends as `(line, column)`
+    /// pairs.
+    fn bounds(src: &str) -> ((usize, usize), (usize, usize)) {
+        match typeck(src) {
+            Ok(()) => panic!("the program should be rejected"),
+            Err(err) => {
+                let span = err.span.expect("the error should have a span");
+                (
+                    (span.start.line, span.start.column),
+                    (span.end.line, span.end.column),
+                )
+            }
+        }
+    }
+
+    /// Type check a program that should be rejected, returning the suggestions attached to the error.
+    fn suggestions(src: &str) -> Vec<String> {
+        match typeck(src) {
+            Ok(()) => panic!("the program should be rejected"),
+            Err(err) => err.suggestions,
+        }
+    }
+
+    const AREA: &str = "
+        struct Rect { w: i32, h: i32 }
+
+        impl Rect {
+            fn width(self) -> i32 { 3 }
+            fn height(self) -> i32 { 4 }
+            fn area(self) -> i32 { self.width() * self.height() }
+        }
+
+        fn main() -> i32 { 0 }
+    ";
+
+    #[test]
+    fn methods_call_sibling_methods_on_self() {
+        assert!(typeck(AREA).is_ok());
+    }
+
+    #[test]
+    fn one_resolver_can_check_a_program_repeatedly() {
+        let file = match parse_str("fn f() -> i32 { let x = 1; x }\nfn main() -> i32 { x }") {
+            Ok(file) => file,
+            Err(_) => panic!("the program should parse"),
+        };
+
+        let mut resolver = Resolver::new(&file);
+        resolver.collect_tys();
+        resolver.collect_functions();
+        let symbols = resolver.dump();
+
+        // The local `x` of `f` must not leak into `main`, even once the first check has seen it
+        for _ in 0..2 {
+            match TypeCk::new(&resolver).run(&file) {
+                Ok(_) => panic!("the program should be rejected"),
+                Err(err) => assert_eq!(err.reason, "Cannot find 'x' in this scope"),
+            }
+        }
+
+        assert_eq!(resolver.dump(), symbols);
+    }
+
+    #[test]
+    fn params_are_bound_in_the_body() {
+        assert!(
+            typeck("fn add(x: i32, y: i32) -> i32 { x + y }\nfn main() -> i32 { add(1, 2) }")
+                .is_ok()
+        );
+        assert!(typeck("struct P { x: i32 }\nimpl P { fn plus(self, k: i32) -> i32 { k } }\nfn main() -> i32 { 0 }").is_ok());
+    }
+
+    #[test]
+    fn params_are_local_to_their_function() {
+        let reason = reason("fn f(x: i32) -> i32 { x }\nfn main() -> i32 { x }");
+        assert_eq!(reason, "Cannot find 'x' in this scope");
+    }
+
+    #[test]
+    fn params_must_have_known_types() {
+        assert_eq!(
+            reason("fn f(x: Foo) -> i32 { 1 }\nfn main() -> i32 { 0 }"),
+            "Unknown type 'Foo'"
+        );
+    }
+
+    #[test]
+    fn unknown_types_suggest_the_closest_known_one() {
+        assert_eq!(
+            suggestions("fn main() -> i32 { let x: i23 = 1; 0 }"),
+            ["did you mean 'i32'?"]
+        );
+        assert!(suggestions("fn main() -> i32 { let x: Spaceship = 1; 0 }").is_empty());
+    }
+
+    #[test]
+    fn only_integers_can_be_ordered() {
+        assert!(typeck("fn main() -> i32 { if 1 < 2 { 1 } else { 0 } }").is_ok());
+        assert_eq!(
+            reason("fn main() -> i32 { let a = (1 == 1) < (2 == 2); 0 }"),
+            "Values of type 'bool' can't be ordered with '<'"
+        );
+    }
+
+    #[test]
+    fn only_main_may_run_off_its_end_instead_of_returning_i32() {
+        assert!(typeck("fn main() -> i32 {}").is_ok());
+        assert!(typeck("fn main() -> i32 { return; }").is_ok());
+
+        assert_eq!(
+            reason("fn f() -> i32 {}\nfn main() -> i32 { f() }"),
+            "Function must return type 'i32' but type '()' is returned instead"
+        );
+    }
+
+    #[test]
+    fn boolean_literals_have_type_bool() {
+        assert!(typeck("fn main() -> i32 { let b: bool = true; let c = false; 0 }").is_ok());
+        assert_eq!(
+            reason("fn main() -> i32 { let b: i32 = false; 0 }"),
+            "The expression assigned to variable 'b' must have type 'i32' but it actually has type 'bool'"
+        );
+    }
+
+    #[test]
+    fn a_function_body_has_the_type_of_its_tail() {
+        assert!(typeck("fn f() -> i32 { 1 + 2 }\nfn main() -> i32 { f() }").is_ok());
+
+        let src = "fn f() -> i32 { true }\nfn main() -> i32 { f() }";
+        assert_eq!(
+            reason(src),
+            "Function must return type 'i32' but type 'bool' is returned instead"
+        );
+
+        // The error points at the tail, not at the return type
+        assert_eq!(bounds(src), ((1, 17), (1, 20)));
+    }
+
+    #[test]
+    fn argument_errors_name_the_position_and_the_parameter() {
+        let src = "fn f(x: i32, y: i32) -> i32 { x + y }\nfn main() -> i32 { f(1, true) }";
+
+        assert_eq!(
+            reason(src),
+            "Argument 2 ('y') of 'f' expects type 'i32' but found 'bool'"
+        );
+        // The error points at the offending argument
+        assert_eq!(bounds(src), ((2, 25), (2, 28)));
+    }
+
+    #[test]
+    fn assertions_take_booleans() {
+        assert!(typeck("fn main() -> i32 { assert(1 == 1); 0 }").is_ok());
+        assert_eq!(
+            reason("fn main() -> i32 { assert(1); 0 }"),
+            "Argument 1 ('condition') of 'assert' expects type 'bool' but found 'i32'"
+        );
+    }
+
+    #[test]
+    fn doubled_unary_operators_keep_their_operand_type() {
+        assert!(typeck("fn main() -> i32 { let b: bool = !!true; - - 5 }").is_ok());
+        assert_eq!(
+            reason("fn main() -> i32 { let b = !5; 0 }"),
+            "Cannot apply unary operator '!' to a value of type 'i32'"
+        );
+        assert_eq!(
+            reason("fn main() -> i32 { - - true }"),
+            "Cannot apply unary operator '-' to a value of type 'bool'"
+        );
+    }
+
+    #[test]
+    fn a_mismatched_return_points_at_the_return_statement() {
+        let src = "fn main() -> () { return 5; }";
+        assert_eq!(
+            reason(src),
+            "Function must return type '()' but type 'i32' is returned instead"
+        );
+        assert_eq!(bounds(src), ((1, 19), (1, 26)));
+    }
+
+    #[test]
+    fn nested_returns_must_match_the_return_type() {
+        let src = "fn main() -> i32 { if true { return true; } 3 }";
+        assert_eq!(
+            reason(src),
+            "Function must return type 'i32' but type 'bool' is returned instead"
+        );
+        assert_eq!(bounds(src), ((1, 30), (1, 40)));
+
+        assert_eq!(
+            reason("fn main() -> () { 'a: { return 5; } }"),
+            "Function must return type '()' but type 'i32' is returned instead"
+        );
+
+        // A nested `return;` in an `i32` main still exits with zero
+        assert!(typeck("fn main() -> i32 { if true { return; } 3 }").is_ok());
+        assert!(typeck(
+            "fn f() -> i32 { 'a: { if true { return 1; } } 2 }\nfn main() -> i32 { f() }"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn tuple_structs_are_built_by_calling_them() {
+        let pair = "struct Pair(i32, bool);\n";
+
+        assert!(typeck(&format!(
+            "{pair}fn main() -> i32 {{ let p = Pair(1, true); p.0 }}"
+        ))
+        .is_ok());
+        assert_eq!(
+            reason(&format!("{pair}fn main() -> i32 {{ Pair(1, 2).0 }}")),
+            "Argument 2 ('1') of 'Pair' expects type 'bool' but found 'i32'"
+        );
+        assert_eq!(
+            reason(&format!("{pair}fn main() -> i32 {{ Pair(1, true).2 }}")),
+            "Tuple index 2 is out of range for type 'Pair'"
+        );
+        assert_eq!(
+            reason("struct S { a: i32 }\nfn main() -> i32 { S(1).a }"),
+            "Struct 'S' has named fields, so it can't be called like a function"
+        );
+    }
+
+    #[test]
+    fn impl_blocks_must_target_a_declared_struct() {
+        assert!(
+            typeck("struct S {}\nimpl S { fn f(self) -> i32 { 1 } }\nfn main() -> i32 { 0 }")
+                .is_ok()
+        );
+        assert_eq!(
+            reason("impl Bogus {}\nfn main() -> i32 { 0 }"),
+            "Cannot implement unknown type 'Bogus'"
+        );
+        assert_eq!(
+            reason("impl i32 {}\nfn main() -> i32 { 0 }"),
+            "Cannot implement the primitive type 'i32'"
+        );
+    }
+
+    #[test]
+    fn array_lengths_can_name_constants() {
+        let fun = |len: i32, ret: &str| {
+            format!(
+                "const N: i32 = {len};\nfn f(a: [i32; N]) -> {ret} {{ a }}\nfn main() -> i32 {{ 0 }}"
+            )
+        };
+
+        assert!(typeck(&fun(3, "[i32; 3]")).is_ok());
+        assert_eq!(
+            reason(&fun(3, "[i32; 4]")),
+            "Function must return type '[i32; 4]' but type '[i32; 3]' is returned instead"
+        );
+        assert_eq!(
+            reason(&fun(-3, "i32")),
+            "The length of an array can't be negative, but it's -3"
+        );
+    }
+
+    #[test]
+    fn field_type_errors_point_at_the_field_type() {
+        let src = "struct S { x: i32, y: Foo }\nfn main() -> i32 { let s = S { x: 1, y: 2 }; 0 }";
+        assert_eq!(reason(src), "Unknown type 'Foo'");
+        assert_eq!(bounds(src), ((1, 23), (1, 25)));
+
+        // A value of the wrong type is reported where it's provided, with a note saying where the field is declared
+        let src = "struct S { x: i32 }\nfn main() -> i32 { let s = S { x: true }; 0 }";
+        match typeck(src) {
+            Ok(()) => panic!("the program should be rejected"),
+            Err(err) => assert_eq!(
+                err.notes,
+                ["Field 'x' is declared with type 'i32' on line 1"]
+            ),
         }
+        assert_eq!(bounds(src), ((2, 35), (2, 38)));
     }
 }
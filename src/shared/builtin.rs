@@ -0,0 +1,102 @@
+/// Represents a function provided by the compiler rather than defined in the program. Builtins only deal in
+/// primitive types, so their signatures are written as type names, and booleans are passed to them as one or zero.
+pub struct Builtin {
+    /// The name the builtin is called by.
+    pub name: &'static str,
+
+    /// The name and type of every parameter.
+    pub params: &'static [(&'static str, &'static str)],
+
+    /// The type of the result.
+    pub ret: &'static str,
+
+    /// Computes the result from the arguments. A builtin returning `()` produces `None`, and one that stops the
+    /// program produces the reason why.
+    pub eval: fn(&[i32]) -> Result<Option<i32>, Halt>,
+}
+
+/// Describes why a builtin stopped the program instead of producing a result.
+pub enum Halt {
+    /// The program trapped, for the given reason.
+    Trap(String),
+
+    /// The program asked to exit immediately, with the given exit code.
+    Exit(i32),
+}
+
+/// The prelude, which lists every builtin function. Adding a builtin only takes a new entry here: the resolver
+/// registers every entry before the functions in the program, the interpreter calls `eval` to run it, and lowering
+/// gives it a label after the functions in the program. Functions in the program may not share a name with a builtin.
+pub const PRELUDE: &[Builtin] = &[
+    Builtin {
+        name: "print",
+        params: &[("value", "i32")],
+        ret: "()",
+        eval: |args| {
+            println!("{}", args[0]);
+            Ok(None)
+        },
+    },
+    Builtin {
+        name: "assert",
+        params: &[("condition", "bool")],
+        ret: "()",
+        eval: |args| match args[0] {
+            0 => Err(Halt::Trap(String::from("Assertion failed"))),
+            _ => Ok(None),
+        },
+    },
+    Builtin {
+        name: "saturating_add",
+        params: &[("lhs", "i32"), ("rhs", "i32")],
+        ret: "i32",
+        eval: |args| Ok(Some(args[0].saturating_add(args[1]))),
+    },
+    Builtin {
+        name: "checked_add",
+        params: &[("lhs", "i32"), ("rhs", "i32")],
+        ret: "i32",
+        eval: |args| match args[0].checked_add(args[1]) {
+            Some(value) => Ok(Some(value)),
+            None => Err(Halt::Trap(String::from(
+                "Arithmetic overflow in 'checked_add'",
+            ))),
+        },
+    },
+    Builtin {
+        name: "checked_mul",
+        params: &[("lhs", "i32"), ("rhs", "i32")],
+        ret: "i32",
+        eval: |args| match args[0].checked_mul(args[1]) {
+            Some(value) => Ok(Some(value)),
+            None => Err(Halt::Trap(String::from(
+                "Arithmetic overflow in 'checked_mul'",
+            ))),
+        },
+    },
+    Builtin {
+        name: "min",
+        params: &[("lhs", "i32"), ("rhs", "i32")],
+        ret: "i32",
+        eval: |args| Ok(Some(args[0].min(args[1]))),
+    },
+    Builtin {
+        name: "max",
+        params: &[("lhs", "i32"), ("rhs", "i32")],
+        ret: "i32",
+        eval: |args| Ok(Some(args[0].max(args[1]))),
+    },
+    Builtin {
+        name: "exit",
+        params: &[("code", "i32")],
+        ret: "()",
+        eval: |args| Err(Halt::Exit(args[0])),
+    },
+];
+
+impl Builtin {
+    /// Find the builtin with the given name.
+    pub fn find(name: &str) -> Option<&'static Builtin> {
+        PRELUDE.iter().find(|builtin| builtin.name == name)
+    }
+}
@@ -0,0 +1,55 @@
+use super::Span;
+
+/// Represents an error reported to the user, along with any notes and suggestions that help explain it.
+///
+/// Diagnostics are built fluently, for example...
+///
+/// ```ignore
+/// Diagnostic::error("Unknown type 'i23'")
+///     .with_span(span)
+///     .with_suggestion("did you mean 'i32'?")
+/// ```
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// The cause of this error.
+    pub reason: String,
+
+    /// The (optional) span of this error.
+    pub span: Option<Span>,
+
+    /// Additional context, printed beneath the primary span.
+    pub notes: Vec<String>,
+
+    /// Suggested fixes, printed beneath the notes.
+    pub suggestions: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Create a new error diagnostic without a span.
+    pub fn error<S: Into<String>>(reason: S) -> Self {
+        Diagnostic {
+            reason: reason.into(),
+            span: None,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Set the primary span of this diagnostic.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Add a note to this diagnostic.
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Add a suggestion to this diagnostic.
+    pub fn with_suggestion<S: Into<String>>(mut self, suggestion: S) -> Self {
+        self.suggestions.push(suggestion.into());
+        self
+    }
+}
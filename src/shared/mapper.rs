@@ -1,4 +1,4 @@
-use super::{table::SymbolTable, Index};
+use super::{Index, SymbolTable};
 
 /// Maps variable and function identifiers to unique indicies.
 pub struct Mapper<'a> {
@@ -35,12 +35,34 @@ impl<'a> Mapper<'a> {
 
     /// Add one table to the stack of symbol tables.
     pub fn up(&mut self) {
-        let prev = self.table.clone();
-        self.table = SymbolTable::new().with_previous(prev);
+        self.table.up();
     }
 
-    /// Pop one table from the stack of symbol tables.
+    /// Pop one table from the stack of symbol tables. Indices are never reused, so names inserted afterwards still
+    /// get indices of their own.
     pub fn down(&mut self) {
-        self.table = *self.table.previous.clone().unwrap();
+        self.table.down();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_unique_across_scopes() {
+        let mut mapper = Mapper::new();
+        let x = mapper.insert("x");
+
+        mapper.up();
+        let inner = mapper.insert("x");
+        assert_ne!(inner, x);
+        assert_eq!(mapper.find("x"), inner);
+
+        mapper.down();
+        assert_eq!(mapper.find("x"), x);
+
+        let y = mapper.insert("y");
+        assert!(y != x && y != inner);
     }
 }
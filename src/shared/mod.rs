@@ -1,12 +1,24 @@
 #![allow(unused_imports)]
 
+mod builtin;
+mod diagnostic;
 mod map;
+mod mapper;
+mod name;
 mod pool;
+mod source;
 mod span;
+mod table;
 
+pub use builtin::*;
+pub use diagnostic::*;
 pub use map::*;
+pub use mapper::*;
+pub use name::*;
 pub use pool::*;
+pub use source::*;
 pub use span::*;
+pub use table::*;
 
 /// Serves as an index for many data structures throughout the compiler.
 pub type Index = usize;
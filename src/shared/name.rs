@@ -0,0 +1,90 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Display};
+use std::ops::Deref;
+
+use super::{Index, Pool};
+
+thread_local! {
+    /// Every name interned so far. Names live for as long as the compiler runs, so their text is leaked rather than
+    /// reference counted, which lets `Name::as_str` hand out `'static` slices.
+    static NAMES: RefCell<Pool<&'static str>> = RefCell::new(Pool::new());
+}
+
+/// Represents an interned identifier. Each distinct identifier is stored exactly once, so copying a name never
+/// allocates and comparing two names only compares their indices.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Name(Index);
+
+impl Name {
+    /// Intern `text` (if it hasn't been interned yet) and return its name.
+    pub fn intern(text: &str) -> Self {
+        NAMES.with(|names| {
+            let mut names = names.borrow_mut();
+
+            match names.index_of(&text) {
+                Some(index) => Name(*index),
+                None => Name(names.insert(Box::leak(text.into()))),
+            }
+        })
+    }
+
+    /// Return the text of this name.
+    pub fn as_str(&self) -> &'static str {
+        NAMES.with(|names| *names.borrow().value_of(self.0).unwrap())
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_text_is_interned_once() {
+        let first = Name::intern("scooter");
+        let second = Name::intern(&String::from("scooter"));
+
+        assert!(first == second);
+        assert_eq!(first.as_str(), "scooter");
+
+        // Both names share the leaked text, instead of each owning a copy
+        assert!(std::ptr::eq(first.as_str(), second.as_str()));
+    }
+
+    #[test]
+    fn different_text_gives_different_names() {
+        assert!(Name::intern("left") != Name::intern("right"));
+        assert!(Name::intern("left") == "left");
+    }
+}
@@ -0,0 +1,80 @@
+/// Holds the text of every source file in the program. Lines are numbered across all the files one after another,
+/// so the first line of the second file comes right after the last line of the first. This way, every span points
+/// at exactly one file without having to record which one.
+pub struct SourceMap {
+    /// Every line of every file, in order.
+    lines: Vec<String>,
+
+    /// Every file, in the order they were added.
+    files: Vec<SourceFile>,
+
+    /// How many columns the lexer counted each tab as.
+    tab_width: usize,
+}
+
+/// Represents a single source file.
+struct SourceFile {
+    /// The path the file was read from.
+    path: String,
+
+    /// The number of the first line of this file, counted across all files.
+    first_line: usize,
+}
+
+impl SourceMap {
+    /// Create an empty source map.
+    pub fn new() -> Self {
+        SourceMap {
+            lines: Vec::new(),
+            files: Vec::new(),
+            tab_width: 1,
+        }
+    }
+
+    /// Expect spans to count a tab as `width` columns, like a lexer created with the same width.
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Add a file, returning the number of its first line.
+    pub fn add(&mut self, path: &str, text: &str) -> usize {
+        let first_line = self.lines.len() + 1;
+
+        self.files.push(SourceFile {
+            path: path.to_owned(),
+            first_line,
+        });
+
+        // Lines ending in `\r\n` are stored without the `\r`, just like the lexer treats it as part of the line break
+        self.lines.extend(
+            text.split('\n')
+                .map(|line| line.strip_suffix('\r').unwrap_or(line).to_owned()),
+        );
+        first_line
+    }
+
+    /// Return the text of a line.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.lines.get(line.checked_sub(1)?).map(String::as_str)
+    }
+
+    /// Return the path of the file a line belongs to, along with the number of the line within that file.
+    pub fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        self.files
+            .iter()
+            .rev()
+            .find(|file| file.first_line <= line)
+            .map(|file| (file.path.as_str(), line - file.first_line + 1))
+    }
+
+    /// Return how many columns a tab counts as.
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Return the number of files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+}
@@ -23,8 +23,8 @@ impl Span {
     }
 }
 
-/// Represents a location in the source code.
-#[derive(Debug, Clone)]
+/// Represents a location in the source code. Locations are ordered by line first, and then by column.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Location {
     /// Line of this location (starting at one).
     pub line: usize,
@@ -0,0 +1,133 @@
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct SymbolTable<'a, T: Clone> {
+    pub previous: Option<Box<SymbolTable<'a, T>>>,
+    pub symbols: HashMap<&'a str, T>,
+}
+
+#[allow(dead_code)]
+impl<'a, T: Clone> SymbolTable<'a, T> {
+    pub fn new() -> Self {
+        SymbolTable {
+            previous: None,
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn with_previous(mut self, previous: SymbolTable<'a, T>) -> Self {
+        self.previous = Some(Box::new(previous));
+        self
+    }
+
+    pub fn find(&self, symbol: &str) -> Option<T> {
+        self.symbols
+            .get(symbol)
+            .cloned()
+            .or_else(|| self.previous.as_ref().and_then(|prev| prev.find(symbol)))
+    }
+
+    pub fn insert(&mut self, symbol: &'a str, value: T) {
+        self.symbols.insert(symbol, value);
+    }
+
+    /// Replace the value of `symbol` in the innermost scope that defines it. Returns `false` if no scope does.
+    pub fn update(&mut self, symbol: &str, value: T) -> bool {
+        match self.symbols.get_mut(symbol) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => match &mut self.previous {
+                Some(previous) => previous.update(symbol, value),
+                None => false,
+            },
+        }
+    }
+
+    /// Iterate over the symbols defined in the current scope, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &T)> {
+        self.symbols.iter().map(|(symbol, value)| (*symbol, value))
+    }
+
+    /// Iterate over every visible symbol, walking out through the enclosing scopes. A symbol shadowed by an inner
+    /// scope is only yielded once, together with its innermost value.
+    pub fn iter_all(&self) -> impl Iterator<Item = (&'a str, &T)> {
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        let mut scope = Some(self);
+
+        while let Some(table) = scope {
+            for (symbol, value) in table.iter() {
+                if seen.insert(symbol) {
+                    result.push((symbol, value));
+                }
+            }
+
+            scope = table.previous.as_deref();
+        }
+
+        result.into_iter()
+    }
+
+    /// Enter a new scope, nested inside the current one.
+    pub fn up(&mut self) {
+        let previous = std::mem::replace(self, SymbolTable::new());
+        self.previous = Some(Box::new(previous));
+    }
+
+    /// Leave the current scope, dropping every symbol defined in it.
+    pub fn down(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            *self = *previous;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inner_scopes_shadow_outer_ones() {
+        let mut table = SymbolTable::new();
+        table.insert("x", 1);
+        table.insert("y", 2);
+
+        table.up();
+        table.insert("x", 3);
+
+        assert_eq!(table.iter().collect::<Vec<_>>(), [("x", &3)]);
+
+        let mut all = table.iter_all().collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, [("x", &3), ("y", &2)]);
+
+        table.down();
+        assert_eq!(table.find("x"), Some(1));
+    }
+
+    #[test]
+    fn leaving_a_scope_drops_its_symbols() {
+        let mut table = SymbolTable::new();
+        table.insert("x", 1);
+
+        table.up();
+        table.insert("y", 2);
+        assert!(table.update("x", 3));
+        assert_eq!(table.find("y"), Some(2));
+
+        // Updates reach the scope that defines the symbol, so they outlive the inner scope
+        table.down();
+        assert_eq!(table.find("y"), None);
+        assert_eq!(table.find("x"), Some(3));
+
+        // Leaving the outermost scope keeps it
+        table.down();
+        assert_eq!(table.find("x"), Some(3));
+    }
+}
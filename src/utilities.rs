@@ -1,23 +1,390 @@
-use crate::shared::Span;
-use colored::Colorize;
+use crate::shared::{Diagnostic, SourceMap, Span};
+use colored::{ColoredString, Colorize};
+use std::io::{self, Write};
+use std::process::exit;
+use std::time::{Duration, Instant};
 
-/// Print an error to the command line.
-pub fn error<S: AsRef<str>>(msg: S, source: &str, span: Option<Span>) {
-    println!("{} | {}\n", "ERROR".red().bold(), msg.as_ref());
+/// Lines longer than this are cut down to a window around the error before being printed.
+const MAX_LINE_WIDTH: usize = 120;
+
+/// How many characters before the error are kept when a line is cut down.
+const LINE_CONTEXT: usize = 40;
+
+/// Tabs in a printed line are expanded to spaces, up to the next multiple of this width.
+const TAB_WIDTH: usize = 4;
+
+/// Print an error to standard error.
+pub fn error<S: AsRef<str>>(msg: S, source: &SourceMap, span: Option<Span>) {
+    error_to(&mut io::stderr(), msg, source, span);
+}
+
+/// Write an error to the given stream.
+pub fn error_to<S: AsRef<str>>(
+    out: &mut dyn Write,
+    msg: S,
+    source: &SourceMap,
+    span: Option<Span>,
+) {
+    let _ = report(out, "ERROR".red().bold(), msg.as_ref(), source, span);
+}
+
+/// Print a warning to standard error.
+pub fn warning<S: AsRef<str>>(msg: S, source: &SourceMap, span: Option<Span>) {
+    warning_to(&mut io::stderr(), msg, source, span);
+}
+
+/// Write a warning to the given stream.
+pub fn warning_to<S: AsRef<str>>(
+    out: &mut dyn Write,
+    msg: S,
+    source: &SourceMap,
+    span: Option<Span>,
+) {
+    let _ = report(out, "WARNING".yellow().bold(), msg.as_ref(), source, span);
+}
+
+/// Write a message with the given label, followed by the line of source code the span points at. When the program
+/// is made up of several files, the line is labelled with the path of its file as well as its number.
+fn report(
+    out: &mut dyn Write,
+    label: ColoredString,
+    msg: &str,
+    source: &SourceMap,
+    span: Option<Span>,
+) -> io::Result<()> {
+    writeln!(out, "{} | {}\n", label, msg)?;
 
     if let Some(span) = span {
-        let line = source.split('\n').nth(span.start.line - 1).unwrap();
+        let line = source.line(span.start.line).unwrap_or("");
+        let start = char_column(line, span.start.column, source.tab_width());
 
         let length = if span.end.line > span.start.line {
-            line.len() - span.start.column
+            line.chars().count().saturating_sub(start) + 1
         } else {
-            span.end.column - span.start.column + 1
+            char_column(line, span.end.column, source.tab_width()).saturating_sub(start) + 1
+        };
+
+        let (line, column) = snippet(line, start);
+        let length = length
+            .min((line.chars().count() + 1).saturating_sub(column))
+            .max(1);
+        let (line, column, length) = render(&line, column, length);
+
+        let marker = " ".repeat(column - 1) + &"~".repeat(length);
+        let location = match source.locate(span.start.line) {
+            Some((path, number)) if source.len() > 1 => format!("{path}:{number}"),
+            Some((_, number)) => number.to_string(),
+            None => span.start.line.to_string(),
+        };
+
+        let col_num_padding = location.chars().count();
+
+        writeln!(out, "{}:{}", location, line)?;
+        writeln!(
+            out,
+            "{} {}\n",
+            " ".repeat(col_num_padding),
+            marker.red().bold()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Print a diagnostic to standard error, followed by its notes and suggestions.
+pub fn diagnostic(diagnostic: &Diagnostic, source: &SourceMap) {
+    let _ = diagnostic_to(&mut io::stderr(), diagnostic, source);
+}
+
+/// Write a diagnostic to the given stream, followed by its notes and suggestions.
+pub fn diagnostic_to(
+    out: &mut dyn Write,
+    diagnostic: &Diagnostic,
+    source: &SourceMap,
+) -> io::Result<()> {
+    report(
+        out,
+        "ERROR".red().bold(),
+        &diagnostic.reason,
+        source,
+        diagnostic.span.clone(),
+    )?;
+
+    for note in &diagnostic.notes {
+        writeln!(out, "{} | {}", "NOTE".blue().bold(), note)?;
+    }
+
+    for suggestion in &diagnostic.suggestions {
+        writeln!(out, "{} | {}", "HELP".green().bold(), suggestion)?;
+    }
+
+    if !diagnostic.notes.is_empty() || !diagnostic.suggestions.is_empty() {
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// Print a summary of how many errors occured and exit with the given code.
+pub fn abort(count: usize, code: i32) -> ! {
+    if count == 1 {
+        eprintln!(
+            "{} | aborting due to 1 previous error",
+            "ERROR".red().bold()
+        );
+    } else {
+        eprintln!(
+            "{} | aborting due to {count} previous errors",
+            "ERROR".red().bold()
+        );
+    }
+
+    exit(code)
+}
+
+/// Measures how long each phase of the compiler takes.
+pub struct Timer {
+    /// The name and duration of every phase that has finished, in order.
+    phases: Vec<(&'static str, Duration)>,
+
+    /// When the current phase started.
+    start: Instant,
+}
+
+impl Timer {
+    /// Create a timer, starting the first phase.
+    pub fn new() -> Self {
+        Timer {
+            phases: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Finish the current phase, recording how long it took, and start the next one.
+    pub fn lap(&mut self, phase: &'static str) {
+        self.phases.push((phase, self.start.elapsed()));
+        self.start = Instant::now();
+    }
+
+    /// Print a table of every finished phase and the total time spent.
+    pub fn report(&self) {
+        let width = self
+            .phases
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .max()
+            .unwrap_or(0);
+        let total = self
+            .phases
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum::<Duration>();
+
+        for (phase, duration) in &self.phases {
+            eprintln!(
+                "{phase:<width$}  {:>10.3} ms",
+                duration.as_secs_f64() * 1000.0
+            );
+        }
+
+        eprintln!(
+            "{:<width$}  {:>10.3} ms",
+            "total",
+            total.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// Convert a column counted by the lexer, where a tab takes `tab_width` columns, into the position of the character
+/// it falls on. Columns past the end of the line stay past the end by the same amount.
+fn char_column(line: &str, column: usize, tab_width: usize) -> usize {
+    let mut current = 1;
+
+    for (i, c) in line.chars().enumerate() {
+        if current >= column {
+            return i + 1;
+        }
+
+        current += match c {
+            '\t' => tab_width,
+            _ => 1,
         };
+    }
+
+    line.chars().count() + 1 + column.saturating_sub(current)
+}
+
+/// Cut a very long line down to a window starting a little before `column`, returning the window and the
+/// position of `column` within it.
+fn snippet(line: &str, column: usize) -> (String, usize) {
+    if line.chars().count() <= MAX_LINE_WIDTH {
+        return (line.to_owned(), column);
+    }
+
+    let skip = column.saturating_sub(LINE_CONTEXT + 1);
+    let window = line.chars().skip(skip).take(MAX_LINE_WIDTH).collect();
+
+    (window, column - skip)
+}
+
+/// Make every character of a line visible, expanding tabs to spaces and escaping other control characters (so a
+/// bell becomes `\x07`). The column and length of the marked region are adjusted to match the rendered line.
+fn render(line: &str, column: usize, length: usize) -> (String, usize, usize) {
+    let mut rendered = String::new();
+    let mut start = None;
+    let mut end = None;
+
+    for (i, c) in line.chars().enumerate() {
+        if i + 1 == column {
+            start = Some(rendered.chars().count() + 1);
+        }
+
+        match c {
+            '\t' => {
+                let width = TAB_WIDTH - rendered.chars().count() % TAB_WIDTH;
+                rendered.push_str(&" ".repeat(width));
+            }
+            c if c.is_control() => rendered.push_str(&format!("\\x{:02x}", c as u32)),
+            c => rendered.push(c),
+        }
+
+        if i + 1 == column + length - 1 {
+            end = Some(rendered.chars().count());
+        }
+    }
+
+    // The marker may sit just past the end of the line (for example, at a missing semicolon)
+    let count = rendered.chars().count();
+    let start = start.unwrap_or(count + 1);
+    let end = end.unwrap_or(count + 1).max(start);
+
+    (rendered, start, end - start + 1)
+}
+
+/// Compute the edit distance between two strings, counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters as a single edit each.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+
+    // `distances[i][j]` is the distance between the first `i` characters of `a` and the first `j` of `b`
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            distances[i][j] = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
 
-        let marker = " ".repeat(span.start.column - 1) + &"~".repeat(length);
-        let col_num_padding = span.start.line.to_string().len();
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::Location;
+
+    #[test]
+    fn notes_and_suggestions_are_rendered_beneath_the_span() {
+        let mut source = SourceMap::new();
+        source.add("main.scoot", "fn main() -> i23 { 0 }");
+
+        let diagnostic = Diagnostic::error("Unknown type 'i23'")
+            .with_span(Span::new(Location::new(1, 14), Location::new(1, 16)))
+            .with_note("types are named after their size")
+            .with_suggestion("did you mean 'i32'?");
+
+        let mut out = Vec::new();
+        diagnostic_to(&mut out, &diagnostic, &source).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let snippet = out.find("1:fn main() -> i23 { 0 }").unwrap();
+        let note = out.find("NOTE").unwrap();
+        let help = out.find("HELP").unwrap();
+
+        assert!(snippet < note && note < help);
+        assert!(out.contains(" | types are named after their size\n"));
+        assert!(out.contains(" | did you mean 'i32'?\n"));
+    }
+
+    /// Write an error on the first line of `text` and return the marker line beneath the snippet.
+    fn marker(source: &mut SourceMap, text: &str, span: Span) -> String {
+        source.add("main.scoot", text);
+
+        let mut out = Vec::new();
+        error_to(
+            &mut out,
+            "Cannot find 'y' in this scope",
+            source,
+            Some(span),
+        );
+        let out = String::from_utf8(out).unwrap();
+
+        out.lines()
+            .find(|line| line.contains('~'))
+            .expect("the error should have a marker")
+            .to_owned()
+    }
+
+    #[test]
+    fn markers_line_up_with_tokens_after_a_tab_of_any_width() {
+        // `y` is the tenth character, which is column 13 when the lexer counts the tab as four columns
+        let spans = [
+            (SourceMap::new(), Location::new(1, 10)),
+            (SourceMap::new().with_tab_width(4), Location::new(1, 13)),
+        ];
+
+        for (mut source, location) in spans {
+            let span = Span::new(location.clone(), location);
+            let marker = marker(&mut source, "\tlet x = y;", span);
+
+            // The tab is printed as four spaces, so the marker is under `y` after the two columns of line number
+            let indent = marker.chars().take_while(|c| *c == ' ').count();
+            assert_eq!(indent, 2 + 12);
+        }
+    }
+
+    #[test]
+    fn tabs_and_control_characters_are_rendered_visibly() {
+        // A tab expands to the next multiple of four columns, moving the marked `a` along with it
+        assert_eq!(render("\ta", 2, 1), (String::from("    a"), 5, 1));
+        assert_eq!(render("ab\tc", 3, 2), (String::from("ab  c"), 3, 3));
+
+        // Other control characters are escaped, and a marker on one covers the whole escape
+        assert_eq!(render("a\x07b", 2, 1), (String::from("a\\x07b"), 2, 4));
+        assert_eq!(render("a\x07b", 3, 1), (String::from("a\\x07b"), 6, 1));
+    }
+
+    #[test]
+    fn long_lines_are_cut_down_around_the_column() {
+        let line = "x".repeat(300);
+        let (window, column) = snippet(&line, 150);
+
+        assert_eq!(window.chars().count(), MAX_LINE_WIDTH);
+        assert_eq!(column, LINE_CONTEXT + 1);
+        assert_eq!(snippet("short", 3), (String::from("short"), 3));
+    }
 
-        println!("{}:{}", span.start.line, line);
-        println!("{} {}\n", " ".repeat(col_num_padding), marker.red().bold());
+    #[test]
+    fn a_span_ending_before_it_starts_still_gets_a_marker() {
+        let mut source = SourceMap::new();
+        let span = Span::new(Location::new(1, 5), Location::new(1, 2));
+        assert_eq!(marker(&mut source, "let x = y;", span).trim(), "~");
     }
 }
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Write a program to a temporary file and compile it, without colors so the output can be matched as plain text.
+fn compile(name: &str, src: &str, args: &[&str]) -> Output {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("scooter-{}-{name}.scoot", std::process::id()));
+    fs::write(&path, src).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_scooter"))
+        .arg("--no-color")
+        .arg("--source")
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    let _ = fs::remove_file(&path);
+    output
+}
+
+#[test]
+fn type_errors_exit_with_the_semantic_error_code() {
+    let output = compile("typeck", "fn main() -> i32 { let x: i32 = true; x }", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stderr.contains("aborting due to 1 previous error"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn syntax_errors_exit_with_the_syntax_error_code() {
+    let output = compile("syntax", "fn main() -> i32 { 1 + }\nfn f( {}", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("aborting due to 2 previous errors"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn running_a_program_exits_with_the_value_of_main() {
+    let output = compile("run", "fn main() -> i32 { 40 + 2 }", &["--run"]);
+    assert_eq!(output.status.code(), Some(42));
+}
+
+#[test]
+fn failing_tests_are_reported_and_exit_with_the_runtime_error_code() {
+    let src = "
+        @test
+        fn passes() -> () { assert(1 + 1 == 2); }
+
+        @test
+        fn fails() -> () { assert(1 + 1 == 3); }
+
+        fn main() -> i32 { 0 }
+    ";
+
+    let output = compile("test", src, &["--test"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stdout.contains("test passes ... ok"), "{stdout}");
+    assert!(stdout.contains("test fails ... FAILED"), "{stdout}");
+    assert!(stdout.contains("1 passed, 1 failed"), "{stdout}");
+}